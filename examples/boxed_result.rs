@@ -15,6 +15,7 @@ struct FooArtifact;
 struct BazArtifact;
 
 #[derive(Debug)]
+#[allow(dead_code)] // Fields are only shown via `Debug`
 struct BarArtifact {
 	foo_artifact: Rc<FooArtifact>,
 	baz_artifact: Rc<BazArtifact>
@@ -49,10 +49,18 @@
 //!
 
 
+use std::any::Any;
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::PoisonError;
 
 use cfg_if::cfg_if;
 
@@ -61,6 +69,7 @@ use crate::BuilderId;
 use crate::Can;
 use crate::CanBuilder;
 use crate::CanSized;
+use crate::CanStrong;
 use crate::Never;
 
 
@@ -77,7 +86,9 @@ use crate::Never;
 /// [`BlueprintUnsized`]: struct.BlueprintUnsized.html
 ///
 pub trait Promise: Debug + 'static {
+	/// The type of the inner builder.
 	type Builder: ?Sized + 'static + Debug;
+	/// The can type used to store the inner builder.
 	type BCan: Can<Self::Builder>;
 
 	/// Get the unique id of the inner builder.
@@ -94,7 +105,7 @@ pub trait Promise: Debug + 'static {
 	/// methods, as a Promise is supposed to be opaque, but this
 	/// accessor is required for this library to work.
 	///
-	fn builder(&self) -> BuilderAccessor<Self::Builder>;
+	fn builder(&self) -> BuilderAccessor<'_, Self::Builder>;
 
 	/// Get the inner builder in a opaque can.
 	///
@@ -103,6 +114,24 @@ pub trait Promise: Debug + 'static {
 	/// accessor is required for this library to work.
 	///
 	fn canned(&self) -> CannedAccessor<Self::BCan>;
+
+	/// Recreates the promise from its canned builder, as returned by
+	/// `canned`.
+	///
+	/// This allows the `Cache` to rebuild an artifact without being given
+	/// its promise, as done by its early cutoff. The default implementation
+	/// returns `None`, i.e. this is not supported.
+	///
+	/// Notice: this function deliberately takes an opaque type with no
+	/// methods, as a Promise is supposed to be opaque, but this
+	/// accessor is required for this library to work.
+	///
+	fn from_canned(_canned: CannedAccessor<Self::BCan>) -> Option<Self>
+			where
+				Self: Sized {
+
+		None
+	}
 }
 
 /// Opaque builder accessor, used internally.
@@ -147,6 +176,83 @@ impl<B, BCan: CanSized<B>> Blueprint<B, BCan> {
 	}
 }
 
+impl<B, BCan> Blueprint<B, BCan>
+		where
+			B: Hash + Eq + 'static,
+			BCan: CanSized<B> + CanStrong,
+			BCan::Bin: AsRef<B> + Clone, {
+
+	/// Returns a `Blueprint` for the given sized Builder, sharing the
+	/// Builder with any existing `Blueprint` of an equal Builder.
+	///
+	/// Unlike `new`, which always creates a new Builder with its own id,
+	/// structurally equal Builders passed to this function share one id and
+	/// thus one Artifact in the `Cache`, as if the `Blueprint` had been
+	/// cloned.
+	///
+	/// The interned Builders are kept per thread and only weakly, i.e. a
+	/// Builder is released once all of its `Blueprint`s are dropped, and an
+	/// equal Builder interned afterwards gets a new id. The released
+	/// Builders of any type are removed from the interner by the next call
+	/// of this function on the same thread. Notice, that equal
+	/// Builders interned by different threads get different ids, see
+	/// [`interned_sync`] for Builders shared between threads.
+	///
+	/// [`interned_sync`]: struct.Blueprint.html#method.interned_sync
+	///
+	pub fn interned(builder: B) -> Self {
+		let mut hasher = DefaultHasher::new();
+		builder.hash(&mut hasher);
+		let hash = hasher.finish();
+
+		let bin = INTERNER.with(|interner| {
+			intern::<Self, B, BCan, _>(
+				&mut interner.borrow_mut(),
+				|| Box::new(Interned::<BCan>::new()),
+				hash,
+				builder,
+			)
+		});
+
+		Self::new_binned(bin)
+	}
+
+	/// Returns a `Blueprint` for the given sized Builder, sharing the
+	/// Builder with any existing `Blueprint` of an equal Builder of any
+	/// thread.
+	///
+	/// This is the thread-safe variant of [`interned`], intended for the
+	/// `Send + Sync` Cans of the [`arc`] module e.g. along with a
+	/// [`SyncCache`]. Unlike `interned`, equal Builders get the same id
+	/// regardless of the thread interning them, at the cost of a global lock.
+	///
+	/// [`interned`]: struct.Blueprint.html#method.interned
+	/// [`arc`]: ../arc/index.html
+	/// [`SyncCache`]: ../cache/struct.SyncCache.html
+	///
+	pub fn interned_sync(builder: B) -> Self
+			where
+				BCan::CanWeak: Send {
+
+		let mut hasher = DefaultHasher::new();
+		builder.hash(&mut hasher);
+		let hash = hasher.finish();
+
+		let mut interner = SYNC_INTERNER.get_or_init(Default::default)
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner);
+
+		let bin = intern::<Self, B, BCan, _>(
+			&mut interner,
+			|| Box::new(Interned::<BCan>::new()),
+			hash,
+			builder,
+		);
+
+		Self::new_binned(bin)
+	}
+}
+
 impl<B, BCan: Can<B>> Blueprint<B, BCan> {
 	/// Create a new `Blueprint` for the given binned Builder.
 	///
@@ -164,8 +270,9 @@ impl<B, BCan: Can<B>> Blueprint<B, BCan> {
 	/// for comparing with other pointers but dereferencing it can never be
 	/// considered safe.
 	///
+	#[cfg(feature = "unsized")]
 	pub(crate) fn builder_ptr(&self) -> *const () {
-		BCan::bin_as_ptr(&self.builder) as *const ()
+		BCan::bin_as_ptr(&self.builder)
 	}
 }
 
@@ -194,7 +301,7 @@ impl<B, BCan: CanSized<B>> Promise for Blueprint<B, BCan>
 		self.id()
 	}
 
-	fn builder(&self) -> BuilderAccessor<B> {
+	fn builder(&self) -> BuilderAccessor<'_, B> {
 		BuilderAccessor {
 			builder: self.builder.as_ref(),
 		}
@@ -205,6 +312,10 @@ impl<B, BCan: CanSized<B>> Promise for Blueprint<B, BCan>
 			can: BCan::from_bin(self.builder.clone()),
 		}
 	}
+
+	fn from_canned(canned: CannedAccessor<BCan>) -> Option<Self> {
+		canned.can.downcast_can().map(Self::new_binned)
+	}
 }
 
 cfg_if! {
@@ -238,6 +349,114 @@ cfg_if! {
 	}
 }
 
+thread_local! {
+	/// Maps the type of `Blueprint` to its interned Builders, see
+	/// `Blueprint::interned`.
+	///
+	static INTERNER: RefCell<HashMap<TypeId, Box<dyn AnyInterned>>> = RefCell::new(HashMap::new());
+}
+
+/// Maps the type of `Blueprint` to its interned Builders shared by all
+/// threads, see `Blueprint::interned_sync`.
+///
+static SYNC_INTERNER: OnceLock<Mutex<HashMap<TypeId, Box<dyn AnyInterned + Send>>>> = OnceLock::new();
+
+/// Returns the interned Builder equal to the given one with the given hash
+/// from the given interner, interning the given one if there is none.
+///
+/// The Builders dropped since are removed from the interner beforehand,
+/// including the types which have no Builders left, so that the weak Cans
+/// do not keep their allocations.
+///
+fn intern<BP, B, BCan, I>(
+		interner: &mut HashMap<TypeId, Box<I>>,
+		new: impl FnOnce() -> Box<I>,
+		hash: u64,
+		builder: B,
+	) -> BCan::Bin
+		where
+			BP: 'static,
+			B: Eq,
+			BCan: CanSized<B> + CanStrong,
+			BCan::Bin: AsRef<B> + Clone,
+			I: ?Sized + AnyInterned {
+
+	interner.retain(|_, interned| interned.sweep());
+
+	interner.entry(TypeId::of::<BP>())
+		.or_insert_with(new)
+		.as_any_mut()
+		.downcast_mut::<Interned<BCan>>()
+		.expect("Interned builders of mismatching type")
+		.intern(hash, builder)
+}
+
+/// The interned Builders of any type, as kept by the interners.
+///
+trait AnyInterned: Any {
+	/// Removes the dropped Builders, returning whether any are left.
+	///
+	fn sweep(&mut self) -> bool;
+
+	/// Returns these interned Builders for downcasting.
+	///
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// The weakly kept interned Builders of one type, grouped by their hash.
+///
+struct Interned<BCan: CanStrong> {
+	buckets: HashMap<u64, Vec<BCan::CanWeak>>,
+}
+
+impl<BCan: CanStrong> Interned<BCan> {
+	/// Creates a new empty set of interned Builders.
+	///
+	fn new() -> Self {
+		Interned {
+			buckets: HashMap::new(),
+		}
+	}
+
+	/// Returns the interned Builder equal to the given one with the given
+	/// hash, interning the given one if there is none.
+	///
+	fn intern<B>(&mut self, hash: u64, builder: B) -> BCan::Bin
+			where
+				B: Eq,
+				BCan: CanSized<B>,
+				BCan::Bin: AsRef<B> + Clone {
+
+		let bucket = self.buckets.entry(hash).or_default();
+
+		let existing = bucket.iter()
+			.filter_map(|weak| BCan::upgrade_from_weak(weak)?.downcast_can())
+			.find(|bin| *bin.as_ref() == builder);
+
+		existing.unwrap_or_else(|| {
+			let bin = BCan::into_bin(builder);
+			bucket.push(BCan::from_bin(bin.clone()).downgrade());
+
+			bin
+		})
+	}
+}
+
+impl<BCan: CanStrong + 'static> AnyInterned for Interned<BCan> {
+	fn sweep(&mut self) -> bool {
+		self.buckets.retain(|_, bucket| {
+			bucket.retain(|weak| BCan::upgrade_from_weak(weak).is_some());
+			!bucket.is_empty()
+		});
+
+		!self.buckets.is_empty()
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
 impl<B, BCan: Can<B>> Clone for Blueprint<B, BCan> where BCan::Bin: Clone {
 	fn clone(&self) -> Self {
 		Blueprint {
@@ -466,7 +685,7 @@ cfg_if! {
 				self.id()
 			}
 
-			fn builder(&self) -> BuilderAccessor<B> {
+			fn builder(&self) -> BuilderAccessor<'_, B> {
 				BuilderAccessor {
 					builder: self.builder.as_ref(),
 				}
@@ -559,6 +778,7 @@ cfg_if! {
 
 
 
+#[allow(dead_code)]
 type BuilderDyn<ArtCan, BCan, Artifact, Err, DynState> = dyn Builder<ArtCan, BCan, Artifact=Artifact, Err=Err, DynState=DynState>;
 
 /// Wraps a Builder as a blueprint for its artifact from the `Cache` allowing
@@ -671,6 +891,7 @@ impl<ArtCan, BCan, Art, Err, DynSt> BlueprintDyn<ArtCan, BCan, Art, Err, DynSt>
 	/// for comparing with other pointers but dereferencing it can never be
 	/// considered safe.
 	///
+	#[allow(dead_code)]
 	pub(crate) fn builder_ptr(&self) -> *const () {
 		BCan::can_as_ptr(&self.builder_canned) as *const ()
 	}
@@ -714,7 +935,7 @@ impl<ArtCan, BCan, Art, Err, DynSt> Promise for BlueprintDyn<ArtCan, BCan, Art,
 		self.id()
 	}
 
-	fn builder(&self) -> BuilderAccessor<dyn Builder<ArtCan, BCan, Artifact=Art, Err=Err, DynState=DynSt>> {
+	fn builder(&self) -> BuilderAccessor<'_, dyn Builder<ArtCan, BCan, Artifact=Art, Err=Err, DynState=DynSt>> {
 		BuilderAccessor {
 			builder: self.builder.as_ref(),
 		}
@@ -796,3 +1017,38 @@ impl<ArtCan, BCan, Art, Err, DynSt, B> From<Blueprint<B, BCan>> for BlueprintDyn
 	}
 }
 
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::rc::Rc;
+
+	#[derive(Debug, Hash, PartialEq, Eq)]
+	struct First(u32);
+
+	#[derive(Debug, Hash, PartialEq, Eq)]
+	struct Second(u32);
+
+	fn is_interned<BP: 'static>() -> bool {
+		INTERNER.with(|interner| {
+			interner.borrow().contains_key(&TypeId::of::<BP>())
+		})
+	}
+
+	#[test]
+	fn test_interned_release() {
+		type FirstBp = Blueprint<First, Rc<dyn Any>>;
+
+		let first = FirstBp::interned(First(1));
+		let again = FirstBp::interned(First(1));
+		assert_eq!(first.id(), again.id());
+		assert!(is_interned::<FirstBp>());
+
+		drop(first);
+		drop(again);
+
+		// Interning any Builder removes the released ones of all types
+		let _second = Blueprint::<Second, Rc<dyn Any>>::interned(Second(1));
+		assert!(!is_interned::<FirstBp>());
+	}
+}
@@ -14,6 +14,9 @@ use cfg_if::cfg_if;
 use crate::Doctor;
 
 use crate::BlueprintDyn;
+use crate::BoxFuture;
+use crate::BuildPolicy;
+use crate::DefaultPolicy;
 use crate::Never;
 
 
@@ -56,8 +59,23 @@ cfg_if! {
 pub type DynamicBlueprint<Artifact, Err=Never, DynState=()> =
 	BlueprintDyn<CanType, BuilderCan, Artifact, Err, DynState>;
 
+/// A `ConstBuilder` using the types of this module.
+///
 pub type ConstBuilder<T> = crate::utils::ConstBuilder<CanType, BuilderCan, BinType<T>, T>;
+/// A `ConfigurableBuilder` using the types of this module.
+///
 pub type ConfigurableBuilder<T> = crate::utils::ConfigurableBuilder<CanType, BuilderCan, T>;
+/// A `FileInputBuilder` using the types of this module.
+///
+pub type FileInputBuilder = crate::utils::FileInputBuilder<CanType, BuilderCan>;
+
+cfg_if! {
+	if #[cfg(feature = "persistence")] {
+		/// Describes how the artifacts of a Builder are stored, using `Box` for artifacts.
+		///
+		pub type Persistence<T, S = ()> = crate::persistence::Persistence<CanType, T, S>;
+	}
+}
 
 /// Allows to resolve any `Blueprint` into its artifact. Usable within a
 /// builders `build` function.
@@ -153,6 +171,16 @@ pub trait Builder: Debug + 'static {
 	/// Return an inital dynamic state for this builder.
 	///
 	fn init_dyn_state(&self) -> Self::DynState;
+
+	/// Returns the policy of this builder.
+	///
+	/// See [`crate::Builder::policy`] for details.
+	///
+	/// [`crate::Builder::policy`]: ../trait.Builder.html#method.policy
+	///
+	fn policy(&self) -> &dyn BuildPolicy<CanType, Self::Artifact, Self::DynState, Self::Err> {
+		&DefaultPolicy
+	}
 }
 
 impl<B: ?Sized + Builder> crate::Builder<CanType, crate::rc::CanType> for B {
@@ -169,6 +197,67 @@ impl<B: ?Sized + Builder> crate::Builder<CanType, crate::rc::CanType> for B {
 	fn init_dyn_state(&self) -> Self::DynState {
 		self.init_dyn_state()
 	}
+
+	fn policy(&self) -> &dyn BuildPolicy<CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self.policy()
+	}
+}
+
+
+/// An async Builder using `Rc` for `Blueprint` and `Box` for artifacts.
+///
+pub trait AsyncBuilder: Debug + 'static {
+	/// The artifact type as produced by this builder.
+	///
+	type Artifact : Debug + 'static;
+
+	/// Type of the dynamic state of this builder.
+	///
+	type DynState : Debug + 'static;
+
+	/// Error type returned by this Builder in case of failure to produce an
+	/// Artifact.
+	type Err : Debug + 'static;
+
+	/// Produces a future of an artifact using the given `Resolver` for
+	/// resolving dependencies.
+	///
+	fn build<'a>(&'a self, resolver: &'a mut Resolver<'_, Self::DynState>)
+		-> BoxFuture<'a, Result<BinType<Self::Artifact>, Self::Err>>;
+
+	/// Return an inital dynamic state for this builder.
+	///
+	fn init_dyn_state(&self) -> Self::DynState;
+
+	/// Returns the policy of this builder.
+	///
+	/// See [`crate::Builder::policy`] for details.
+	///
+	/// [`crate::Builder::policy`]: ../trait.Builder.html#method.policy
+	///
+	fn policy(&self) -> &dyn BuildPolicy<CanType, Self::Artifact, Self::DynState, Self::Err> {
+		&DefaultPolicy
+	}
+}
+
+impl<B: ?Sized + AsyncBuilder> crate::AsyncBuilder<CanType, crate::rc::CanType> for B {
+	type Artifact = B::Artifact;
+	type DynState = B::DynState;
+	type Err = B::Err;
+
+	fn build<'a>(&'a self, cache: &'a mut Resolver<'_, Self::DynState>)
+			-> BoxFuture<'a, Result<<CanType as crate::canning::Can<Self::Artifact>>::Bin, Self::Err>> {
+
+		self.build(cache)
+	}
+
+	fn init_dyn_state(&self) -> Self::DynState {
+		self.init_dyn_state()
+	}
+
+	fn policy(&self) -> &dyn BuildPolicy<CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self.policy()
+	}
 }
 
 
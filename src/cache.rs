@@ -26,9 +26,13 @@
 use std::fmt;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
 
 use cfg_if::cfg_if;
 
+use crate::canning::CanBase;
 #[cfg(feature = "mut_box")]
 use crate::canning::CanRefMut;
 
@@ -40,12 +44,60 @@ use crate::Can;
 use crate::Promise;
 
 use crate::Builder;
+use crate::AsyncBuilder;
 
 mod internal;
+mod hooks;
+mod graph;
+mod eviction;
+mod clock;
+mod cutoff;
+mod snapshot;
+mod warm;
+mod cancel;
+mod version;
+mod listener;
+mod layered;
+mod input;
 
 use internal::BuilderEntry;
 use internal::RawCache;
 
+mod error;
+
+pub use error::BuilderInfo;
+pub use error::CancelledError;
+pub use error::CycleError;
+pub use error::ContextError;
+pub use error::ResolveError;
+pub use error::TimeoutError;
+pub(crate) use error::Interrupt;
+
+mod sync;
+
+pub use sync::SyncCache;
+
+pub use clock::Clock;
+pub use clock::SystemClock;
+pub use clock::ManualClock;
+
+pub use snapshot::Snapshot;
+pub use snapshot::SnapshotEntry;
+
+pub use warm::Warm;
+pub use warm::WarmReport;
+
+pub use cancel::CancellationToken;
+
+pub use version::ArtifactVersion;
+
+pub use listener::DropReason;
+pub use listener::ListenerId;
+
+pub use layered::ParentCache;
+
+pub use input::FileStamp;
+
 
 
 /// Structure for building, caching and dependency tracking of artifacts.
@@ -92,7 +144,7 @@ use internal::RawCache;
 /// Each `get*` and `lookup*`, come in 4 different variants depending offering
 /// different access semantics to the artifact.
 /// - `Bin<T>` variant (`get` & `lookup`) returns a clone of the respective Bin
-///    of the Artifact that is e.g. an `Rc<T>` when using `Rc<dyn Any>` as Can.
+///   of the Artifact that is e.g. an `Rc<T>` when using `Rc<dyn Any>` as Can.
 /// - `&T` variant (`get_ref` & `lookup_ref`) returns a reference to artifact
 ///   within this `Cache`.
 /// - `&mut T` variant (`get_mut` & `lookup_mut`) returns a mutable reference
@@ -109,6 +161,10 @@ use internal::RawCache;
 /// _`Bin<T>` means `<ArtCan as Can<T>>::Bin` \
 /// e.g. `Rc<T>` for types in `rc` module_
 ///
+/// The `get`, `get_ref`, `get_mut` and `get_cloned` accessors as well as
+/// `get_async` also come as `try_get*` variant, which reports a dependency
+/// cycle as error instead of panicking.
+///
 ///
 ///
 /// ## Caching Duration
@@ -154,6 +210,13 @@ use internal::RawCache;
 /// To clear all Artifacts there is the [`clear_artifacts`] method. And to
 /// purge all Builders from the `Cache` there is the [`clear_all`] method.
 ///
+/// The number of kept Artifacts can be limited via [`set_max_artifacts`] and
+/// [`set_max_size`], then the least recently used Artifacts are evicted.
+/// Additionally, Builders may limit the lifetime of their Artifacts via
+/// [`BuildPolicy::time_to_live`], expired Artifacts are invalidated when the
+/// `Cache` is accessed the next time. Either way, the Artifacts are rebuilt
+/// on demand.
+///
 /// [`invalidate`]: struct.Cache.html#method.invalidate
 /// [`purge`]: struct.Cache.html#method.purge
 /// [`garbage_collection`]: struct.Cache.html#method.garbage_collection
@@ -161,6 +224,9 @@ use internal::RawCache;
 /// [`clear_all`]: struct.Cache.html#method.clear_all
 /// [`is_builder_known`]: struct.Cache.html#method.is_builder_known
 /// [`number_of_known_builders`]: struct.Cache.html#method.number_of_known_builders
+/// [`set_max_artifacts`]: struct.Cache.html#method.set_max_artifacts
+/// [`set_max_size`]: struct.Cache.html#method.set_max_size
+/// [`BuildPolicy::time_to_live`]: ../trait.BuildPolicy.html#method.time_to_live
 ///
 pub struct Cache<
 	ArtCan,
@@ -228,6 +294,43 @@ impl<ArtCan, BCan> CacheOwned<ArtCan, BCan>
 			}
 		}
 	}
+
+	/// Creates a new empty cache layered upon the given parent cache.
+	///
+	/// Whenever an Artifact is not cached, the parent is consulted before
+	/// building it. New Artifacts and their dependencies are only recorded
+	/// in the new `Cache`, the parent is never modified. This allows to
+	/// share a set of long-lived Artifacts between many short-lived caches.
+	///
+	/// Invalidating a Builder in the new `Cache` stops it from using the
+	/// Artifacts of that Builder and of its dependents in the parent. Such
+	/// Artifacts are rebuilt by the new `Cache` instead. Notice, that the
+	/// new `Cache` has its own dynamic states, the ones of the parent are
+	/// not copied.
+	///
+	/// The parent is either a `Cache` shared by an `Rc`, or a [`SyncCache`]
+	/// shared by an `Arc`, which can be shared with caches of other threads
+	/// too. The Artifacts taken from the parent are checked on each access
+	/// of the new `Cache`. Once they expired or have been invalidated in the
+	/// parent, they are dropped including their dependents, reported as
+	/// [`DropReason::Outdated`], and the parent is consulted anew.
+	///
+	/// [`SyncCache`]: struct.SyncCache.html
+	/// [`DropReason::Outdated`]: enum.DropReason.html#variant.Outdated
+	///
+	pub fn with_parent<P>(parent: P) -> Self
+			where
+				ArtCan: CanBase + Debug + 'static,
+				BCan: 'static,
+				P: ParentCache<ArtCan, BCan> + 'static {
+
+		let mut cache = Self::new();
+
+		let inner: &mut RawCache<ArtCan, BCan> = &mut cache.inner;
+		inner.set_upstream(Rc::new(layered::Parent::new(parent)));
+
+		cache
+	}
 }
 
 cfg_if! {
@@ -503,8 +606,15 @@ cfg_if! {
 	/// For an overview of different accessor methods see [Artifact Accessors]
 	/// section of `Cache`.
 	///
+	/// # Panics
+	///
+	/// Panics if there is a dependency cycle. Use [`try_get`] to get a
+	/// [`CycleError`] instead.
+	///
 	/// [Artifact Accessors]: struct.Cache.html#artifact-accessors
 	/// [`get_ref`]: struct.Cache.html#method.get_ref
+	/// [`try_get`]: struct.Cache.html#method.try_get
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn get<AP, B: ?Sized>(
 			&mut self,
@@ -520,6 +630,204 @@ cfg_if! {
 		self.inner.get(promise)
 	}
 
+	/// Tries to get the Artifact in its Bin.
+	///
+	/// This is the same as [`get`], except that a dependency cycle is
+	/// reported as [`ResolveError::Cycle`] instead of panicking. An error of
+	/// the Builder is returned as [`ResolveError::Failed`].
+	///
+	/// The cycle might be detected while building any of the dependencies
+	/// of the Artifact. If the Builders on the way resolve via the
+	/// `try_resolve*` methods of the `Resolver`, it is returned to them like
+	/// any other error. Otherwise, all builds up to the nearest Builder which
+	/// does are aborted, see [`Resolver`]. Notice, only the latter requires
+	/// the `unwind` panic strategy, see the [crate documentation].
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`Resolver`]: struct.Resolver.html
+	/// [crate documentation]: ../index.html#dependency-cycles
+	///
+	pub fn try_get<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.try_get(promise)
+	}
+
+	/// Gets the Artifact in its Bin along with its version.
+	///
+	/// This is the same as [`get`], except that the [`ArtifactVersion`] of
+	/// the Artifact is returned too. It can be passed to [`is_current`] later
+	/// on, to cheaply test whether the Artifact is still the current one.
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`ArtifactVersion`]: struct.ArtifactVersion.html
+	/// [`is_current`]: struct.Cache.html#method.is_current
+	///
+	pub fn get_versioned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<(ArtCan::Bin, ArtifactVersion), B::Err>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.get_versioned(promise)
+	}
+
+	/// Tests whether the given version is the one of the current Artifact of
+	/// the given Builder.
+	///
+	/// Returns `false`, if the Artifact has been invalidated, expired,
+	/// evicted or rebuilt since the version was obtained via
+	/// [`get_versioned`]. This method never builds anything, thus it is
+	/// cheap to poll for changes.
+	///
+	/// Notice, with the [early cutoff], a dirty Artifact which turns out to
+	/// be still valid keeps its version, but it is not current until it has
+	/// been requested again.
+	///
+	/// [`get_versioned`]: struct.Cache.html#method.get_versioned
+	/// [early cutoff]: struct.Cache.html#method.set_early_cutoff
+	///
+	pub fn is_current<AP: ?Sized>(
+			&self,
+			promise: &AP,
+			version: ArtifactVersion,
+		) -> bool
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.is_current(promise, version)
+	}
+
+	/// Gets the Artifact in its Bin, allowing to cancel the build.
+	///
+	/// This is the same as [`get`], except that the given token can be
+	/// checked by the Builders via [`Resolver::is_cancelled`] while building
+	/// the Artifact, including the Builders of all its dependencies which
+	/// are built on the way.
+	///
+	/// Cancellation is cooperative: the Builders are expected to return an
+	/// `Err` when they observe it, thus no Artifact is recorded for them.
+	/// Errors returned while the token is cancelled are never cached, see
+	/// [`BuildPolicy::cached_error`]. Dependencies which have been built
+	/// completely are kept in the `Cache`.
+	///
+	/// Once the token has been cancelled, no further build is started, not
+	/// even by a Builder which ignores the cancellation. Instead, a
+	/// [`ResolveError::Cancelled`] is returned, either by this method or to
+	/// the Builder resolving the skipped one via a `try_resolve*` method of
+	/// the `Resolver`. Only a plain `resolve*` method can not return it, thus
+	/// it aborts the builds on the way by unwinding, see the
+	/// [crate documentation]. An error of the Builder is returned as
+	/// [`ResolveError::Failed`], and a dependency cycle as
+	/// [`ResolveError::Cycle`].
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`Resolver::is_cancelled`]: struct.Resolver.html#method.is_cancelled
+	/// [`BuildPolicy::cached_error`]: ../trait.BuildPolicy.html#method.cached_error
+	/// [`ResolveError::Cancelled`]: enum.ResolveError.html#variant.Cancelled
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	/// [crate documentation]: ../index.html#dependency-cycles
+	///
+	pub fn get_cancellable<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			token: &CancellationToken,
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.get_cancellable(promise, token)
+	}
+
+	/// Gets the Artifact in its Bin, skipping builds after the given deadline.
+	///
+	/// This is the same as [`get`], except that no build is started once the
+	/// `Clock` of this `Cache` has reached the `deadline`. If the Artifact is
+	/// not cached by then, a [`ResolveError::Timeout`] is returned instead.
+	/// An error of the Builder is returned as [`ResolveError::Failed`].
+	///
+	/// The deadline applies to the dependencies too. It is checked whenever
+	/// a Builder resolves a dependency, which is not cached. If the deadline
+	/// has passed, the `try_resolve*` methods of the `Resolver` return a
+	/// `ResolveError::Timeout` to the resolving Builder, while the plain
+	/// `resolve*` methods abort the builds on the way by unwinding, see the
+	/// [crate documentation]. Errors returned after the deadline are never
+	/// cached, see [`BuildPolicy::cached_error`].
+	///
+	/// Notice, that the deadline is only checked when a build starts, a
+	/// running build is never interrupted. Thus, the deadline is exceeded by
+	/// the duration of the builds running at the deadline, unless the
+	/// Builders query the time left via [`Resolver::remaining_budget`] and
+	/// give up on their own.
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`ResolveError::Timeout`]: enum.ResolveError.html#variant.Timeout
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`Resolver::remaining_budget`]: struct.Resolver.html#method.remaining_budget
+	/// [`BuildPolicy::cached_error`]: ../trait.BuildPolicy.html#method.cached_error
+	/// [crate documentation]: ../index.html#dependency-cycles
+	///
+	pub fn get_with_deadline<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			deadline: Instant,
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.get_with_deadline(promise, deadline)
+	}
+
+	/// Gets the Artifact in its Bin, skipping builds after the given budget.
+	///
+	/// This is the same as [`get_with_deadline`] with a deadline `budget`
+	/// after the current time of the `Clock` of this `Cache`. Like the
+	/// deadline, the budget is only checked when a build starts, thus it
+	/// does not bound the duration of the running builds.
+	///
+	/// [`get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+	///
+	pub fn get_with_budget<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			budget: Duration,
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let deadline = self.inner.now() + budget;
+		self.inner.get_with_deadline(promise, deadline)
+	}
+
 	/// Gets the Artifact by reference.
 	///
 	/// Returns the Artifact as reference into this `Cache`. The reference is
@@ -543,11 +851,18 @@ cfg_if! {
 	/// For an overview of different accessor methods see [Artifact Accessors]
 	/// section of `Cache`.
 	///
+	/// # Panics
+	///
+	/// Panics if there is a dependency cycle. Use [`try_get_ref`] to get a
+	/// [`CycleError`] instead.
+	///
 	/// [Artifact Accessors]: struct.Cache.html#artifact-accessors
 	/// [`get`]: struct.Cache.html#method.get
 	/// [`get_cloned`]: struct.Cache.html#method.get_cloned
 	/// [`boxed`]: ../boxed/index.html
 	/// [`get_mut`]: struct.Cache.html#method.get_mut
+	/// [`try_get_ref`]: struct.Cache.html#method.try_get_ref
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn get_ref<AP, B: ?Sized>(
 			&mut self,
@@ -562,6 +877,29 @@ cfg_if! {
 		self.inner.get_ref(promise)
 	}
 
+	/// Tries to get the Artifact by reference.
+	///
+	/// This is the same as [`get_ref`], except that a dependency cycle is
+	/// reported as [`ResolveError::Cycle`] instead of panicking, see
+	/// [`try_get`].
+	///
+	/// [`get_ref`]: struct.Cache.html#method.get_ref
+	/// [`try_get`]: struct.Cache.html#method.try_get
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	///
+	pub fn try_get_ref<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&B::Artifact, ResolveError<B::Err>>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.try_get_ref(promise)
+	}
+
 
 cfg_if! {
 	if #[cfg(feature = "mut_box")] {
@@ -587,6 +925,11 @@ cfg_if! {
 		/// For an overview of different accessor methods see [Artifact Accessors]
 		/// section of `Cache`.
 		///
+		/// # Panics
+		///
+		/// Panics if there is a dependency cycle. Use [`try_get_mut`] to get a
+		/// [`CycleError`] instead.
+		///
 		///
 		///
 		/// # Unstable
@@ -602,6 +945,8 @@ cfg_if! {
 		///
 		/// [Artifact Accessors]: struct.Cache.html#artifact-accessors
 		/// [`get_ref`]: struct.Cache.html#method.get_ref
+		/// [`try_get_mut`]: struct.Cache.html#method.try_get_mut
+		/// [`CycleError`]: struct.CycleError.html
 		///
 		#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "mut_box")))]
 		pub fn get_mut<AP, B: ?Sized>(
@@ -616,6 +961,34 @@ cfg_if! {
 
 			self.inner.get_mut(promise)
 		}
+
+		/// Tries to get the Artifact by mutable reference.
+		///
+		/// This is the same as [`get_mut`], except that a dependency cycle is
+		/// reported as [`ResolveError::Cycle`] instead of panicking, see
+		/// [`try_get`].
+		///
+		/// # Unstable
+		///
+		/// Like [`get_mut`], **this method must be considered unstable!**
+		///
+		/// [`get_mut`]: struct.Cache.html#method.get_mut
+		/// [`try_get`]: struct.Cache.html#method.try_get
+		/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+		///
+		#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "mut_box")))]
+		pub fn try_get_mut<AP, B: ?Sized>(
+				&mut self,
+				promise: &AP
+			) -> Result<&mut B::Artifact, ResolveError<B::Err>>
+				where
+					ArtCan: CanRefMut<B::Artifact>,
+					B: Builder<ArtCan, BCan>,
+					BCan: Can<AP::Builder>,
+					AP: Promise<Builder = B, BCan = BCan>  {
+
+			self.inner.try_get_mut(promise)
+		}
 	}
 }
 
@@ -633,8 +1006,15 @@ cfg_if! {
 	/// For an overview of different accessor methods see [Artifact Accessors]
 	/// section of `Cache`.
 	///
+	/// # Panics
+	///
+	/// Panics if there is a dependency cycle. Use [`try_get_cloned`] to get a
+	/// [`CycleError`] instead.
+	///
 	/// [Artifact Accessors]: struct.Cache.html#artifact-accessors
 	/// [`get_ref`]: struct.Cache.html#method.get_ref
+	/// [`try_get_cloned`]: struct.Cache.html#method.try_get_cloned
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn get_cloned<AP, B: ?Sized>(
 			&mut self,
@@ -650,6 +1030,112 @@ cfg_if! {
 		self.inner.get_cloned(promise)
 	}
 
+	/// Tries to get a clone of the Artifact.
+	///
+	/// This is the same as [`get_cloned`], except that a dependency cycle is
+	/// reported as [`ResolveError::Cycle`] instead of panicking, see
+	/// [`try_get`].
+	///
+	/// [`get_cloned`]: struct.Cache.html#method.get_cloned
+	/// [`try_get`]: struct.Cache.html#method.try_get
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	///
+	pub fn try_get_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, ResolveError<B::Err>>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.try_get_cloned(promise)
+	}
+
+	/// Gets the Artifact of an async Builder in its Bin.
+	///
+	/// This is the async counterpart of [`get`] for [`AsyncBuilder`]s. The
+	/// returned future will try to build the Artifact if it is not stored in
+	/// the `Cache`, otherwise it is ready immediately. An `Err` will be
+	/// returned only, if the Artifact was not cached and the Builder returned
+	/// an `Err`.
+	///
+	/// If the returned future is dropped before it completes, the pending
+	/// builds are abandoned and none of their Artifacts is cached.
+	///
+	/// # Panics
+	///
+	/// Panics if there is a dependency cycle. Use [`try_get_async`] to get a
+	/// [`CycleError`] instead.
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`AsyncBuilder`]: ../trait.AsyncBuilder.html
+	/// [`try_get_async`]: struct.Cache.html#method.try_get_async
+	/// [`CycleError`]: struct.CycleError.html
+	///
+	pub async fn get_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, B::Err>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: AsyncBuilder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.get_async(promise).await
+	}
+
+	/// Tries to get the Artifact of an async Builder in its Bin.
+	///
+	/// This is the same as [`get_async`], except that a dependency cycle is
+	/// reported as [`ResolveError::Cycle`] instead of panicking, see
+	/// [`try_get`].
+	///
+	/// [`get_async`]: struct.Cache.html#method.get_async
+	/// [`try_get`]: struct.Cache.html#method.try_get
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	///
+	pub async fn try_get_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: AsyncBuilder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.try_get_async(promise).await
+	}
+
+	/// Gets a clone of the Artifact of an async Builder.
+	///
+	/// This is the async counterpart of [`get_cloned`] for
+	/// [`AsyncBuilder`]s, see [`get_async`] for details.
+	///
+	/// [`get_cloned`]: struct.Cache.html#method.get_cloned
+	/// [`get_async`]: struct.Cache.html#method.get_async
+	/// [`AsyncBuilder`]: ../trait.AsyncBuilder.html
+	///
+	pub async fn get_async_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, B::Err>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: AsyncBuilder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.get_async_cloned(promise).await
+	}
+
 	/// Gets the dynamic state of the given builder, if any.
 	///
 	/// To initialize the dynamic state when it does not exist, use the
@@ -697,9 +1183,13 @@ cfg_if! {
 	/// it didn't exist yet in this `Cache`.
 	///
 	/// As opposed to [`dyn_state`], this method will invalidate the Artifact of
-	/// the given Builder, including all depending Artifacts.
+	/// the given Builder, including all depending Artifacts. To only
+	/// invalidate them on actual changes, use [`update_dyn_state`] or
+	/// [`set_dyn_state`] instead.
 	///
 	/// [`dyn_state`]: struct.Cache.html#method.dyn_state
+	/// [`update_dyn_state`]: struct.Cache.html#method.update_dyn_state
+	/// [`set_dyn_state`]: struct.Cache.html#method.set_dyn_state
 	///
 	pub fn dyn_state_mut<AP, B: ?Sized>(
 			&mut self, promise: &AP
@@ -712,6 +1202,98 @@ cfg_if! {
 		self.inner.dyn_state_mut(promise)
 	}
 
+	/// Updates the dynamic state of the given Builder via the given closure.
+	///
+	/// This method will initialize the dynamic state if
+	/// it didn't exist yet in this `Cache`.
+	///
+	/// The closure returns whether it actually changed the dynamic state.
+	/// Only then the Artifact of the given Builder, including all depending
+	/// Artifacts, are invalidated, as opposed to [`dyn_state_mut`], which
+	/// always invalidates them. Returns the result of the closure.
+	///
+	/// [`dyn_state_mut`]: struct.Cache.html#method.dyn_state_mut
+	///
+	pub fn update_dyn_state<AP, B: ?Sized, F>(
+			&mut self,
+			promise: &AP,
+			update: F,
+		) -> bool
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>,
+				F: FnOnce(&mut B::DynState) -> bool {
+
+		self.inner.update_dyn_state(promise, update)
+	}
+
+	/// Sets the dynamic state of the given Builder, if it differs from the
+	/// current one.
+	///
+	/// Like [`update_dyn_state`], the Artifact of the given Builder,
+	/// including all depending Artifacts, are only invalidated, if the
+	/// dynamic state changed. Returns whether it changed.
+	///
+	/// This behaves the same as [`SyncCache::set_dyn_state`], i.e. a Builder
+	/// without a dynamic state yet compares the given one against its
+	/// initial dynamic state.
+	///
+	/// [`update_dyn_state`]: struct.Cache.html#method.update_dyn_state
+	/// [`SyncCache::set_dyn_state`]: struct.SyncCache.html#method.set_dyn_state
+	///
+	pub fn set_dyn_state<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			dyn_state: B::DynState,
+		) -> bool
+			where
+				B: Builder<ArtCan, BCan>,
+				B::DynState: PartialEq,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.inner.update_dyn_state(promise, |current| {
+			if *current == dyn_state {
+				false
+			} else {
+				*current = dyn_state;
+				true
+			}
+		})
+	}
+
+	/// Creates a copy of this `Cache`, sharing the current Artifacts.
+	///
+	/// The fork shares all Artifacts with this `Cache` via their Bins, e.g.
+	/// `Rc`s, thus forking is cheap. In the fork, dynamic states can be
+	/// changed and Artifacts rebuilt without disturbing this `Cache`, and
+	/// vice versa, e.g. to preview the effect of some changes.
+	///
+	/// The dynamic states are shared too, until either `Cache` changes one
+	/// of them, then that `Cache` gets its own copy via
+	/// [`BuildPolicy::fork_dyn_state`]. This includes the dynamic states of
+	/// Builders which have never been built. If a Builder does not opt in to
+	/// copying its dynamic state, the fork starts over with a new dynamic
+	/// state for that Builder, and drops the Artifacts of it and its
+	/// dependents.
+	///
+	/// Listeners, cached errors, the upstream and the persistence are not
+	/// forked. The fork uses the same `Clock` as this `Cache`.
+	///
+	/// [`BuildPolicy::fork_dyn_state`]: ../trait.BuildPolicy.html#method.fork_dyn_state
+	///
+	pub fn fork(&self) -> CacheOwned<ArtCan, BCan>
+			where
+				ArtCan: Clone,
+				BCan::CanWeak: Clone {
+
+		let mut fork = CacheOwned::new();
+		self.inner.fork_into(&mut fork.inner);
+
+		fork
+	}
+
 	/// Deletes all cached Artifacts in this cache, but keeps dynamic states.
 	///
 	pub fn clear_artifacts(&mut self) {
@@ -776,12 +1358,53 @@ cfg_if! {
 		self.inner.invalidate(promise)
 	}
 
-	/// Invalidates all builders and their dyn state which can not be builded
-	/// any more, because there are no more references to them.
+	/// Registers a listener for the Artifact of the given Builder.
 	///
-	/// This function has the complexity of `O(n)` with `n` being the [number of
-	/// known builders]. Thus this function is not light-weight, but should be
-	/// called regularly at appropriate locations, i.e. where many builders
+	/// The `listener` is called whenever the Artifact of the Builder is
+	/// dropped from this `Cache`, with the [`DropReason`] why. That is when it
+	/// is invalidated directly or because of a dependency, when it expires,
+	/// gets evicted, purged or garbage collected, or when the artifacts are
+	/// cleared. The listener is not called if there is no Artifact, e.g.
+	/// because it has not been built yet or has already been dropped.
+	///
+	/// Notice, with the [early cutoff], a dirty Artifact counts as dropped,
+	/// even if it turns out to be still valid later.
+	///
+	/// The listener stays registered until it is removed via
+	/// [`unsubscribe`], or until the Builder is purged, garbage collected or
+	/// the entire `Cache` is cleared, since then the Builder is forgotten.
+	///
+	/// [`DropReason`]: enum.DropReason.html
+	/// [early cutoff]: struct.Cache.html#method.set_early_cutoff
+	/// [`unsubscribe`]: struct.Cache.html#method.unsubscribe
+	///
+	pub fn subscribe<AP: ?Sized, F>(
+			&mut self,
+			promise: &AP,
+			listener: F,
+		) -> ListenerId
+			where
+				F: FnMut(DropReason) + 'static,
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.subscribe(promise, Box::new(listener))
+	}
+
+	/// Removes the given listener.
+	///
+	/// Returns whether the listener was registered.
+	///
+	pub fn unsubscribe(&mut self, listener: ListenerId) -> bool {
+		self.inner.unsubscribe(listener)
+	}
+
+	/// Invalidates all builders and their dyn state which can not be builded
+	/// any more, because there are no more references to them.
+	///
+	/// This function has the complexity of `O(n)` with `n` being the [number of
+	/// known builders]. Thus this function is not light-weight, but should be
+	/// called regularly at appropriate locations, i.e. where many builders
 	/// go out of scope.
 	///
 	/// If builders and dynamic states are explicitly removed before going out
@@ -825,6 +1448,345 @@ cfg_if! {
 	pub fn number_of_known_builders(&self) -> usize {
 		self.inner.number_of_known_builders()
 	}
+
+	/// Returns the number of currently cached Artifacts.
+	///
+	pub fn number_of_artifacts(&self) -> usize {
+		self.inner.number_of_artifacts()
+	}
+
+	/// Returns all Builders known to this `Cache`, ordered by id.
+	///
+	/// These are the Builders for which [`is_builder_known`] returns `true`.
+	///
+	/// [`is_builder_known`]: struct.Cache.html#method.is_builder_known
+	///
+	pub fn known_builders(&self) -> Vec<BuilderInfo> {
+		self.inner.known_builders()
+	}
+
+	/// Returns the Builders whose Artifacts have been resolved by the given
+	/// Builder to build its Artifact, ordered by id.
+	///
+	/// The dependencies are tracked from the building of the Artifacts, see
+	/// [`Resolver`]. They are kept as long as the Artifact of the given
+	/// Builder is valid, even if it has been evicted.
+	///
+	/// [`Resolver`]: struct.Resolver.html
+	///
+	pub fn dependencies_of<AP: ?Sized>(&self, promise: &AP) -> Vec<BuilderInfo>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.dependencies_of(promise.id())
+	}
+
+	/// Returns the Builders which resolved the Artifact of the given Builder
+	/// to build their Artifacts, ordered by id.
+	///
+	/// These are the Artifacts which are invalidated along with the one of
+	/// the given Builder, also see [`dependencies_of`].
+	///
+	/// [`dependencies_of`]: struct.Cache.html#method.dependencies_of
+	///
+	pub fn dependents_of<AP: ?Sized>(&self, promise: &AP) -> Vec<BuilderInfo>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.dependents_of(promise.id())
+	}
+
+	/// Returns the direct and indirect dependencies of the given Builder,
+	/// ordered by id.
+	///
+	/// See [`dependencies_of`].
+	///
+	/// [`dependencies_of`]: struct.Cache.html#method.dependencies_of
+	///
+	pub fn transitive_dependencies_of<AP: ?Sized>(&self, promise: &AP) -> Vec<BuilderInfo>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.transitive_dependencies_of(promise.id())
+	}
+
+	/// Returns the direct and indirect dependents of the given Builder,
+	/// ordered by id.
+	///
+	/// See [`dependents_of`].
+	///
+	/// [`dependents_of`]: struct.Cache.html#method.dependents_of
+	///
+	pub fn transitive_dependents_of<AP: ?Sized>(&self, promise: &AP) -> Vec<BuilderInfo>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.inner.transitive_dependents_of(promise.id())
+	}
+
+	/// Returns all known Builders, such that each Builder comes after all
+	/// its dependencies.
+	///
+	/// This is an order in which the Artifacts could be built one after
+	/// another, according to the recorded dependencies. Also see
+	/// [`dependencies_of`].
+	///
+	/// [`dependencies_of`]: struct.Cache.html#method.dependencies_of
+	///
+	pub fn topological_order(&self) -> Vec<BuilderInfo> {
+		self.inner.topological_order()
+	}
+
+	/// Takes a snapshot of the current state of this `Cache`.
+	///
+	/// The [`Snapshot`] lists all known Builders, whether they have an
+	/// Artifact or a dynamic state, and the dependencies between them. It
+	/// can be rendered as DOT graph or as JSON. Unlike the `VisgraphDoc`
+	/// of the `diagnostics` feature, this does not require to record all
+	/// events of the `Cache` from the beginning.
+	///
+	/// [`Snapshot`]: struct.Snapshot.html
+	///
+	pub fn snapshot(&self) -> Snapshot {
+		self.inner.snapshot()
+	}
+
+	/// Builds the Artifacts of the given promises up front.
+	///
+	/// The promises may be of different types, see [`Warm`]. They are built
+	/// one after another, each along with its dependencies, just as by
+	/// [`get`]. Artifacts which are already cached are not built again.
+	/// Failing builds do not stop the warm-up, instead they are reported in
+	/// the returned [`WarmReport`], together with the number of builds which
+	/// actually ran.
+	///
+	/// This allows to do the expensive building e.g. at the startup of a
+	/// service, instead of on its first request.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use daab::rc::Cache;
+	/// use daab::rc::Blueprint;
+	/// use daab::rc::ConstBuilder;
+	/// use daab::rc::ConfigurableBuilder;
+	///
+	/// let number = Blueprint::new(ConstBuilder::new(std::rc::Rc::new(42)));
+	/// let text = Blueprint::new(ConfigurableBuilder::new(String::from("foo")));
+	///
+	/// let mut cache = Cache::new();
+	///
+	/// let report = cache.warm(&[&number, &text]);
+	/// assert!(report.is_success());
+	/// assert_eq!(report.builds(), 2);
+	///
+	/// // Nothing to do any more
+	/// assert_eq!(cache.warm(&[&number, &text]).builds(), 0);
+	/// ```
+	///
+	/// [`Warm`]: trait.Warm.html
+	/// [`get`]: struct.Cache.html#method.get
+	/// [`WarmReport`]: struct.WarmReport.html
+	///
+	pub fn warm(&mut self, promises: &[&dyn Warm<ArtCan, BCan>]) -> WarmReport {
+		let builds = self.inner.number_of_builds();
+
+		let failed = promises.iter()
+			.filter_map(|promise| {
+				promise.warm(self).err().map(|err| (promise.info(), err))
+			})
+			.collect();
+
+		WarmReport::new(self.inner.number_of_builds() - builds, failed)
+	}
+
+	/// Returns the maximum number of Artifacts kept by this cache, if any.
+	///
+	/// See [`set_max_artifacts`].
+	///
+	/// [`set_max_artifacts`]: struct.Cache.html#method.set_max_artifacts
+	///
+	pub fn max_artifacts(&self) -> Option<usize> {
+		self.inner.max_artifacts()
+	}
+
+	/// Limits the number of Artifacts kept by this cache.
+	///
+	/// When the limit is exceeded, the least recently used Artifacts are
+	/// evicted from this cache. Every lookup or retrieval of an Artifact
+	/// counts as a use. An evicted Artifact is transparently rebuilt when it
+	/// is requested again.
+	///
+	/// Eviction only drops the Artifact. The dynamic state of its Builder
+	/// is kept, and the Artifacts of depending Builders remain valid, since
+	/// they hold their own Bins of it. However, if the evicted Artifact gets
+	/// invalidated later, its dependents are invalidated too.
+	///
+	/// The Artifact which has just been built is never evicted, even if the
+	/// limit is zero. Passing `None` removes the limit, which is the default.
+	///
+	pub fn set_max_artifacts(&mut self, max: Option<usize>) {
+		self.inner.set_max_artifacts(max)
+	}
+
+	/// Returns the maximum sum of Artifact sizes kept by this cache, if any.
+	///
+	/// See [`set_max_size`].
+	///
+	/// [`set_max_size`]: struct.Cache.html#method.set_max_size
+	///
+	pub fn max_size(&self) -> Option<usize> {
+		self.inner.max_size()
+	}
+
+	/// Limits the sum of the sizes of the Artifacts kept by this cache.
+	///
+	/// The size of each Artifact is reported by its Builder via
+	/// [`BuildPolicy::artifact_size`]. Otherwise, this limit behaves just like
+	/// the one of [`set_max_artifacts`], both limits may be combined.
+	///
+	/// [`BuildPolicy::artifact_size`]: ../trait.BuildPolicy.html#method.artifact_size
+	/// [`set_max_artifacts`]: struct.Cache.html#method.set_max_artifacts
+	///
+	pub fn set_max_size(&mut self, max: Option<usize>) {
+		self.inner.set_max_size(max)
+	}
+
+	/// Returns the sum of the sizes of all currently cached Artifacts.
+	///
+	/// See [`set_max_size`].
+	///
+	/// [`set_max_size`]: struct.Cache.html#method.set_max_size
+	///
+	pub fn artifacts_size(&self) -> usize {
+		self.inner.artifacts_size()
+	}
+
+	/// Sets the source of the current time, which is used to expire
+	/// Artifacts.
+	///
+	/// Builders may limit the lifetime of their Artifacts via
+	/// [`BuildPolicy::time_to_live`]. An Artifact expires when it is older than
+	/// its time to live, or when any of its dependencies expires. Expired
+	/// Artifacts are treated as if they had been invalidated, i.e. they and
+	/// their dependents are rebuilt on the next `get`.
+	///
+	/// By default, the [`SystemClock`] is used. Injecting a [`ManualClock`]
+	/// allows to control the expiry deterministically, e.g. in tests.
+	///
+	/// Notice, while building, the time of the `Cache` stands still, i.e. no
+	/// Artifact expires until the outermost build is over.
+	///
+	/// [`BuildPolicy::time_to_live`]: ../trait.BuildPolicy.html#method.time_to_live
+	/// [`SystemClock`]: struct.SystemClock.html
+	/// [`ManualClock`]: struct.ManualClock.html
+	///
+	pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+		self.inner.set_clock(Rc::new(clock))
+	}
+
+	/// Returns whether the early cutoff is enabled.
+	///
+	/// See [`set_early_cutoff`].
+	///
+	/// [`set_early_cutoff`]: struct.Cache.html#method.set_early_cutoff
+	///
+	pub fn early_cutoff(&self) -> bool {
+		self.inner.early_cutoff()
+	}
+
+	/// Enables or disables the early cutoff when rebuilding Artifacts.
+	///
+	/// Without the early cutoff, invalidating an Artifact drops all its
+	/// (transitive) dependents, which are all rebuilt on the next `get`.
+	/// With the early cutoff, the dependents are only marked as dirty
+	/// instead. When a dirty Artifact is requested, its dirty dependencies
+	/// are brought up to date first. If none of them changed, the dirty
+	/// Artifact is reused, otherwise it gets rebuilt.
+	///
+	/// Whether a rebuilt Artifact changed is decided by its Builder via
+	/// [`BuildPolicy::artifacts_equal`], which by default considers any rebuilt
+	/// Artifact as changed. If it did not change, the previous Artifact is
+	/// kept.
+	///
+	/// Notice, a dirty dependency can only be rebuilt on its own, if its
+	/// Builder is wrapped in a [`Blueprint`]. Otherwise, it counts as
+	/// changed, and its dependents rebuild it. Also, dirty Artifacts do not
+	/// count towards the limits of [`set_max_artifacts`] and
+	/// [`set_max_size`].
+	///
+	/// Disabling the early cutoff invalidates all dirty Artifacts. It is
+	/// disabled by default.
+	///
+	/// [`BuildPolicy::artifacts_equal`]: ../trait.BuildPolicy.html#method.artifacts_equal
+	/// [`Blueprint`]: ../blueprint/struct.Blueprint.html
+	/// [`set_max_artifacts`]: struct.Cache.html#method.set_max_artifacts
+	/// [`set_max_size`]: struct.Cache.html#method.set_max_size
+	///
+	pub fn set_early_cutoff(&mut self, enabled: bool) {
+		self.inner.set_early_cutoff(enabled)
+	}
+
+	/// Invalidates all Artifacts which are stale according to their Builders.
+	///
+	/// Each Builder with a cached Artifact is asked via
+	/// [`BuildPolicy::is_stale`], whether its Artifact became stale due to
+	/// inputs which are not tracked by this `Cache`. The stale Artifacts are
+	/// invalidated including their dependents, just like via [`invalidate`].
+	///
+	/// Notice, only Builders wrapped in a [`Blueprint`] can be asked, the
+	/// Artifacts of others are never considered stale.
+	///
+	/// [`BuildPolicy::is_stale`]: ../trait.BuildPolicy.html#method.is_stale
+	/// [`invalidate`]: struct.Cache.html#method.invalidate
+	/// [`Blueprint`]: ../blueprint/struct.Blueprint.html
+	///
+	pub fn validate(&mut self) {
+		self.inner.validate()
+	}
+
+	/// Invalidates the Artifacts of all Builders whose watched files changed.
+	///
+	/// Builders watch the files they read via [`Resolver::watch_file`], as
+	/// done by the [`FileInputBuilder`]. A file counts as changed if the
+	/// hash of its contents changed, or if it can no longer be read. Only
+	/// files whose modification time changed are read, see
+	/// [`FileStamp::has_changed`]. Only the Artifacts of the Builders of
+	/// changed files are invalidated (including their dependents), so that
+	/// they are rebuilt on the next `get`.
+	///
+	/// [`Resolver::watch_file`]: struct.Resolver.html#method.watch_file
+	/// [`FileInputBuilder`]: ../utils/struct.FileInputBuilder.html
+	/// [`FileStamp::has_changed`]: struct.FileStamp.html#method.has_changed
+	///
+	pub fn refresh_inputs(&mut self) {
+		self.inner.refresh_inputs()
+	}
+
+cfg_if! {
+	if #[cfg(feature = "persistence")] {
+		/// Sets the directory to store the Artifacts of persistent Builders
+		/// in, creating it if necessary.
+		///
+		/// Artifacts stored in this directory, e.g. by a `Cache` of a previous
+		/// run, are restored instead of being built, if they are still valid.
+		/// For details see the [`persistence`] module.
+		///
+		/// **Notice: This method is only available if the `persistence`
+		/// feature has been activated**.
+		///
+		/// [`persistence`]: ../persistence/index.html
+		///
+		#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "persistence")))]
+		pub fn set_store(&mut self, dir: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+			self.inner.set_store(dir.as_ref())
+		}
+	}
+}
 }
 
 
@@ -847,9 +1809,26 @@ cfg_if! {
 /// These tracked dependencies are used to correctly implement the Artifact
 /// invalidation of the `Cache` through [`Cache::invalidate`].
 ///
+/// The `Resolver` also keeps track of the Builders currently being built.
+/// If a Builder (directly or indirectly) resolves its own Artifact, this
+/// dependency cycle is detected. The `try_resolve*` methods, such as
+/// [`try_resolve`], return it as [`CycleError`] to the owning Builder. If it
+/// is detected by a plain `resolve*` method instead, which can only return
+/// the error type of the resolved Builder, the builds are aborted by
+/// unwinding up to the nearest `try_resolve*` call or [`Cache::try_get`],
+/// which reports it. Only if there is none, i.e. the Artifact was requested
+/// via a plain accessor such as [`Cache::get`], it panics. Notice, aborting
+/// the builds requires the `unwind` panic strategy, see the
+/// [crate documentation].
+///
 /// [`build`]: ../trait.Builder.html#tymethod.build
 /// [`Cache`]: struct.Cache.html
 /// [`Cache::invalidate`]: struct.Cache.html#method.invalidate
+/// [`try_resolve`]: struct.Resolver.html#method.try_resolve
+/// [`CycleError`]: struct.CycleError.html
+/// [`Cache::try_get`]: struct.Cache.html#method.try_get
+/// [`Cache::get`]: struct.Cache.html#method.get
+/// [crate documentation]: ../index.html#dependency-cycles
 ///
 pub struct Resolver<'a, ArtCan, BCan: CanStrong, DynState = ()> {
 	user: &'a BuilderEntry<BCan>,
@@ -887,6 +1866,22 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 	}
 
 
+	/// Checks that the deadline allows to resolve the given promise, aborting
+	/// the build of the owning Builder otherwise, see `Resolver`.
+	///
+	fn check_budget<AP>(
+			&mut self,
+			promise: &AP
+		)
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		if let Err(timeout) = self.cache.check_deadline(promise) {
+			Interrupt::from(timeout).raise();
+		}
+	}
+
 	/// Resolves an Artifact to its Bin.
 	///
 	/// Returns the Artifact in its Bin. That is an `Rc<B::Artifact>` when using
@@ -901,8 +1896,14 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 	///
 	/// Also see the corresponding [`get`] method of `Cache`.
 	///
+	/// If the Builder is already being built, i.e. if there is a dependency
+	/// cycle, the build of the owning Builder is aborted, see `Resolver`.
+	/// Use [`try_resolve`] to get a [`CycleError`] instead.
+	///
 	/// [`resolve_ref`]: struct.Resolver.html#method.resolve_ref
 	/// [`get`]: struct.Cache.html#method.get
+	/// [`try_resolve`]: struct.Resolver.html#method.try_resolve
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn resolve<AP, B: ?Sized>(
 			&mut self,
@@ -915,8 +1916,98 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan> {
 
+		self.check_budget(promise);
 		self.track_dependency(promise);
-		self.cache.get(promise)
+		self.cache.fetch(promise).map_err(ResolveError::or_raise)
+	}
+
+	/// Returns the time left until the deadline of the current build.
+	///
+	/// This is the time left until the deadline given to
+	/// [`Cache::get_with_deadline`] or [`Cache::get_with_budget`], measured
+	/// by the `Clock` of the `Cache`, or zero if it has passed already.
+	/// Returns `None` if there is no deadline.
+	///
+	/// Since the `Cache` never interrupts a running build, a long running
+	/// Builder should check this on its own and give up once it is zero.
+	///
+	/// [`Cache::get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+	/// [`Cache::get_with_budget`]: struct.Cache.html#method.get_with_budget
+	///
+	pub fn remaining_budget(&self) -> Option<Duration> {
+		self.cache.remaining_budget()
+	}
+
+	/// Tests whether the current build has been cancelled.
+	///
+	/// This is the case, if the token passed to [`Cache::get_cancellable`]
+	/// has been cancelled. A Builder observing this should return an `Err`
+	/// as soon as possible. Meanwhile, resolving a dependency which is not
+	/// cached fails with a [`ResolveError::Cancelled`], see
+	/// [`Cache::get_cancellable`]. Builds started via any other method are
+	/// never cancelled.
+	///
+	/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+	/// [`ResolveError::Cancelled`]: enum.ResolveError.html#variant.Cancelled
+	///
+	pub fn is_cancelled(&self) -> bool {
+		self.cache.is_cancelled()
+	}
+
+	/// Resolves an Artifact to its Bin, recording the failing Builders.
+	///
+	/// This is the same as [`resolve`], except that an error is returned as
+	/// [`ContextError`], which additionally lists the Builders from the
+	/// failing one up to the Builder of this `Resolver`. The original error
+	/// of the failing Builder remains accessible.
+	///
+	/// The resolved Builder may either have a plain error type `E` or use
+	/// `ContextError<E>` itself, in which case its chain is extended. Thus, if
+	/// each Builder on the way uses `ContextError<E>` as `Err` type and
+	/// resolves via this method, the error returned by [`Cache::get`] names
+	/// the whole path down to the failing Builder.
+	///
+	/// The `Cache` traces each failed build back to the failed resolution,
+	/// which preceded it within that build. Therefore, the failing Builder is
+	/// the one whose build failed first, and Builders which just pass the
+	/// error on via [`resolve`] are listed too.
+	///
+	/// Like [`resolve`], this aborts the build of the owning Builder if
+	/// there is a dependency cycle.
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`ContextError`]: struct.ContextError.html
+	/// [`Cache::get`]: struct.Cache.html#method.get
+	///
+	pub fn resolve_with_context<AP, B: ?Sized, Err>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ContextError<Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				B::Err: Into<ContextError<Err>>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		self.resolve(promise).map_err(|err| {
+			let mut err = err.into();
+
+			// Add the Builders the error passed through since it was last
+			// recorded, as traced by the build
+			let path = self.cache.failure_path(promise.id());
+			let recorded = err.chain().last().and_then(|last| {
+				path.iter().position(|info| info.id() == last.id())
+			});
+
+			for info in &path[recorded.map_or(0, |pos| pos + 1)..] {
+				err.push(*info);
+			}
+			err.push(self.cache.builder_info(self.user.id()));
+
+			err
+		})
 	}
 
 	/// Resolves an Artifact by reference.
@@ -937,9 +2028,15 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 	///
 	/// Also see the corresponding [`get_ref`] method of `Cache`.
 	///
+	/// If the Builder is already being built, i.e. if there is a dependency
+	/// cycle, the build of the owning Builder is aborted, see `Resolver`.
+	/// Use [`try_resolve_ref`] to get a [`CycleError`] instead.
+	///
 	/// [`resolve`]: struct.Resolver.html#method.resolve
 	/// [`resolve_cloned`]: struct.Resolver.html#method.resolve_cloned
 	/// [`get_ref`]: struct.Cache.html#method.get_ref
+	/// [`try_resolve_ref`]: struct.Resolver.html#method.try_resolve_ref
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn resolve_ref<AP, B: ?Sized>(
 			&mut self,
@@ -951,8 +2048,9 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
+		self.check_budget(promise);
 		self.track_dependency(promise);
-		self.cache.get_ref(promise)
+		self.cache.fetch_ref(promise).map_err(ResolveError::or_raise)
 	}
 
 	/// Resolves an Artifact into a clone of it.
@@ -968,8 +2066,14 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 	///
 	/// Also see the corresponding [`get_cloned`] method of `Cache`.
 	///
+	/// If the Builder is already being built, i.e. if there is a dependency
+	/// cycle, the build of the owning Builder is aborted, see `Resolver`.
+	/// Use [`try_resolve_cloned`] to get a [`CycleError`] instead.
+	///
 	/// [`resolve_ref`]: struct.Resolver.html#method.resolve_ref
 	/// [`get_cloned`]: struct.Cache.html#method.get_cloned
+	/// [`try_resolve_cloned`]: struct.Resolver.html#method.try_resolve_cloned
+	/// [`CycleError`]: struct.CycleError.html
 	///
 	pub fn resolve_cloned<AP, B: ?Sized>(
 			&mut self,
@@ -982,8 +2086,269 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
+		self.check_budget(promise);
+		self.track_dependency(promise);
+		self.cache.fetch_ref(promise).cloned().map_err(ResolveError::or_raise)
+	}
+
+	/// Resolves the Artifact of an async Builder to its Bin.
+	///
+	/// This is the async counterpart of [`resolve`] for [`AsyncBuilder`]s,
+	/// thus it is only usable from within the `build` method of another
+	/// `AsyncBuilder`.
+	///
+	/// Also see the corresponding [`get_async`] method of `Cache`.
+	///
+	/// If the Builder is already being built, i.e. if there is a dependency
+	/// cycle, the build of the owning Builder is aborted, see `Resolver`.
+	/// Use [`try_resolve_async`] to get a [`CycleError`] instead.
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`get_async`]: struct.Cache.html#method.get_async
+	/// [`AsyncBuilder`]: ../trait.AsyncBuilder.html
+	/// [`try_resolve_async`]: struct.Resolver.html#method.try_resolve_async
+	/// [`CycleError`]: struct.CycleError.html
+	///
+	pub async fn resolve_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, B::Err>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: AsyncBuilder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		self.check_budget(promise);
+		self.track_dependency(promise);
+		self.cache.fetch_async(promise).await.map_err(ResolveError::or_raise)
+	}
+
+	/// Resolves the Artifact of an async Builder into a clone of it.
+	///
+	/// This is the async counterpart of [`resolve_cloned`] for
+	/// [`AsyncBuilder`]s, see [`resolve_async`] for details.
+	///
+	/// [`resolve_cloned`]: struct.Resolver.html#method.resolve_cloned
+	/// [`resolve_async`]: struct.Resolver.html#method.resolve_async
+	/// [`AsyncBuilder`]: ../trait.AsyncBuilder.html
+	///
+	pub async fn resolve_async_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, B::Err>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: AsyncBuilder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.check_budget(promise);
+		self.track_dependency(promise);
+		self.cache.fetch_async_cloned(promise).await.map_err(ResolveError::or_raise)
+	}
+
+	/// Checks that resolving the given promise does not form a dependency
+	/// cycle.
+	///
+	fn check_cycle<AP>(
+			&self,
+			promise: &AP
+		) -> Result<(), CycleError>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		match self.cache.find_cycle(promise.id()) {
+			Some(cycle) => Err(cycle),
+			None => Ok(()),
+		}
+	}
+
+	/// Tries to resolve an Artifact to its Bin.
+	///
+	/// This is the same as [`resolve`], except that a dependency cycle is
+	/// reported as [`ResolveError::Cycle`] instead of aborting the build of
+	/// the owning Builder. This includes cycles detected while building the
+	/// dependencies of the resolved Builder. An error of the resolved Builder
+	/// is returned as [`ResolveError::Failed`].
+	///
+	/// If the deadline given to [`Cache::get_with_deadline`] has passed and
+	/// the Artifact is not cached, it is not built, but reported as
+	/// [`ResolveError::Timeout`] instead.
+	///
+	/// Likewise, if the token given to [`Cache::get_cancellable`] has been
+	/// cancelled, it is reported as [`ResolveError::Cancelled`].
+	///
+	/// If a cycle, a timeout or a cancellation is detected for the given
+	/// promise itself, no dependency is recorded.
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`ResolveError::Timeout`]: enum.ResolveError.html#variant.Timeout
+	/// [`Cache::get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+	/// [`ResolveError::Cancelled`]: enum.ResolveError.html#variant.Cancelled
+	/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+	///
+	pub fn try_resolve<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		self.check_cycle(promise)?;
+		self.cache.check_deadline(promise)?;
+		self.cache.check_cancelled(promise)?;
+		self.track_dependency(promise);
+		self.cache.try_get(promise)
+	}
+
+	/// Tries to resolve an Artifact by reference.
+	///
+	/// This is the same as [`resolve_ref`], except that a dependency cycle
+	/// is reported as [`ResolveError::Cycle`] instead of aborting the build
+	/// of the owning Builder, see [`try_resolve`]. An error of the resolved
+	/// Builder is returned as [`ResolveError::Failed`].
+	///
+	/// If the deadline given to [`Cache::get_with_deadline`] has passed and
+	/// the Artifact is not cached, it is not built, but reported as
+	/// [`ResolveError::Timeout`] instead.
+	///
+	/// Likewise, if the token given to [`Cache::get_cancellable`] has been
+	/// cancelled, it is reported as [`ResolveError::Cancelled`].
+	///
+	/// If a cycle, a timeout or a cancellation is detected for the given
+	/// promise itself, no dependency is recorded.
+	///
+	/// [`resolve_ref`]: struct.Resolver.html#method.resolve_ref
+	/// [`try_resolve`]: struct.Resolver.html#method.try_resolve
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`ResolveError::Timeout`]: enum.ResolveError.html#variant.Timeout
+	/// [`Cache::get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+	/// [`ResolveError::Cancelled`]: enum.ResolveError.html#variant.Cancelled
+	/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+	///
+	pub fn try_resolve_ref<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&B::Artifact, ResolveError<B::Err>>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.check_cycle(promise)?;
+		self.cache.check_deadline(promise)?;
+		self.cache.check_cancelled(promise)?;
+		self.track_dependency(promise);
+		self.cache.try_get_ref(promise)
+	}
+
+	/// Tries to resolve an Artifact into a clone of it.
+	///
+	/// This is the same as [`resolve_cloned`], except that a dependency
+	/// cycle is reported as [`ResolveError::Cycle`] instead of aborting the
+	/// build of the owning Builder, see [`try_resolve`]. An error of the
+	/// resolved Builder is returned as [`ResolveError::Failed`].
+	///
+	/// If the deadline given to [`Cache::get_with_deadline`] has passed and
+	/// the Artifact is not cached, it is not built, but reported as
+	/// [`ResolveError::Timeout`] instead.
+	///
+	/// Likewise, if the token given to [`Cache::get_cancellable`] has been
+	/// cancelled, it is reported as [`ResolveError::Cancelled`].
+	///
+	/// If a cycle, a timeout or a cancellation is detected for the given
+	/// promise itself, no dependency is recorded.
+	///
+	/// [`resolve_cloned`]: struct.Resolver.html#method.resolve_cloned
+	/// [`try_resolve`]: struct.Resolver.html#method.try_resolve
+	/// [`ResolveError::Cycle`]: enum.ResolveError.html#variant.Cycle
+	/// [`ResolveError::Failed`]: enum.ResolveError.html#variant.Failed
+	/// [`ResolveError::Timeout`]: enum.ResolveError.html#variant.Timeout
+	/// [`Cache::get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+	/// [`ResolveError::Cancelled`]: enum.ResolveError.html#variant.Cancelled
+	/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+	///
+	pub fn try_resolve_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, ResolveError<B::Err>>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.check_cycle(promise)?;
+		self.cache.check_deadline(promise)?;
+		self.cache.check_cancelled(promise)?;
 		self.track_dependency(promise);
-		self.cache.get_cloned(promise)
+		self.cache.try_get_cloned(promise)
+	}
+
+	/// Tries to resolve the Artifact of an async Builder to its Bin.
+	///
+	/// This is the async counterpart of [`try_resolve`] for
+	/// [`AsyncBuilder`]s, thus it is only usable from within the `build`
+	/// method of another `AsyncBuilder`.
+	///
+	/// [`try_resolve`]: struct.Resolver.html#method.try_resolve
+	/// [`AsyncBuilder`]: ../trait.AsyncBuilder.html
+	///
+	pub async fn try_resolve_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: AsyncBuilder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		self.check_cycle(promise)?;
+		self.cache.check_deadline(promise)?;
+		self.cache.check_cancelled(promise)?;
+		self.track_dependency(promise);
+		self.cache.try_get_async(promise).await
+	}
+
+	/// Resolves multiple Artifacts to their Bins, one after another.
+	///
+	/// Returns the Artifacts in their Bins in the order of the given
+	/// promises, stopping at the first `Err`. This is the same as calling
+	/// [`resolve`] for each promise, thus it works with any `Cache` and
+	/// any Cans. Use [`resolve_all`] to build the Artifacts in parallel via
+	/// a [`SyncCache`].
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`resolve_all`]: struct.Resolver.html#method.resolve_all
+	/// [`SyncCache`]: struct.SyncCache.html
+	///
+	pub fn resolve_each<AP, B: ?Sized>(
+			&mut self,
+			promises: &[AP]
+		) -> Result<Vec<ArtCan::Bin>, B::Err>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		promises.iter().map(|promise| self.resolve(promise)).collect()
 	}
 
 	/// Returns the dynamic state of the owning Builder.
@@ -1000,6 +2365,121 @@ impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
 		// before we comme here.
 		self.cache.dyn_state_cast_mut(self.user.id()).unwrap()
 	}
+
+	/// Returns the dynamic state of the given Builder, recording it as
+	/// dependency of the owning Builder.
+	///
+	/// This method will initialize the dynamic state if it didn't exist yet,
+	/// but unlike [`resolve`], it does not build the Artifact of the given
+	/// Builder. Since the dependency is recorded, modifying the dynamic
+	/// state later, e.g. via [`Cache::dyn_state_mut`], invalidates the
+	/// Artifact of the owning Builder.
+	///
+	/// If the given Builder is currently building, its dynamic state would
+	/// form a dependency cycle, thus the build of the owning Builder is
+	/// aborted, see `Resolver`. In particular, the owning Builder has to use
+	/// [`my_state`] instead.
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`my_state`]: struct.Resolver.html#method.my_state
+	/// [`Cache::dyn_state_mut`]: struct.Cache.html#method.dyn_state_mut
+	///
+	pub fn dyn_state_of<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> &B::DynState
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		if let Err(cycle) = self.check_cycle(promise) {
+			Interrupt::from(cycle).raise();
+		}
+
+		self.track_dependency(promise);
+		self.cache.read_dyn_state(self.user.id(), promise)
+	}
+
+	/// Watches the given file, which the Artifact of the owning Builder
+	/// depends on.
+	///
+	/// When the file changes, [`Cache::refresh_inputs`] invalidates the
+	/// Artifact of the owning Builder. The watch ends when the Artifact is
+	/// rebuilt or invalidated. Also see the [`FileInputBuilder`].
+	///
+	/// [`Cache::refresh_inputs`]: struct.Cache.html#method.refresh_inputs
+	/// [`FileInputBuilder`]: ../utils/struct.FileInputBuilder.html
+	///
+	pub fn watch_file(&mut self, stamp: FileStamp) {
+		self.cache.watch_file(self.user.id(), stamp)
+	}
 }
 
 
+
+impl<'a, ArtCan, BCan, DynState> Resolver<'a, ArtCan, BCan, DynState>
+	where
+		ArtCan: Debug,
+		BCan: CanStrong,
+		DynState: 'static, {
+
+	/// Resolves multiple Artifacts to their Bins, building them in parallel.
+	///
+	/// Returns the Artifacts in their Bins in the order of the given
+	/// promises. The dependencies upon the given promises are recorded
+	/// exactly as if they were resolved one after another via [`resolve`],
+	/// including that resolving stops at the first `Err` in that order.
+	///
+	/// When the Artifact of the owning Builder is built via a [`SyncCache`],
+	/// the Artifacts which are not yet cached are built by a bounded number
+	/// of threads (see [`SyncCache::set_max_threads`]), and this method waits
+	/// for all of them. Otherwise, i.e. with a plain
+	/// `Cache`, which is not thread-safe, the Artifacts are built one after
+	/// another. Therefore, this method requires thread-safe Cans such as
+	/// those of the [`arc`] module, use [`resolve_each`] with any other
+	/// Cans.
+	///
+	/// Like [`resolve`], this aborts the build of the owning Builder if
+	/// there is a dependency cycle.
+	///
+	/// # Panics
+	///
+	/// Panics if building any of the Artifacts panicked.
+	///
+	/// [`resolve`]: struct.Resolver.html#method.resolve
+	/// [`SyncCache`]: struct.SyncCache.html
+	/// [`SyncCache::set_max_threads`]: struct.SyncCache.html#method.set_max_threads
+	/// [`arc`]: ../arc/index.html
+	/// [`resolve_each`]: struct.Resolver.html#method.resolve_each
+	///
+	pub fn resolve_all<AP, B: ?Sized>(
+			&mut self,
+			promises: &[AP]
+		) -> Result<Vec<ArtCan::Bin>, B::Err>
+			where
+				ArtCan: CanSized<B::Artifact> + Clone + Send + Sync,
+				ArtCan::Bin: Send + 'static,
+				B: Builder<ArtCan, BCan>,
+				B::Err: Send,
+				BCan: Can<AP::Builder> + Debug + Send + Sync,
+				BCan::CanWeak: Clone + Send + Sync,
+				AP: Promise<Builder = B, BCan = BCan> + Clone + Send + 'static {
+
+		match sync::build_parallel(self.cache, promises) {
+			Some(results) => {
+				promises.iter().zip(results).map(|(promise, result)| {
+					match result {
+						// Records the dependency and gets the shared artifact
+						Ok(_) => self.resolve(promise),
+						Err(err) => {
+							self.track_dependency(promise);
+							Err(err)
+						}
+					}
+				}).collect()
+			}
+			None => self.resolve_each(promises),
+		}
+	}
+}
@@ -0,0 +1,47 @@
+//!
+//! Cooperative cancellation of builds.
+//!
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+
+
+/// A token to cancel builds which are in progress.
+///
+/// The token is passed to [`Cache::get_cancellable`], and Builders check it
+/// from within their `build` method via [`Resolver::is_cancelled`]. All
+/// clones of a token share the same state, thus a build can be cancelled
+/// from another thread or from within another Builder.
+///
+/// Cancellation is cooperative: a Builder which observes it is expected to
+/// return an `Err` as soon as possible.
+///
+/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+/// [`Resolver::is_cancelled`]: struct.Resolver.html#method.is_cancelled
+///
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+	cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+	/// Creates a new token, which is not cancelled.
+	///
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Requests the cancellation of all builds using this token.
+	///
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::SeqCst);
+	}
+
+	/// Tests whether the cancellation has been requested.
+	///
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::SeqCst)
+	}
+}
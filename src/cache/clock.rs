@@ -0,0 +1,91 @@
+//!
+//! Time sources for the expiry of artifacts.
+//!
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+
+
+/// A source of the current time, as used by the [`Cache`] to expire
+/// artifacts.
+///
+/// By default, a `Cache` uses the [`SystemClock`]. An other clock can be
+/// injected via [`Cache::set_clock`], e.g. a [`ManualClock`] for testing.
+///
+/// [`Cache`]: struct.Cache.html
+/// [`Cache::set_clock`]: struct.Cache.html#method.set_clock
+/// [`SystemClock`]: struct.SystemClock.html
+/// [`ManualClock`]: struct.ManualClock.html
+///
+pub trait Clock: Debug {
+	/// Returns the current time.
+	///
+	fn now(&self) -> Instant;
+}
+
+/// The clock of the operating system, as given by `Instant::now`.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+/// A clock which only advances when told so.
+///
+/// All clones of a `ManualClock` share the same time, thus one clone can be
+/// given to a [`Cache`] while the other one is used to advance the time.
+///
+/// ```
+/// use std::time::Duration;
+/// use daab::cache::Clock;
+/// use daab::cache::ManualClock;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+///
+/// clock.clone().advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+///
+/// [`Cache`]: struct.Cache.html
+///
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+	now: Rc<Cell<Instant>>,
+}
+
+impl ManualClock {
+	/// Creates a new clock starting at the current time.
+	///
+	pub fn new() -> Self {
+		Self {
+			now: Rc::new(Cell::new(Instant::now())),
+		}
+	}
+
+	/// Advances the time of this clock and all its clones.
+	///
+	pub fn advance(&self, duration: Duration) {
+		self.now.set(self.now.get() + duration);
+	}
+}
+
+impl Default for ManualClock {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Clock for ManualClock {
+	fn now(&self) -> Instant {
+		self.now.get()
+	}
+}
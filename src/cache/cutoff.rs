@@ -0,0 +1,156 @@
+//!
+//! Revision tracking of artifacts for the early cutoff.
+//!
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::BuilderId;
+
+
+
+/// Tracks when artifacts have been built and when they have changed.
+///
+/// Each build gets a new revision. The artifact of a builder records the
+/// revision when it was last verified, i.e. built or found to be still valid,
+/// and the revision when its value last changed. An artifact whose
+/// dependencies have not changed since it was verified is still valid, even
+/// if some of them have been rebuilt.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Revisions {
+	/// Whether the early cutoff is enabled. If not, nothing is tracked.
+	///
+	enabled: bool,
+
+	/// The latest revision.
+	///
+	revision: u64,
+
+	/// Maps builder id to the revision when its artifact last changed.
+	///
+	changed: HashMap<BuilderId, u64>,
+
+	/// Maps builder id to the revision when its artifact was last verified.
+	///
+	verified: HashMap<BuilderId, u64>,
+
+	/// The builders which have been invalidated themselves, thus they must be
+	/// rebuilt, unlike their merely dirty dependents.
+	///
+	forced: HashSet<BuilderId>,
+
+	/// Maps builder id to the builders which read its dyn state, thus they
+	/// must be rebuilt when it is changed, regardless of its artifact.
+	///
+	readers: HashMap<BuilderId, HashSet<BuilderId>>,
+}
+
+impl Revisions {
+	/// Creates a new disabled tracker.
+	///
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	/// Tests whether the early cutoff is enabled.
+	///
+	pub(crate) fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	/// Enables or disables the early cutoff.
+	///
+	/// Disabling forgets all revisions.
+	///
+	pub(crate) fn set_enabled(&mut self, enabled: bool) {
+		self.enabled = enabled;
+
+		if !enabled {
+			self.clear();
+		}
+	}
+
+	/// Records a new build of the artifact of the given builder.
+	///
+	/// If the artifact is `unchanged`, i.e. equal to the previous one, its
+	/// revision of change is kept.
+	///
+	pub(crate) fn record(&mut self, bid: BuilderId, unchanged: bool) {
+		if !self.enabled {
+			return;
+		}
+
+		self.revision += 1;
+		self.verified.insert(bid, self.revision);
+		self.forced.remove(&bid);
+
+		if !unchanged || !self.changed.contains_key(&bid) {
+			self.changed.insert(bid, self.revision);
+		}
+	}
+
+	/// Records that the artifact of the given builder is still valid.
+	///
+	pub(crate) fn verify(&mut self, bid: BuilderId) {
+		self.verified.insert(bid, self.revision);
+	}
+
+	/// Returns the revision when the artifact of the given builder last
+	/// changed, if known.
+	///
+	pub(crate) fn changed_at(&self, bid: BuilderId) -> Option<u64> {
+		self.changed.get(&bid).copied()
+	}
+
+	/// Returns the revision when the artifact of the given builder was last
+	/// verified, if known.
+	///
+	pub(crate) fn verified_at(&self, bid: BuilderId) -> Option<u64> {
+		self.verified.get(&bid).copied()
+	}
+
+	/// Marks the given builder to be rebuilt, along with the builders which
+	/// read its dyn state.
+	///
+	pub(crate) fn force(&mut self, bid: BuilderId) {
+		self.forced.insert(bid);
+
+		if let Some(readers) = self.readers.remove(&bid) {
+			self.forced.extend(readers);
+		}
+	}
+
+	/// Records that the given `reader` has read the dyn state of the given
+	/// builder.
+	///
+	pub(crate) fn read_dyn_state(&mut self, reader: BuilderId, bid: BuilderId) {
+		if self.enabled {
+			self.readers.entry(bid).or_default().insert(reader);
+		}
+	}
+
+	/// Tests whether the given builder has to be rebuilt.
+	///
+	pub(crate) fn is_forced(&self, bid: BuilderId) -> bool {
+		self.forced.contains(&bid)
+	}
+
+	/// Forgets the revisions of the given builder.
+	///
+	pub(crate) fn remove(&mut self, bid: BuilderId) {
+		self.changed.remove(&bid);
+		self.verified.remove(&bid);
+		self.forced.remove(&bid);
+		self.readers.remove(&bid);
+	}
+
+	/// Forgets all revisions.
+	///
+	pub(crate) fn clear(&mut self) {
+		self.changed.clear();
+		self.verified.clear();
+		self.forced.clear();
+		self.readers.clear();
+	}
+}
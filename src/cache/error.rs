@@ -0,0 +1,468 @@
+//!
+//! Error types reported by the `Cache` and `Resolver`.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+use crate::Promise;
+use crate::BuilderId;
+
+
+
+/// Identifies a Builder by its id and type name.
+///
+/// This is a lightweight and untyped description of a Builder, as used in
+/// error reports such as the [`CycleError`].
+///
+/// [`CycleError`]: struct.CycleError.html
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BuilderInfo {
+	id: BuilderId,
+	type_name: &'static str,
+}
+
+impl BuilderInfo {
+	/// Describes the builder of the given promise.
+	///
+	pub(crate) fn new<AP: ?Sized>(promise: &AP) -> Self
+		where AP: Promise {
+
+		BuilderInfo {
+			id: promise.id(),
+			type_name: std::any::type_name::<AP::Builder>(),
+		}
+	}
+
+	/// Describes a builder by its parts.
+	///
+	pub(crate) fn from_parts(id: BuilderId, type_name: &'static str) -> Self {
+		BuilderInfo {
+			id,
+			type_name,
+		}
+	}
+
+	/// Returns the id of the Builder.
+	///
+	pub fn id(&self) -> BuilderId {
+		self.id
+	}
+
+	/// Returns the type name of the Builder.
+	///
+	/// Notice, for type-erased Builders (e.g. in a `BlueprintDyn`) this is
+	/// the name of the trait object type.
+	///
+	pub fn type_name(&self) -> &'static str {
+		self.type_name
+	}
+}
+
+impl Display for BuilderInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} ({:p})", self.type_name, self.id)
+	}
+}
+
+
+
+/// A dependency cycle between Builders.
+///
+/// This error is reported, when a Builder (directly or indirectly) resolves
+/// its own Artifact, which would otherwise recurse endlessly.
+///
+/// The [`chain`] starts and ends with the same Builder, listing all Builders
+/// in the order they resolved each other.
+///
+/// [`chain`]: struct.CycleError.html#method.chain
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError {
+	chain: Vec<BuilderInfo>,
+}
+
+impl CycleError {
+	/// Creates a new cycle error from the given chain.
+	///
+	/// The chain must start and end with the same Builder.
+	///
+	pub(crate) fn new(chain: Vec<BuilderInfo>) -> Self {
+		debug_assert!(chain.len() >= 2);
+		debug_assert_eq!(chain.first(), chain.last());
+
+		CycleError {
+			chain,
+		}
+	}
+
+	/// Returns the Builders involved in the cycle.
+	///
+	/// The first Builder is the one which has been resolved again, thus it
+	/// is also the last Builder of the chain.
+	///
+	pub fn chain(&self) -> &[BuilderInfo] {
+		&self.chain
+	}
+}
+
+impl Display for CycleError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Dependency cycle detected: ")?;
+
+		for (i, info) in self.chain.iter().enumerate() {
+			if i > 0 {
+				write!(f, " -> ")?;
+			}
+			write!(f, "{}", info)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Error for CycleError {}
+
+
+
+/// The deadline was exceeded before building an Artifact.
+///
+/// This error is reported instead of starting a build after the deadline
+/// given to [`Cache::get_with_deadline`] has passed. Artifacts which are
+/// already cached are still returned.
+///
+/// [`Cache::get_with_deadline`]: struct.Cache.html#method.get_with_deadline
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeoutError {
+	builder: BuilderInfo,
+}
+
+impl TimeoutError {
+	/// Creates a new timeout error for the given skipped Builder.
+	///
+	pub(crate) fn new(builder: BuilderInfo) -> Self {
+		TimeoutError {
+			builder,
+		}
+	}
+
+	/// Returns the Builder whose build has been skipped.
+	///
+	pub fn builder(&self) -> BuilderInfo {
+		self.builder
+	}
+}
+
+impl Display for TimeoutError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Deadline exceeded before building {}", self.builder)
+	}
+}
+
+impl Error for TimeoutError {}
+
+
+
+/// The builds have been cancelled before building an Artifact.
+///
+/// This error is reported instead of starting a build after the token given
+/// to [`Cache::get_cancellable`] has been cancelled. Artifacts which are
+/// already cached are still returned.
+///
+/// [`Cache::get_cancellable`]: struct.Cache.html#method.get_cancellable
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CancelledError {
+	builder: BuilderInfo,
+}
+
+impl CancelledError {
+	/// Creates a new cancellation error for the given skipped Builder.
+	///
+	pub(crate) fn new(builder: BuilderInfo) -> Self {
+		CancelledError {
+			builder,
+		}
+	}
+
+	/// Returns the Builder whose build has been skipped.
+	///
+	pub fn builder(&self) -> BuilderInfo {
+		self.builder
+	}
+}
+
+impl Display for CancelledError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Cancelled before building {}", self.builder)
+	}
+}
+
+impl Error for CancelledError {}
+
+
+
+/// Error of resolving an Artifact.
+///
+/// This error is returned by the `try_resolve*` methods of the `Resolver`.
+/// It is either an error returned by the resolved Builder (i.e. [`Failed`])
+/// or an error detected by the `Cache` itself such as a [`Cycle`], a
+/// [`Timeout`] or a [`Cancelled`] build.
+///
+/// [`Failed`]: enum.ResolveError.html#variant.Failed
+/// [`Cycle`]: enum.ResolveError.html#variant.Cycle
+/// [`Timeout`]: enum.ResolveError.html#variant.Timeout
+/// [`Cancelled`]: enum.ResolveError.html#variant.Cancelled
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResolveError<Err> {
+	/// The resolved Builder returned an error.
+	Failed(Err),
+	/// The resolved Builder is already being built, i.e. there is a
+	/// dependency cycle.
+	Cycle(CycleError),
+	/// The deadline has passed before the resolved Builder could be built.
+	Timeout(TimeoutError),
+	/// The builds have been cancelled before the resolved Builder could be
+	/// built.
+	Cancelled(CancelledError),
+}
+
+impl<Err> From<CycleError> for ResolveError<Err> {
+	fn from(cycle: CycleError) -> Self {
+		ResolveError::Cycle(cycle)
+	}
+}
+
+impl<Err> From<TimeoutError> for ResolveError<Err> {
+	fn from(timeout: TimeoutError) -> Self {
+		ResolveError::Timeout(timeout)
+	}
+}
+
+impl<Err> From<CancelledError> for ResolveError<Err> {
+	fn from(cancelled: CancelledError) -> Self {
+		ResolveError::Cancelled(cancelled)
+	}
+}
+
+impl<Err: Display> Display for ResolveError<Err> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ResolveError::Failed(err) => write!(f, "Builder failed: {}", err),
+			ResolveError::Cycle(cycle) => Display::fmt(cycle, f),
+			ResolveError::Timeout(timeout) => Display::fmt(timeout, f),
+			ResolveError::Cancelled(cancelled) => Display::fmt(cancelled, f),
+		}
+	}
+}
+
+impl<Err: Error + 'static> Error for ResolveError<Err> {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			ResolveError::Failed(err) => Some(err),
+			ResolveError::Cycle(cycle) => Some(cycle),
+			ResolveError::Timeout(timeout) => Some(timeout),
+			ResolveError::Cancelled(cancelled) => Some(cancelled),
+		}
+	}
+}
+
+
+
+/// An error detected by the `Cache` while building, which aborts the builds
+/// up to the nearest caller able to report it.
+///
+/// It is returned as `ResolveError` wherever possible. Only the plain
+/// `resolve*` methods of the `Resolver` can not return it, since they
+/// return the error type of the resolved Builder. They abort the builds by
+/// unwinding instead, see `raise` and `catch`, which requires the `unwind`
+/// panic strategy.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Interrupt {
+	/// A dependency cycle.
+	Cycle(CycleError),
+	/// An exceeded deadline.
+	Timeout(TimeoutError),
+	/// Cancelled builds.
+	Cancelled(CancelledError),
+}
+
+impl Interrupt {
+	/// Aborts the current builds by unwinding with this interrupt.
+	///
+	pub(crate) fn raise(self) -> ! {
+		std::panic::resume_unwind(Box::new(self))
+	}
+
+	/// Runs `f`, catching any interrupt raised by it.
+	///
+	/// Any other panic is propagated.
+	///
+	pub(crate) fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Interrupt> {
+		std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+			.map_err(|payload| {
+				match payload.downcast::<Interrupt>() {
+					Ok(interrupt) => *interrupt,
+					Err(payload) => std::panic::resume_unwind(payload),
+				}
+			})
+	}
+}
+
+impl From<CycleError> for Interrupt {
+	fn from(cycle: CycleError) -> Self {
+		Interrupt::Cycle(cycle)
+	}
+}
+
+impl From<TimeoutError> for Interrupt {
+	fn from(timeout: TimeoutError) -> Self {
+		Interrupt::Timeout(timeout)
+	}
+}
+
+impl From<CancelledError> for Interrupt {
+	fn from(cancelled: CancelledError) -> Self {
+		Interrupt::Cancelled(cancelled)
+	}
+}
+
+impl Display for Interrupt {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Interrupt::Cycle(cycle) => Display::fmt(cycle, f),
+			Interrupt::Timeout(timeout) => Display::fmt(timeout, f),
+			Interrupt::Cancelled(cancelled) => Display::fmt(cancelled, f),
+		}
+	}
+}
+
+impl<Err> From<Interrupt> for ResolveError<Err> {
+	fn from(interrupt: Interrupt) -> Self {
+		match interrupt {
+			Interrupt::Cycle(cycle) => ResolveError::Cycle(cycle),
+			Interrupt::Timeout(timeout) => ResolveError::Timeout(timeout),
+			Interrupt::Cancelled(cancelled) => ResolveError::Cancelled(cancelled),
+		}
+	}
+}
+
+impl<Err> ResolveError<Err> {
+	/// Splits this error into the error of the Builder or an interrupt.
+	///
+	pub(crate) fn into_failed(self) -> Result<Err, Interrupt> {
+		match self {
+			ResolveError::Failed(err) => Ok(err),
+			ResolveError::Cycle(cycle) => Err(Interrupt::Cycle(cycle)),
+			ResolveError::Timeout(timeout) => Err(Interrupt::Timeout(timeout)),
+			ResolveError::Cancelled(cancelled) => Err(Interrupt::Cancelled(cancelled)),
+		}
+	}
+
+	/// Unwraps the error of the Builder, raising any other error as
+	/// interrupt.
+	///
+	pub(crate) fn or_raise(self) -> Err {
+		self.into_failed().unwrap_or_else(|interrupt| interrupt.raise())
+	}
+
+	/// Unwraps the error of the Builder, panicking on any other error.
+	///
+	pub(crate) fn or_panic(self) -> Err {
+		self.into_failed().unwrap_or_else(|interrupt| panic!("{}", interrupt))
+	}
+}
+
+
+
+/// An error of a Builder along with the chain of Builders it passed through.
+///
+/// This error wrapper is produced by [`Resolver::resolve_with_context`]. It
+/// keeps the original error of the failing Builder, accessible via
+/// [`original`], and records each Builder on the way from the failing one up
+/// to the top-level request, see [`chain`].
+///
+/// A Builder which uses `ContextError<E>` as its own `Err` type passes the
+/// chain on when it is resolved via `resolve_with_context` itself, so the
+/// chain grows with each level of dependencies.
+///
+/// [`Resolver::resolve_with_context`]: struct.Resolver.html#method.resolve_with_context
+/// [`original`]: struct.ContextError.html#method.original
+/// [`chain`]: struct.ContextError.html#method.chain
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContextError<Err> {
+	original: Err,
+	chain: Vec<BuilderInfo>,
+}
+
+impl<Err> ContextError<Err> {
+	/// Wraps the given error with an empty chain.
+	///
+	/// When this error is returned by a Builder, that Builder will become
+	/// the first one of the chain.
+	///
+	pub fn new(original: Err) -> Self {
+		ContextError {
+			original,
+			chain: Vec::new(),
+		}
+	}
+
+	/// Appends the given Builder to the chain.
+	///
+	pub(crate) fn push(&mut self, builder: BuilderInfo) {
+		self.chain.push(builder);
+	}
+
+	/// Returns the original error of the failing Builder.
+	///
+	pub fn original(&self) -> &Err {
+		&self.original
+	}
+
+	/// Unwraps the original error of the failing Builder.
+	///
+	pub fn into_original(self) -> Err {
+		self.original
+	}
+
+	/// Returns the Builders the error passed through.
+	///
+	/// The first Builder is the failing one and the last Builder is the one
+	/// which has been requested at top-level.
+	///
+	pub fn chain(&self) -> &[BuilderInfo] {
+		&self.chain
+	}
+}
+
+impl<Err> From<Err> for ContextError<Err> {
+	fn from(original: Err) -> Self {
+		ContextError::new(original)
+	}
+}
+
+impl<Err: Display> Display for ContextError<Err> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.original)?;
+
+		for info in &self.chain {
+			write!(f, "\n  in {}", info)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<Err: Error + 'static> Error for ContextError<Err> {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		Some(&self.original)
+	}
+}
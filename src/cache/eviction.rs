@@ -0,0 +1,170 @@
+//!
+//! Least-recently-used tracking of artifacts.
+//!
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::BuilderId;
+
+
+
+/// Tracks the usage and size of the cached artifacts, to determine which to
+/// evict when exceeding the capacity.
+///
+/// Each artifact gets a stamp of its last use, the artifact with the lowest
+/// stamp is the least recently used one.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Eviction {
+	/// The maximum number of artifacts, if any.
+	///
+	max_artifacts: Option<usize>,
+
+	/// The maximum sum of the artifact sizes, if any.
+	///
+	max_size: Option<usize>,
+
+	/// The sum of the sizes of all tracked artifacts.
+	///
+	total_size: usize,
+
+	/// The stamp for the next use.
+	///
+	next_stamp: u64,
+
+	/// Maps builder id to the stamp of the last use and the size of its
+	/// artifact.
+	///
+	entries: HashMap<BuilderId, (u64, usize)>,
+
+	/// Maps the stamp of the last use to the builder id, thus it is ordered
+	/// from the least recently used to the most recently used artifact.
+	///
+	order: BTreeMap<u64, BuilderId>,
+}
+
+impl Eviction {
+	/// Creates a new tracker without any limits.
+	///
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the maximum number of artifacts, if any.
+	///
+	pub(crate) fn max_artifacts(&self) -> Option<usize> {
+		self.max_artifacts
+	}
+
+	/// Sets the maximum number of artifacts.
+	///
+	pub(crate) fn set_max_artifacts(&mut self, max: Option<usize>) {
+		self.max_artifacts = max;
+	}
+
+	/// Returns the maximum sum of artifact sizes, if any.
+	///
+	pub(crate) fn max_size(&self) -> Option<usize> {
+		self.max_size
+	}
+
+	/// Sets the maximum sum of artifact sizes.
+	///
+	pub(crate) fn set_max_size(&mut self, max: Option<usize>) {
+		self.max_size = max;
+	}
+
+	/// Returns the sum of the sizes of all tracked artifacts.
+	///
+	pub(crate) fn total_size(&self) -> usize {
+		self.total_size
+	}
+
+	/// Returns a new stamp.
+	///
+	fn stamp(&mut self) -> u64 {
+		let stamp = self.next_stamp;
+		self.next_stamp += 1;
+		stamp
+	}
+
+	/// Returns the size of the tracked artifact of the given builder, if any.
+	///
+	pub(crate) fn size(&self, bid: BuilderId) -> Option<usize> {
+		self.entries.get(&bid).map(|(_, size)| *size)
+	}
+
+	/// Tracks a new artifact of the given size as most recently used.
+	///
+	pub(crate) fn insert(&mut self, bid: BuilderId, size: usize) {
+		self.remove(bid);
+
+		let stamp = self.stamp();
+		self.entries.insert(bid, (stamp, size));
+		self.order.insert(stamp, bid);
+		self.total_size += size;
+	}
+
+	/// Marks the artifact of the given builder as most recently used.
+	///
+	pub(crate) fn touch(&mut self, bid: BuilderId) {
+		let stamp = self.stamp();
+
+		if let Some(entry) = self.entries.get_mut(&bid) {
+			self.order.remove(&entry.0);
+			self.order.insert(stamp, bid);
+			entry.0 = stamp;
+		}
+	}
+
+	/// Stops tracking the artifact of the given builder.
+	///
+	pub(crate) fn remove(&mut self, bid: BuilderId) {
+		if let Some((stamp, size)) = self.entries.remove(&bid) {
+			self.order.remove(&stamp);
+			self.total_size -= size;
+		}
+	}
+
+	/// Stops tracking any artifact.
+	///
+	pub(crate) fn clear(&mut self) {
+		self.entries.clear();
+		self.order.clear();
+		self.total_size = 0;
+	}
+
+	/// Tests whether the tracked artifacts exceed any limit.
+	///
+	fn exceeded(&self) -> bool {
+		self.max_artifacts.map(|max| self.entries.len() > max).unwrap_or(false)
+			|| self.max_size.map(|max| self.total_size > max).unwrap_or(false)
+	}
+
+	/// Stops tracking the least recently used artifacts until no limit is
+	/// exceeded any more, and returns their builder ids.
+	///
+	/// The artifact of `keep` is never evicted, even if it exceeds the limits
+	/// on its own.
+	///
+	pub(crate) fn evict(&mut self, keep: Option<BuilderId>) -> Vec<BuilderId> {
+		let mut evicted = Vec::new();
+
+		while self.exceeded() {
+			let victim = self.order.values()
+				.copied()
+				.find(|bid| Some(*bid) != keep);
+
+			match victim {
+				Some(bid) => {
+					self.remove(bid);
+					evicted.push(bid);
+				}
+				None => break,
+			}
+		}
+
+		evicted
+	}
+}
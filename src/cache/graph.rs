@@ -0,0 +1,342 @@
+//!
+//! Dependency tracking between builders.
+//!
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::BuilderId;
+
+
+
+/// The dependency graph between builders, by id.
+///
+/// This is used by the caches to record which artifacts have been used to
+/// produce which other artifacts, and to determine the artifacts which have to
+/// be invalidated along with a given one.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DependencyGraph {
+	/// Tracks the set of direct depending builders of each builder, by id.
+	///
+	/// A dependent builder is one that requires the former's artifact to
+	/// produce its own. This maps for each builder (key), which other
+	/// builders (value) depend on it. I.e. it maps what artifacts needs to be
+	/// invalidate if the former one becomes invalid.
+	///
+	/// A reverse mapping is provided via `dependencies`. Both must be kept in
+	/// sync.
+	///
+	pub(super) dependents: HashMap<BuilderId, HashSet<BuilderId>>,
+
+	/// Tracks the set of direct dependencies of any builders, by id.
+	///
+	/// A dependency is a requirement for an builders artifact. This maps for
+	/// each builder (key), which other builders (value) have been used to
+	/// produce the former's artifact. I.e. it maps what dependencies must be
+	/// removed if the former one becomes invalid (because that it does no
+	/// longer depend on it).
+	///
+	/// This is the reverse of `dependents`. Both must be kept in sync.
+	///
+	pub(super) dependencies: HashMap<BuilderId, HashSet<BuilderId>>,
+
+	/// Tracks all builder id of builders which (yet) have no dependents.
+	///
+	/// This list is an heuristic optimization structure for the garbage collection.
+	/// It is the list of builders to be checked by the garbage collection, reducing the amount
+	/// of checks required.
+	///
+	known_leaf_builder: HashSet<BuilderId>,
+}
+
+impl DependencyGraph {
+	/// Creates a new empty graph.
+	///
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	/// Enlists a new builder, which has no dependents, thus it is a leaf.
+	///
+	pub(crate) fn insert(&mut self, bid: BuilderId) {
+		self.known_leaf_builder.insert(bid);
+	}
+
+	/// Record the dependency of `user` upon `dependency`.
+	///
+	pub(crate) fn add_dependency(&mut self, user: BuilderId, dependency: BuilderId) {
+		// Map dependents (`dependency` has new dependent `user`)
+		self.dependents.entry(dependency)
+			.or_default()
+			.insert(user);
+
+		// Unmark the dependency as leaf, since it has now at least one dependent
+		self.known_leaf_builder.remove(&dependency);
+
+		// Revers mapping (`user` depends on `dependency`)
+		self.dependencies.entry(user)
+			.or_default()
+			.insert(dependency);
+	}
+
+	/// Returns the direct dependencies of the given builder.
+	///
+	pub(crate) fn dependencies_of(&self, bid: BuilderId) -> impl Iterator<Item = BuilderId> + '_ {
+		self.dependencies.get(&bid).into_iter().flatten().copied()
+	}
+
+	/// Returns the direct dependents of the given builder.
+	///
+	pub(crate) fn dependents_of(&self, bid: BuilderId) -> impl Iterator<Item = BuilderId> + '_ {
+		self.dependents.get(&bid).into_iter().flatten().copied()
+	}
+
+	/// Tests whether `user` directly depends on `dependency`.
+	///
+	fn has_dependency(&self, user: BuilderId, dependency: BuilderId) -> bool {
+		self.dependencies.get(&user).map(|set| set.contains(&dependency)).unwrap_or(false)
+	}
+
+	/// Returns the builders which (yet) have no dependents.
+	///
+	pub(crate) fn leafs(&self) -> impl Iterator<Item = BuilderId> + '_ {
+		self.known_leaf_builder.iter().copied()
+	}
+
+	/// Tests whether the given builder has no dependents.
+	///
+	pub(crate) fn is_leaf(&self, bid: BuilderId) -> bool {
+		self.known_leaf_builder.contains(&bid)
+	}
+
+	/// Tests whether the given builder has at least one dependent.
+	///
+	pub(crate) fn has_dependents(&self, bid: BuilderId) -> bool {
+		self.dependents.get(&bid).map(|set| !set.is_empty()).unwrap_or(false)
+	}
+
+	/// Removes the given builder and all its (transitive) dependents from the
+	/// graph.
+	///
+	/// Returns the ids of all removed builders, whose artifacts have become
+	/// invalid. This includes the given builder. The removed builders remain
+	/// known as leafs.
+	///
+	pub(crate) fn invalidate(&mut self, builder: BuilderId) -> Vec<BuilderId> {
+
+		// An unknown builder has no dependents and must not become a leaf.
+		if !self.is_leaf(builder) && !self.dependents.contains_key(&builder) {
+			return vec![builder];
+		}
+
+		// Remember already processed builders, because they have no more
+		// dependencies mapping.
+		let mut processed = HashSet::new();
+		processed.insert(builder);
+
+		// Stack of builder to be invalidated.
+		let mut pending = Vec::new();
+		pending.push(builder);
+
+		// The invalidated builders
+		let mut invalidated = Vec::new();
+
+
+		while let Some(bid) = pending.pop() {
+			// Mark builder as processed
+			processed.insert(bid);
+
+			// Get all dependents and invalidate them too
+			if let Some(set) = self.dependents.remove(&bid) {
+				for dep in set {
+					pending.push(dep);
+				}
+			}
+
+			// Remove dependencies too
+			if let Some(set) = self.dependencies.remove(&bid) {
+				for dep in set {
+					// For each dependency ensure that either it had been
+					// processed before, or it has a counterpart mapping.
+					// In the latter case, remove the dependent relation.
+					if !processed.contains(&dep) {
+						let found = self.dependents.get_mut(&dep)
+								.expect("Mapped dependency has no dependents counterpart map.")
+								.remove(&bid);
+
+						// Notice the above code has important side-effects, thus
+						// only the return value is tested in the assert macro.
+						debug_assert!(found);
+
+						// Check whether this depenencies has other dependents left, or whether it
+						// became a leaf now.
+						let is_leaf = self.dependents[&dep].is_empty();
+						if is_leaf {
+							self.known_leaf_builder.insert(dep);
+						}
+					}
+				}
+			}
+
+			// bid it self has been invalidated, means it has no more dependents, and thus it is a
+			// leaf now!
+			self.known_leaf_builder.insert(bid);
+
+			invalidated.push(bid);
+		}
+
+		invalidated
+	}
+
+	/// Returns the given builder and all its (transitive) dependents, without
+	/// changing the graph.
+	///
+	pub(crate) fn dependents_closure(&self, builder: BuilderId) -> Vec<BuilderId> {
+		Self::closure(&self.dependents, builder)
+	}
+
+	/// Returns the given builder and all its (transitive) dependencies.
+	///
+	pub(crate) fn dependencies_closure(&self, builder: BuilderId) -> Vec<BuilderId> {
+		Self::closure(&self.dependencies, builder)
+	}
+
+	/// Returns the given builder and all builders reachable from it via the
+	/// given edges.
+	///
+	fn closure(
+			edges: &HashMap<BuilderId, HashSet<BuilderId>>,
+			builder: BuilderId,
+		) -> Vec<BuilderId> {
+
+		let mut found = HashSet::new();
+		found.insert(builder);
+
+		let mut pending = vec![builder];
+		let mut closure = Vec::new();
+
+		while let Some(bid) = pending.pop() {
+			for next in edges.get(&bid).into_iter().flatten() {
+				if found.insert(*next) {
+					pending.push(*next);
+				}
+			}
+
+			closure.push(bid);
+		}
+
+		closure
+	}
+
+	/// Orders the given builders, such that each builder comes after all its
+	/// dependencies.
+	///
+	/// Dependencies which are not given are left out.
+	///
+	pub(crate) fn topological_order(&self, builders: &[BuilderId]) -> Vec<BuilderId> {
+		let given: HashSet<_> = builders.iter().copied().collect();
+		let mut visited = HashSet::new();
+		let mut order = Vec::new();
+
+		for &start in builders {
+			if !visited.insert(start) {
+				continue;
+			}
+
+			// Depth-first search, a builder is finished after its dependencies
+			let mut stack = vec![(start, false)];
+
+			while let Some((bid, finished)) = stack.pop() {
+				if finished {
+					order.push(bid);
+					continue;
+				}
+
+				stack.push((bid, true));
+
+				for dep in self.dependencies_of(bid) {
+					if given.contains(&dep) && visited.insert(dep) {
+						stack.push((dep, false));
+					}
+				}
+			}
+		}
+
+		order
+	}
+
+	/// Removes the dependencies of the given builder, but keeps its
+	/// dependents.
+	///
+	/// This is used before rebuilding an artifact, which might have other
+	/// dependencies then.
+	///
+	pub(crate) fn remove_dependencies(&mut self, user: BuilderId) {
+		for dep in self.dependencies.remove(&user).into_iter().flatten() {
+			if let Some(set) = self.dependents.get_mut(&dep) {
+				set.remove(&user);
+
+				// The dependency might have become a leaf now
+				if set.is_empty() {
+					self.dependents.remove(&dep);
+					self.known_leaf_builder.insert(dep);
+				}
+			}
+		}
+	}
+
+	/// Removes all (transitive) dependents of the given builder from the
+	/// graph, but keeps the builder itself.
+	///
+	/// Returns the ids of all removed builders.
+	///
+	pub(crate) fn invalidate_dependents(&mut self, builder: BuilderId) -> Vec<BuilderId> {
+		let mut invalidated = Vec::new();
+
+		let dependents: Vec<_> = self.dependents.get(&builder)
+			.into_iter().flatten().copied().collect();
+
+		for dep in dependents {
+			// A dependent might have been invalidated as dependent of an
+			// other dependent before.
+			if self.has_dependency(dep, builder) {
+				invalidated.extend(self.invalidate(dep));
+			}
+		}
+
+		// Now, `builder` has no more dependents, i.e. it is a leaf
+		self.dependents.remove(&builder);
+		self.known_leaf_builder.insert(builder);
+
+		invalidated
+	}
+
+	/// Forgets the given builder.
+	///
+	/// The builder must have been invalidated before, i.e. it must be a leaf.
+	///
+	pub(crate) fn remove(&mut self, bid: BuilderId) {
+		debug_assert!(!self.has_dependents(bid));
+
+		self.known_leaf_builder.remove(&bid);
+	}
+
+	/// Removes all dependencies, turning all given builders into leafs.
+	///
+	pub(crate) fn clear_dependencies(&mut self, known: impl Iterator<Item = BuilderId>) {
+		self.dependents.clear();
+		self.dependencies.clear();
+
+		// Now, all know builders are leafs!
+		self.known_leaf_builder.extend(known);
+	}
+
+	/// Removes all builders.
+	///
+	pub(crate) fn clear(&mut self) {
+		self.dependents.clear();
+		self.dependencies.clear();
+		self.known_leaf_builder.clear();
+	}
+}
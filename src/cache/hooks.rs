@@ -0,0 +1,121 @@
+//!
+//! The methods of sync and async builders used around their builds.
+//!
+
+use std::fmt::Debug;
+
+use crate::CanStrong;
+use crate::CanSized;
+use crate::Can;
+
+use crate::Promise;
+
+use crate::Builder;
+use crate::AsyncBuilder;
+use crate::BuildPolicy;
+
+use super::internal::RawCache;
+
+
+
+/// Tells the `BuildHooks` of a `Builder` apart.
+///
+pub(crate) enum Blocking {}
+
+/// Tells the `BuildHooks` of an `AsyncBuilder` apart.
+///
+pub(crate) enum NonBlocking {}
+
+/// The methods of a builder, which a `RawCache` uses around building its
+/// artifact.
+///
+/// This is implemented for both `Builder`s and `AsyncBuilder`s, which are
+/// told apart by the `Kind`, being either `Blocking` or `NonBlocking`. Thus,
+/// their builds are prepared and finished by the same code, only the
+/// building itself differs.
+///
+pub(crate) trait BuildHooks<ArtCan, BCan: CanStrong, Kind>: Debug + 'static {
+	/// See `Builder::Artifact`.
+	type Artifact: Debug + 'static;
+
+	/// See `Builder::DynState`.
+	type DynState: Debug + 'static;
+
+	/// See `Builder::Err`.
+	type Err: Debug + 'static;
+
+	/// See `Builder::init_dyn_state`.
+	fn init_dyn_state(&self) -> Self::DynState;
+
+	/// See `Builder::policy`.
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err>;
+
+	/// Builds the artifact of the given promise right away, as used by the
+	/// early cutoff.
+	///
+	/// Returns whether the artifact has been built successfully.
+	///
+	fn rebuild<AP>(cache: &mut RawCache<ArtCan, BCan>, promise: &AP) -> bool
+		where
+			ArtCan: CanSized<Self::Artifact>,
+			BCan: Can<AP::Builder>,
+			AP: Promise<Builder = Self, BCan = BCan>;
+}
+
+impl<ArtCan, BCan, B> BuildHooks<ArtCan, BCan, Blocking> for B
+	where
+		ArtCan: Debug,
+		BCan: CanStrong,
+		B: ?Sized + Builder<ArtCan, BCan> {
+
+	type Artifact = B::Artifact;
+	type DynState = B::DynState;
+	type Err = B::Err;
+
+	fn init_dyn_state(&self) -> Self::DynState {
+		Builder::init_dyn_state(self)
+	}
+
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err> {
+		Builder::policy(self)
+	}
+
+	fn rebuild<AP>(cache: &mut RawCache<ArtCan, BCan>, promise: &AP) -> bool
+			where
+				ArtCan: CanSized<Self::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = Self, BCan = BCan> {
+
+		cache.build(promise).is_ok()
+	}
+}
+
+impl<ArtCan, BCan, B> BuildHooks<ArtCan, BCan, NonBlocking> for B
+	where
+		ArtCan: Debug,
+		BCan: CanStrong,
+		B: ?Sized + AsyncBuilder<ArtCan, BCan> {
+
+	type Artifact = B::Artifact;
+	type DynState = B::DynState;
+	type Err = B::Err;
+
+	fn init_dyn_state(&self) -> Self::DynState {
+		AsyncBuilder::init_dyn_state(self)
+	}
+
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err> {
+		AsyncBuilder::policy(self)
+	}
+
+	fn rebuild<AP>(_cache: &mut RawCache<ArtCan, BCan>, _promise: &AP) -> bool
+			where
+				ArtCan: CanSized<Self::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = Self, BCan = BCan> {
+
+		// The artifact can only be built by awaiting it, thus its dependents
+		// are rebuilt instead
+		false
+	}
+}
@@ -0,0 +1,105 @@
+//!
+//! Watched input files of artifacts.
+//!
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+
+
+/// The state of a file, as watched by a `Cache`.
+///
+/// A `FileStamp` records the modification time and a hash of the contents
+/// of a file. It is registered via [`Resolver::watch_file`] by the Builder
+/// which read the file, so that [`Cache::refresh_inputs`] can invalidate
+/// the Artifact of that Builder when the file changes.
+///
+/// [`Resolver::watch_file`]: struct.Resolver.html#method.watch_file
+/// [`Cache::refresh_inputs`]: struct.Cache.html#method.refresh_inputs
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStamp {
+	path: PathBuf,
+	modified: Option<SystemTime>,
+	hash: u64,
+}
+
+impl FileStamp {
+	/// Reads the given file, returning its stamp along with its contents.
+	///
+	/// The modification time is taken before reading, thus a concurrent
+	/// modification is detected by the stamp later on.
+	///
+	pub(crate) fn read(path: PathBuf) -> io::Result<(Self, Vec<u8>)> {
+		let modified = fs::metadata(&path)?.modified().ok();
+		let contents = fs::read(&path)?;
+
+		let stamp = FileStamp {
+			path,
+			modified,
+			hash: hash_contents(&contents),
+		};
+
+		Ok((stamp, contents))
+	}
+
+	/// Returns the path of the file.
+	///
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Returns the modification time of the file, if the platform supports
+	/// it.
+	///
+	pub fn modified(&self) -> Option<SystemTime> {
+		self.modified
+	}
+
+	/// Returns the hash of the contents of the file.
+	///
+	pub fn hash(&self) -> u64 {
+		self.hash
+	}
+
+	/// Tests whether the contents of the file changed since this stamp was
+	/// taken.
+	///
+	/// The file is only read, if its modification time changed. If its
+	/// contents turn out to be unchanged, this stamp takes the new
+	/// modification time, so that the file is not read again until it is
+	/// modified again. A file which can no longer be read counts as changed.
+	///
+	pub fn has_changed(&mut self) -> bool {
+		let modified = fs::metadata(&self.path)
+			.and_then(|meta| meta.modified())
+			.ok();
+
+		if modified.is_some() && modified == self.modified {
+			return false;
+		}
+
+		match fs::read(&self.path) {
+			Ok(contents) if hash_contents(&contents) == self.hash => {
+				self.modified = modified;
+
+				false
+			}
+			_ => true,
+		}
+	}
+}
+
+/// Hashes the contents of a file for its `FileStamp`.
+///
+fn hash_contents(contents: &[u8]) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	contents.hash(&mut hasher);
+	hasher.finish()
+}
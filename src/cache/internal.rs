@@ -6,9 +6,14 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Debug;
+use std::future::Future;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::marker::PhantomData;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
 
 use cfg_if::cfg_if;
 
@@ -19,11 +24,46 @@ use crate::Can;
 use crate::CanRefMut;
 
 use crate::Promise;
+use crate::blueprint::CannedAccessor;
 
 use crate::Builder;
+use crate::AsyncBuilder;
 use crate::BuilderId;
 
 use super::Resolver;
+use super::hooks::Blocking;
+use super::hooks::BuildHooks;
+use super::hooks::NonBlocking;
+use super::graph::DependencyGraph;
+use super::eviction::Eviction;
+use super::clock::Clock;
+use super::clock::SystemClock;
+use super::cutoff::Revisions;
+use super::cancel::CancellationToken;
+use super::version::ArtifactVersion;
+use super::listener::Listeners;
+use super::listener::ListenerId;
+use super::listener::DropReason;
+
+cfg_if! {
+	if #[cfg(feature = "persistence")] {
+		use std::io;
+		use std::path::Path;
+
+		use crate::persistence::Persisted;
+		use crate::persistence::Persistence;
+		use crate::Never;
+	}
+}
+use super::BuilderInfo;
+use super::CancelledError;
+use super::CycleError;
+use super::FileStamp;
+use super::Interrupt;
+use super::ResolveError;
+use super::TimeoutError;
+use super::Snapshot;
+use super::SnapshotEntry;
 
 
 
@@ -80,6 +120,269 @@ impl<BCan: CanStrong> fmt::Pointer for BuilderEntry<BCan> {
 
 
 
+/// A cache consulted by a `RawCache` before building any artifact.
+///
+/// This allows a `RawCache` to act as a worker of another cache, such as the
+/// `SyncCache`, where the artifacts are built by the worker, but shared
+/// through the upstream cache.
+///
+pub(crate) trait Upstream<ArtCan, BCan: CanStrong> {
+	/// Returns the artifact of the given builder, if the upstream has it.
+	///
+	/// Otherwise, the builder gets claimed for building by the caller, who
+	/// must `release` it afterwards. Together with the claim, the upstream
+	/// hands over its dyn state of the builder, if it has any.
+	///
+	/// Returns an error, if waiting for the artifact would form a dependency
+	/// cycle.
+	///
+	fn acquire(&self, builder: BuilderInfo) -> Result<Acquired<ArtCan>, CycleError>;
+
+	/// Releases a claimed builder.
+	///
+	/// If the artifact has been built successfully, it is given as `built`.
+	/// Also, any dyn state handed over by `acquire` is returned.
+	///
+	fn release(
+		&self,
+		builder: BuilderId,
+		built: Option<Built<'_, ArtCan, BCan>>,
+		dyn_state: Option<Box<dyn Any + Send>>,
+	);
+
+	/// Stops providing the artifact of the given builder, because it has
+	/// been invalidated by the caller.
+	///
+	/// Returns the dependents of the builder known only to the upstream,
+	/// which have to be invalidated by the caller too.
+	///
+	fn detach(&self, _builder: BuilderId) -> Vec<BuilderId> {
+		Vec::new()
+	}
+
+	/// Tells whether the upstream keeps the dyn states returned by
+	/// `release`, thus the caller should hand over its own ones too.
+	///
+	fn keeps_dyn_states(&self) -> bool {
+		false
+	}
+
+	/// Tells whether the given artifact, as returned by `acquire`, is still
+	/// the valid artifact of the given builder in the upstream.
+	///
+	/// The caller drops an outdated artifact together with its dependents,
+	/// and acquires the builder anew.
+	///
+	fn is_current(&self, _builder: BuilderId, _artifact: &ArtCan) -> bool {
+		true
+	}
+
+	/// Returns this upstream as `Any`, to access its concrete type.
+	///
+	fn as_any(&self) -> &dyn Any;
+}
+
+/// Result of `Upstream::acquire`.
+///
+pub(crate) enum Acquired<ArtCan> {
+	/// The upstream has the artifact.
+	Artifact(ArtCan),
+	/// The builder is claimed, with the dyn state handed over if any.
+	Claimed(Option<Box<dyn Any + Send>>),
+}
+
+/// A successfully built artifact as given to `Upstream::release`.
+///
+pub(crate) struct Built<'a, ArtCan, BCan: CanStrong> {
+	/// The new artifact.
+	pub(crate) artifact: &'a ArtCan,
+	/// The weak can of its builder.
+	pub(crate) builder: &'a BCan::CanWeak,
+	/// The direct dependencies of the artifact, with the weak can of their
+	/// builders and the artifacts that have been used, if any.
+	pub(crate) dependencies: Vec<(BuilderId, &'a BCan::CanWeak, Option<&'a ArtCan>)>,
+}
+
+/// A claim of a builder at an `Upstream`.
+///
+/// The claim is released when dropped, so that a panicking build does not
+/// block the builder forever.
+///
+struct Claim<ArtCan, BCan: CanStrong> {
+	upstream: Option<Rc<dyn Upstream<ArtCan, BCan>>>,
+	builder: BuilderId,
+}
+
+impl<ArtCan, BCan: CanStrong> Claim<ArtCan, BCan> {
+	/// Releases the claim, see `Upstream::release`.
+	///
+	fn release(
+			mut self,
+			built: Option<Built<'_, ArtCan, BCan>>,
+			dyn_state: Option<Box<dyn Any + Send>>,
+		) {
+
+		if let Some(upstream) = self.upstream.take() {
+			upstream.release(self.builder, built, dyn_state);
+		}
+	}
+}
+
+impl<ArtCan, BCan: CanStrong> Drop for Claim<ArtCan, BCan> {
+	fn drop(&mut self) {
+		if let Some(upstream) = self.upstream.take() {
+			upstream.release(self.builder, None, None);
+		}
+	}
+}
+
+/// A running build of a `RawCache`, giving access to the cache meanwhile.
+///
+/// The builder is on the build stack of the cache as long as the frame
+/// exists. If the build is aborted, e.g. by a panicking builder or a dropped
+/// future, the frame takes the builder from the build stack and releases its
+/// claim when dropped.
+///
+struct BuildFrame<'a, ArtCan, BCan: CanStrong> {
+	cache: Option<&'a mut RawCache<ArtCan, BCan>>,
+	claim: Option<Claim<ArtCan, BCan>>,
+	/// The previous artifact, if it is dirty.
+	old: Option<ArtCan>,
+	/// The failed resolution of the enclosing build, restored when finished.
+	outer_failed: Option<BuilderId>,
+}
+
+impl<'a, ArtCan, BCan: CanStrong> BuildFrame<'a, ArtCan, BCan> {
+	/// Marks the given builder as building, until the frame is finished.
+	///
+	fn new(
+			cache: &'a mut RawCache<ArtCan, BCan>,
+			builder: BuilderInfo,
+			claim: Claim<ArtCan, BCan>,
+			old: Option<ArtCan>,
+		) -> Self {
+
+		cache.build_stack.push(builder);
+		let outer_failed = cache.failed.take();
+
+		BuildFrame {
+			cache: Some(cache),
+			claim: Some(claim),
+			old,
+			outer_failed,
+		}
+	}
+
+	/// Ends the build, returning the cache, the claim and the previous
+	/// artifact to finish it.
+	///
+	fn finish(mut self)
+			-> (&'a mut RawCache<ArtCan, BCan>, Claim<ArtCan, BCan>, Option<ArtCan>) {
+
+		// Just unwrap, since both are only taken here or when dropped
+		let cache = self.cache.take().unwrap();
+		let claim = self.claim.take().unwrap();
+
+		let top = cache.build_stack.pop();
+		debug_assert_eq!(top.map(|info| info.id()), Some(claim.builder));
+		cache.failed = self.outer_failed;
+
+		(cache, claim, self.old.take())
+	}
+}
+
+impl<ArtCan, BCan: CanStrong> std::ops::Deref for BuildFrame<'_, ArtCan, BCan> {
+	type Target = RawCache<ArtCan, BCan>;
+
+	fn deref(&self) -> &Self::Target {
+		self.cache.as_ref().expect("Used a finished build frame")
+	}
+}
+
+impl<ArtCan, BCan: CanStrong> std::ops::DerefMut for BuildFrame<'_, ArtCan, BCan> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.cache.as_mut().expect("Used a finished build frame")
+	}
+}
+
+impl<ArtCan, BCan: CanStrong> Drop for BuildFrame<'_, ArtCan, BCan> {
+	fn drop(&mut self) {
+		if let (Some(cache), Some(claim)) = (self.cache.take(), self.claim.take()) {
+			let top = cache.build_stack.pop();
+			debug_assert_eq!(top.map(|info| info.id()), Some(claim.builder));
+			cache.failed = self.outer_failed;
+
+			// Hand the dyn state back, if it was borrowed
+			let dyn_state = cache.borrowed_dyn_states.remove(&claim.builder);
+			claim.release(None, dyn_state);
+		}
+	}
+}
+
+/// The outcome of `RawCache::begin_build`.
+///
+enum Begun<'a, ArtCan, BCan: CanStrong> {
+	/// The artifact is available without building it.
+	Done(&'a mut ArtCan),
+	/// The artifact has to be built within the given frame.
+	Building(BuildFrame<'a, ArtCan, BCan>),
+}
+
+/// Awaits the given future, catching any interrupt raised while polling
+/// it, see `Interrupt`.
+///
+async fn interruptible<T, E, F>(future: F) -> Result<T, ResolveError<E>>
+		where F: Future<Output = Result<T, ResolveError<E>>> {
+
+	let mut future = Box::pin(future);
+
+	std::future::poll_fn(|cx| {
+		match Interrupt::catch(|| future.as_mut().poll(cx)) {
+			Ok(poll) => poll,
+			Err(interrupt) => std::task::Poll::Ready(Err(interrupt.into())),
+		}
+	}).await
+}
+
+
+
+/// A type-erased dyn state.
+///
+type AnyDynState = Box<dyn Any>;
+
+/// Copies the dyn state of the builder given by its can for a forked cache,
+/// if possible, see `BuildPolicy::fork_dyn_state`.
+///
+type ForkDynState<ArtCan, BCan> = fn(&RawCache<ArtCan, BCan>, BCan) -> Option<AnyDynState>;
+
+/// Type-erased functions operating on the builder given by its can.
+///
+/// These are instantiated for the concrete promise type of a builder when
+/// it is built, so that the builder can be used later on by its id.
+///
+struct Handlers<ArtCan, BCan: CanStrong> {
+	/// Rebuilds the artifact, as used by the early cutoff.
+	///
+	/// Returns whether the artifact has been built successfully.
+	///
+	rebuild: fn(&mut RawCache<ArtCan, BCan>, BCan) -> bool,
+
+	/// Asks the builder whether its artifact is stale, see
+	/// `BuildPolicy::is_stale`.
+	///
+	is_stale: fn(&RawCache<ArtCan, BCan>, BCan) -> bool,
+}
+
+impl<ArtCan, BCan: CanStrong> Clone for Handlers<ArtCan, BCan> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<ArtCan, BCan: CanStrong> Copy for Handlers<ArtCan, BCan> {}
+
+
+
 /// The raw cache. Only for internal use.
 ///
 /// This struct is used by the "outer" Cache and Resolver.
@@ -102,44 +405,141 @@ pub(crate) struct RawCache<
 
 	/// Maps builder id to their DynState value.
 	///
-	dyn_states: HashMap<BuilderId, Box<dyn Any>>,
+	/// These are shared with the forks of this cache, until either changes
+	/// them, see `unshare_dyn_state`.
+	///
+	dyn_states: HashMap<BuilderId, Rc<dyn Any>>,
 
-	/// Tracks the set of direct depending builders of each builder, by id.
+	/// Maps builder id to the DynState value handed over by the upstream
+	/// cache for the duration of its build.
+	///
+	/// These take precedence over `dyn_states`.
 	///
-	/// A dependent builder is one that requires the former's artifact to
-	/// produce its own. This maps for each builder (key), which other
-	/// builders (value) depend on it. I.e. it maps what artifacts needs to be
-	/// invalidate if the former one becomes invalid.
+	borrowed_dyn_states: HashMap<BuilderId, Box<dyn Any + Send>>,
+
+	/// Tracks the dependencies between the builders.
 	///
-	/// A reverse mapping is provided via `dependencies`. Both must be kept in
-	/// sync.
+	graph: DependencyGraph,
+
+	/// Keeps a weak reference to all known builders that are those which are
+	/// used as builder id in any other mapping.
 	///
-	dependents: HashMap<BuilderId, HashSet<BuilderId>>,
+	known_builders: HashMap<BuilderId, <BCan as CanStrong>::CanWeak>,
 
-	/// Tracks the set of direct dependencies of any builders, by id.
+	/// Maps builder id to the type name of the builder, for all known
+	/// builders.
 	///
-	/// A dependency is a requirement for an builders artifact. This maps for
-	/// each builder (key), which other builders (value) have been used to
-	/// produce the former's artifact. I.e. it maps what dependencies must be
-	/// removed if the former one becomes invalid (because that it does no
-	/// longer depend on it).
+	type_names: HashMap<BuilderId, &'static str>,
+
+	/// The stack of builders which are currently building their artifact.
 	///
-	/// This is the reverse of `dependents`. Both must be kept in sync.
+	/// The last entry is the innermost build. A builder that is resolved
+	/// while being on this stack forms a dependency cycle.
 	///
-	dependencies: HashMap<BuilderId, HashSet<BuilderId>>,
+	build_stack: Vec<BuilderInfo>,
 
-	/// Tracks all builder id of builders which (yet) have no dependents.
+	/// Maps the builders, whose latest build failed, to the dependency whose
+	/// failed resolution preceded it, if any.
 	///
-	/// This list is an heuristic optimization structure for the garbage collection.
-	/// It is the list of builders to be checked by the garbage collection, reducing the amount
-	/// of checks required.
+	/// This traces an error back to the builder it originates from.
 	///
-	known_leaf_builder: HashSet<BuilderId>,
+	failures: HashMap<BuilderId, Option<BuilderId>>,
 
-	/// Keeps a weak reference to all known builders that are those which are
-	/// used as builder id in any other mapping.
+	/// The builder, whose resolution failed last within the innermost build.
 	///
-	known_builders: HashMap<BuilderId, <BCan as CanStrong>::CanWeak>,
+	failed: Option<BuilderId>,
+
+	/// The cache to be consulted before building any artifact, if any.
+	///
+	upstream: Option<Rc<dyn Upstream<ArtCan, BCan>>>,
+
+	/// The builders whose artifacts have been taken from the upstream.
+	///
+	/// These are checked against the upstream on each access, see `expire`.
+	///
+	acquired: HashSet<BuilderId>,
+
+	/// Tracks the usage of the artifacts, to evict the least recently used
+	/// ones when exceeding the capacity.
+	///
+	/// This is a `RefCell`, because looking up an artifact is a use too.
+	///
+	eviction: RefCell<Eviction>,
+
+	/// Maps builder id to the point in time when its artifact expires.
+	///
+	/// An artifact expires when either its own time to live is over, or any
+	/// of its dependencies expires.
+	///
+	expiry: HashMap<BuilderId, Instant>,
+
+	/// The source of the current time for the expiry.
+	///
+	clock: Rc<dyn Clock>,
+
+	/// Tracks the revisions of the artifacts for the early cutoff.
+	///
+	revisions: Revisions,
+
+	/// Maps builder id to its dirty artifact and its size, if any.
+	///
+	/// With the early cutoff, invalidated artifacts are moved here instead of
+	/// being dropped, because they might still be valid, or they are compared
+	/// to their rebuilt successors.
+	///
+	stale: HashMap<BuilderId, Option<(ArtCan, usize)>>,
+
+	/// Maps builder id to the type-erased functions of its builder.
+	///
+	handlers: HashMap<BuilderId, Handlers<ArtCan, BCan>>,
+
+	/// Maps builder id to the function copying its dyn state for a forked
+	/// cache, or to `None` if the builder does not copy its dyn states.
+	///
+	/// Unlike the `handlers`, this is registered along with the dyn state,
+	/// even if the builder is never built.
+	///
+	dyn_state_forks: HashMap<BuilderId, Option<ForkDynState<ArtCan, BCan>>>,
+
+	/// The number of builds which ran so far.
+	///
+	builds: usize,
+
+	/// Maps builder id to the cached error of its last build.
+	///
+	/// The errors are only cached if the builder opts in, see
+	/// `BuildPolicy::cached_error`.
+	///
+	errors: HashMap<BuilderId, Box<dyn Any>>,
+
+	/// Maps builder id to the files watched by its artifact.
+	///
+	inputs: HashMap<BuilderId, Vec<FileStamp>>,
+
+	/// The token to cancel the current builds, if any.
+	///
+	cancellation: Option<CancellationToken>,
+
+	/// The point in time after which no build may be started, if any.
+	///
+	deadline: Option<Instant>,
+
+	/// Maps builder id to the version of its artifact, including dirty ones.
+	///
+	versions: HashMap<BuilderId, ArtifactVersion>,
+
+	/// The version given to the latest artifact.
+	///
+	last_version: u64,
+
+	/// The listeners to notify about dropped artifacts.
+	///
+	listeners: Listeners,
+
+	/// Tracks the persistent artifacts and stores them on disk.
+	///
+	#[cfg(feature = "persistence")]
+	persisted: Persisted,
 
 	/// The doctor for error diagnostics.
 	#[cfg(feature = "diagnostics")]
@@ -160,7 +560,7 @@ cfg_if! {
 
 			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 				write!(f, "Cache {{ cache: {:?}, dependents: {:?}, doctor: {:?}, ... }}",
-					self.artifacts, self.dependents, self.doctor)
+					self.artifacts, self.graph.dependents, self.doctor)
 			}
 		}
 
@@ -175,10 +575,32 @@ cfg_if! {
 				Self {
 					artifacts: HashMap::new(),
 					dyn_states: HashMap::new(),
-					dependents: HashMap::new(),
-					dependencies: HashMap::new(),
+					borrowed_dyn_states: HashMap::new(),
+					graph: DependencyGraph::new(),
 					known_builders: HashMap::new(),
-					known_leaf_builder: HashSet::new(),
+					type_names: HashMap::new(),
+					build_stack: Vec::new(),
+					failures: HashMap::new(),
+					failed: None,
+					upstream: None,
+					acquired: HashSet::new(),
+					eviction: RefCell::new(Eviction::new()),
+					expiry: HashMap::new(),
+					clock: Rc::new(SystemClock),
+					revisions: Revisions::new(),
+					stale: HashMap::new(),
+					handlers: HashMap::new(),
+					dyn_state_forks: HashMap::new(),
+					errors: HashMap::new(),
+					inputs: HashMap::new(),
+					cancellation: None,
+					deadline: None,
+					versions: HashMap::new(),
+					last_version: 0,
+					listeners: Listeners::new(),
+					builds: 0,
+					#[cfg(feature = "persistence")]
+					persisted: Persisted::new(),
 
 					doctor,
 				}
@@ -193,7 +615,7 @@ cfg_if! {
 
 			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 				write!(f, "Cache {{ cache: {:?}, dependents: {:?}, ... }}",
-					self.artifacts, self.dependents)
+					self.artifacts, self.graph.dependents)
 			}
 		}
 
@@ -206,10 +628,32 @@ cfg_if! {
 				Self {
 					artifacts: HashMap::new(),
 					dyn_states: HashMap::new(),
-					dependents: HashMap::new(),
-					dependencies: HashMap::new(),
+					borrowed_dyn_states: HashMap::new(),
+					graph: DependencyGraph::new(),
 					known_builders: HashMap::new(),
-					known_leaf_builder: HashSet::new(),
+					type_names: HashMap::new(),
+					build_stack: Vec::new(),
+					failures: HashMap::new(),
+					failed: None,
+					upstream: None,
+					acquired: HashSet::new(),
+					eviction: RefCell::new(Eviction::new()),
+					expiry: HashMap::new(),
+					clock: Rc::new(SystemClock),
+					revisions: Revisions::new(),
+					stale: HashMap::new(),
+					handlers: HashMap::new(),
+					dyn_state_forks: HashMap::new(),
+					errors: HashMap::new(),
+					inputs: HashMap::new(),
+					cancellation: None,
+					deadline: None,
+					versions: HashMap::new(),
+					last_version: 0,
+					listeners: Listeners::new(),
+					builds: 0,
+					#[cfg(feature = "persistence")]
+					persisted: Persisted::new(),
 				}
 			}
 		}
@@ -221,6 +665,18 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 			ArtCan: Debug,
 			BCan: CanStrong {
 
+	/// Sets the cache to be consulted before building any artifact.
+	///
+	pub(crate) fn set_upstream(&mut self, upstream: Rc<dyn Upstream<ArtCan, BCan>>) {
+		self.upstream = Some(upstream);
+	}
+
+	/// Returns the cache to be consulted before building any artifact.
+	///
+	pub(crate) fn upstream(&self) -> Option<&dyn Upstream<ArtCan, BCan>> {
+		self.upstream.as_deref()
+	}
+
 	/// Record the dependency of `user` upon `promise`.
 	///
 	/// The `user` must be already listed in `known_builders`.
@@ -242,18 +698,8 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 		debug_assert!(self.is_builder_known_by_id(user.id()),
 			"Tracking dependency for unknown builder");
 
-		// Map dependents (`promise` has new dependent `user`)
-		self.dependents.entry(promise.id())
-			.or_insert_with(HashSet::new)
-			.insert(user.id());
-
-		// Unmark the promise as leaf, since it has now at least one depenency
-		self.known_leaf_builder.remove(&promise.id());
-
-		// Revers mapping (`user` depends on `promise`)
-		self.dependencies.entry(user.id())
-			.or_insert_with(HashSet::new)
-			.insert(promise.id());
+		// Record that `user` depends on `promise`
+		self.graph.add_dependency(user.id(), promise.id());
 
 		// Diagnostics
 		#[cfg(feature = "diagnostics")]
@@ -278,6 +724,7 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				AP: Promise<BCan = BCan> {
 
 		self.artifacts.contains_key(&promise.id())
+			&& !self.is_expired(promise.id())
 	}
 
 	/// Tests whether the artifact or dyn state of the given builder is
@@ -319,7 +766,13 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				AP: Promise<Builder = B, BCan = BCan>  {
 
 
+		if self.is_expired(promise.id()) {
+			return None;
+		}
+
 		// Get the artifact from the hash map ensuring integrity
+		self.touch(promise.id());
+
 		self.artifacts.get(&promise.id()).map(
 			|ent| {
 				// Ensure that the builder to the artifact is known
@@ -346,7 +799,13 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				AP: Promise<Builder = B, BCan = BCan>  {
 
 
+		if self.is_expired(promise.id()) {
+			return None;
+		}
+
 		// Get the artifact from the hash map ensuring integrity
+		self.touch(promise.id());
+
 		self.artifacts.get(&promise.id()).map(
 			|ent| {
 				// Ensure that the builder to the artifact is known
@@ -364,6 +823,7 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 	///
 	/// Unstable!
 	///
+	#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
 	pub(crate) fn lookup_mut<AP, B: ?Sized>(
 			&mut self,
 			promise: &AP
@@ -376,6 +836,8 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 
 		let id = promise.id();
 
+		self.expire();
+
 		// Since the user chose to use `mut` instead of `ref` he intends to
 		// modify the artifact consequently invalidating all dependent builders
 		// TODO reconsider where the automatic invalidation is such a good idea
@@ -389,6 +851,8 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 		);
 
 		// Get the artifact from the hash map ensuring integrity
+		self.touch(id);
+
 		self.artifacts.get_mut(&id).map(
 			|ent| {
 				// Ensure value type
@@ -417,124 +881,811 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 	}
 
 
-	/// Build and insert the artifact for `promise`.
-	///
-	/// This is an internal function.
+	/// Prepares building the artifact for `promise`.
 	///
 	/// There must be no artifact in cache for the given builder.
 	///
-	fn build<AP, B: ?Sized>(
+	/// Returns `None`, if the upstream provided the artifact, which has been
+	/// inserted then. Otherwise, the returned claim must be passed to a
+	/// `BuildFrame`, which marks the builder as building.
+	///
+	/// Returns an interrupt, if the builder is already building, or if the
+	/// builds have been cancelled or are overdue.
+	///
+	fn start_build<AP>(
 			&mut self,
 			promise: &AP
-		) -> Result<&mut ArtCan, B::Err>
+		) -> Result<Option<Claim<ArtCan, BCan>>, Interrupt>
 			where
-				B: Builder<ArtCan, BCan>,
-				ArtCan: CanSized<B::Artifact>,
 				BCan: Can<AP::Builder>,
-				AP: Promise<Builder = B, BCan = BCan>  {
+				AP: Promise<BCan = BCan>  {
 
 		// Ensure that there yet is no artifact for that builder in cache
 		debug_assert!(!self.contains_artifact(promise));
 
+		// Except for an expired one, which is invalid now
+		if self.artifacts.contains_key(&promise.id()) {
+			self.invalidate_by_id(&promise.id(), DropReason::Expired);
+		}
+
+		// Building a builder which is already being built would recurse
+		// endlessly.
+		if let Some(cycle) = self.find_cycle(promise.id()) {
+			return Err(cycle.into());
+		}
+
+		// No new build starts once the builds have been cancelled or once
+		// their deadline has passed
+		if self.is_cancelled() {
+			return Err(CancelledError::new(BuilderInfo::new(promise)).into());
+		}
+		if self.is_overdue() {
+			return Err(TimeoutError::new(BuilderInfo::new(promise)).into());
+		}
+
 		// Ensure that the promise is known, because we will add its dynamic
 		// state & (possibly) its artifact.
 		self.make_builder_known(promise);
 
-		// Ensure there is a DynState
-		self.ensure_dyn_state(promise);
+		// keep the id
+		let id = promise.id();
 
-		// Create Resolver prerequisites
-		let ent = BuilderEntry::new(promise);
-		#[cfg(feature = "diagnostics")]
-		let diag_builder = BuilderHandle::new(promise);
+		// Any artifact taken from the upstream is replaced now
+		self.acquired.remove(&id);
+
+		// The upstream might already have the artifact, otherwise we have
+		// to build it on its behalf.
+		let upstream = match self.upstream.clone() {
+			Some(upstream) => {
+				match upstream.acquire(BuilderInfo::new(promise))? {
+					Acquired::Artifact(art_can) => {
+						self.artifacts.insert(id, art_can);
+						self.acquired.insert(id);
+						self.new_version(id);
+						self.revisions.record(id, false);
+
+						// The size is unknown here
+						self.eviction.get_mut().insert(id, 0);
+						self.evict(Some(id));
+
+						return Ok(None);
+					}
+					Acquired::Claimed(dyn_state) => {
+						if let Some(dyn_state) = dyn_state {
+							self.borrowed_dyn_states.insert(id, dyn_state);
+						}
 
-		// Create a temporary resolver
-		let mut resolver = Resolver {
-			user: &ent,
-			cache: self,
-			#[cfg(feature = "diagnostics")]
-			diag_builder: &diag_builder,
-			_b: PhantomData,
+						Some(upstream)
+					}
+				}
+			}
+			None => None,
 		};
 
-		// Construct the artifact
-		let art_res = promise.builder().builder.build(
-			&mut resolver,
-		);
+		// The new build watches its files anew
+		self.inputs.remove(&id);
 
-		// Add artifact to cache if it was successful, otherwise just return
-		// the error
-		art_res.map(move |art_bin| {
+		Ok(Some(Claim {
+			upstream,
+			builder: id,
+		}))
+	}
+
+	/// Completes building an artifact started by `start_build`, after its
+	/// `BuildFrame` has been finished.
+	///
+	/// Inserts the artifact if it was built successfully, otherwise just
+	/// returns the error. The `size` and `ttl` are the size and the time to
+	/// live of the artifact as reported by its builder.
+	///
+	fn finish_build<T, E>(
+			&mut self,
+			claim: Claim<ArtCan, BCan>,
+			#[cfg(feature = "diagnostics")]
+			diag_builder: &BuilderHandle<BCan>,
+			art_res: Result<<ArtCan as Can<T>>::Bin, E>,
+			size: usize,
+			ttl: Option<Duration>,
+		) -> Result<&mut ArtCan, E>
+			where
+				T: Debug + 'static,
+				ArtCan: CanSized<T>  {
 
-			// diagnostics
-			cfg_if!(
-				if #[cfg(feature = "diagnostics")] {
-					let handle = ArtifactHandle::new(art_bin);
+		let id = claim.builder;
 
-					// Update doctor on diagnostics mode
-					self.doctor.build(&diag_builder, &handle);
+		// Hand the dyn state back, if it was borrowed
+		let dyn_state = self.borrowed_dyn_states.remove(&id);
 
-					let art_can = handle.into_inner();
+		// Add artifact to cache if it was successful, otherwise just return
+		// the error
+		match art_res {
+			Ok(art_bin) => {
+				// diagnostics
+				cfg_if!(
+					if #[cfg(feature = "diagnostics")] {
+						let handle = ArtifactHandle::new(art_bin);
+
+						// Update doctor on diagnostics mode
+						self.doctor.build(diag_builder, &handle);
+
+						let art_can = handle.into_inner();
+					} else {
+						let art_can = ArtCan::from_bin(art_bin);
+					}
+				);
+
+				// Insert/Replace artifact
+				self.artifacts.insert(
+					id,
+					art_can,
+				);
+				//.expect_none("Built an artifact while it was still in cache");
+				self.new_version(id);
+
+				// Make room for the new artifact
+				self.eviction.get_mut().insert(id, size);
+				self.evict(Some(id));
+
+				// The artifact expires no later than its dependencies
+				let own_expiry = ttl.map(|ttl| self.clock.now() + ttl);
+				let expiry = self.graph.dependencies_of(id)
+					.filter_map(|dep| self.expiry.get(&dep).copied())
+					.chain(own_expiry)
+					.min();
+
+				if let Some(expiry) = expiry {
+					self.expiry.insert(id, expiry);
 				} else {
-					let art_can = ArtCan::from_bin(art_bin);
+					self.expiry.remove(&id);
 				}
-			);
 
-			// keep the id
-			let id = promise.id();
-
-			// Insert/Replace artifact
-			self.artifacts.insert(
-				id,
-				art_can,
-			);
-			//.expect_none("Built an artifact while it was still in cache");
+				// Share the artifact with the upstream
+				claim.release(Some(self.built(id)), dyn_state);
 
-			// Just unwrap, since we just inserted it
-			self.artifacts.get_mut(&id).unwrap()
-		})
+				// Just unwrap, since we just inserted it
+				Ok(self.artifacts.get_mut(&id).unwrap())
+			}
+			Err(err) => {
+				claim.release(None, dyn_state);
 
+				Err(err)
+			}
+		}
 	}
 
-
-	/// Gets the bin with the artifact of the given builder.
+	/// Starts building the artifact for `promise`, as common to `build` and
+	/// `build_async`.
 	///
-	pub(crate) fn get<AP, B: ?Sized>(
+	/// There must be no artifact in cache for the given builder.
+	///
+	/// Returns the artifact, if the upstream provided it or if it has been
+	/// restored. Otherwise, returns the frame of the build, which has to be
+	/// passed to `end_build` along with the newly built artifact.
+	///
+	/// Returns an interrupt instead, if the build must not start, see
+	/// `start_build`.
+	///
+	fn begin_build<AP, B: ?Sized, K>(
 			&mut self,
 			promise: &AP
-		) -> Result<ArtCan::Bin, B::Err>
+		) -> Result<Begun<'_, ArtCan, BCan>, Interrupt>
 			where
-				B: Builder<ArtCan, BCan>,
+				B: BuildHooks<ArtCan, BCan, K>,
 				ArtCan: CanSized<B::Artifact>,
-				ArtCan: Clone,
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
+		// The previous artifact, if it is dirty
+		let old = self.take_stale(promise.id());
 
-		if let Some(art) = self.lookup(promise) {
-			Ok(art)
+		let claim = match self.start_build(promise)? {
+			Some(claim) => claim,
+			// Just unwrap, since the upstream provided it
+			None => return Ok(Begun::Done(self.artifacts.get_mut(&promise.id()).unwrap())),
+		};
 
-		} else {
-			self.build(promise).map(|art| {
-				art.clone().downcast_can()
-				.expect("Just build artifact is of invalid type")
-			})
-		}
-	}
+		// Mark the builder as building, until its build is over or aborted
+		let mut frame = BuildFrame::new(self, BuilderInfo::new(promise), claim, old);
 
-	/// Gets a reference to the artifact of the given builder.
-	///
-	pub(crate) fn get_ref<AP, B: ?Sized>(
-			&mut self,
-			promise: &AP
-		) -> Result<&B::Artifact, B::Err>
-			where
-				B: Builder<ArtCan, BCan>,
+		// Allow to use the builder by its id
+		frame.handlers.insert(promise.id(), Handlers {
+			rebuild: Self::rebuild::<AP, B, K>,
+			is_stale: Self::is_stale::<AP, B, K>,
+		});
+		frame.register_dyn_state_fork::<AP, B, K>(promise);
+
+		#[cfg(feature = "persistence")]
+		let persistence = promise.builder().builder.policy().persistence();
+
+		// Restore the stored DynState, unless there is one
+		#[cfg(feature = "persistence")]
+		frame.restore_dyn_state(promise.id(), persistence.as_ref());
+
+		// Ensure there is a DynState
+		frame.ensure_dyn_state_with(promise.id(), || {
+			promise.builder().builder.init_dyn_state()
+		});
+
+		// Hand it over to the upstream, which keeps it beyond this build
+		frame.lend_dyn_state::<AP, B, K>(promise);
+
+		// Restore the stored artifact instead of building it, if possible
+		#[cfg(feature = "persistence")]
+		{
+			if let Some(art_bin) = frame.restore_artifact(promise.id(), persistence.as_ref()) {
+				let size = promise.builder().builder.policy().artifact_size(&art_bin);
+				let ttl = promise.builder().builder.policy().time_to_live();
+
+				frame.revisions.record(promise.id(), false);
+
+				let (cache, claim, _) = frame.finish();
+
+				cfg_if! {
+					if #[cfg(feature = "diagnostics")] {
+						let res = cache.finish_build::<B::Artifact, Never>(
+							claim, &BuilderHandle::new(promise), Ok(art_bin), size, ttl);
+					} else {
+						let res = cache.finish_build::<B::Artifact, Never>(
+							claim, Ok(art_bin), size, ttl);
+					}
+				}
+
+				match res {
+					Ok(art) => return Ok(Begun::Done(art)),
+					Err(never) => match never {},
+				}
+			}
+		}
+
+		// Count the build, unlike restoring or looking up an artifact
+		frame.builds += 1;
+
+		Ok(Begun::Building(frame))
+	}
+
+	/// Ends the build started by `begin_build` with the newly built artifact
+	/// and inserts it.
+	///
+	fn end_build<'a, AP, B: ?Sized, K>(
+			frame: BuildFrame<'a, ArtCan, BCan>,
+			promise: &AP,
+			#[cfg(feature = "diagnostics")]
+			diag_builder: &BuilderHandle<BCan>,
+			mut art_res: Result<ArtCan::Bin, B::Err>,
+		) -> Result<&'a mut ArtCan, B::Err>
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				ArtCan: CanSized<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		// The failed resolution, which the error might stem from
+		let cause = frame.failed;
+
+		// The build is over, thus the builder may be resolved again
+		let (cache, claim, old) = frame.finish();
+
+		// Keep the previous artifact, if the new one is equal
+		let mut unchanged = false;
+		if let (Some(old_bin), Ok(new_bin)) = (old.and_then(|old| old.downcast_can()), &art_res) {
+			if promise.builder().builder.policy().artifacts_equal(&old_bin, new_bin) {
+				art_res = Ok(old_bin);
+				unchanged = true;
+			}
+		}
+
+		match &art_res {
+			Ok(_) => {
+				cache.revisions.record(promise.id(), unchanged);
+				cache.failures.remove(&promise.id());
+			}
+			Err(err) => {
+				// Trace the error back to its origin
+				cache.failures.insert(promise.id(), cause);
+				cache.failed = Some(promise.id());
+
+				// Cache the error, if the builder opts in, except when the build
+				// has been cancelled or is overdue, since it might succeed next
+				// time
+				if !cache.is_cancelled() && !cache.is_overdue() {
+					if let Some(err) = promise.builder().builder.policy().cached_error(err) {
+						cache.errors.insert(promise.id(), Box::new(err));
+					}
+				}
+			}
+		}
+
+		let size = art_res.as_ref()
+			.map(|bin| promise.builder().builder.policy().artifact_size(bin))
+			.unwrap_or(0);
+		let ttl = promise.builder().builder.policy().time_to_live();
+
+		// Store the artifact
+		#[cfg(feature = "persistence")]
+		{
+			if let (Some(persistence), Ok(art_bin)) = (promise.builder().builder.policy().persistence(), &art_res) {
+				cache.store_artifact(promise.id(), &persistence, art_bin);
+			}
+		}
+
+		cfg_if! {
+			if #[cfg(feature = "diagnostics")] {
+				cache.finish_build(claim, diag_builder, art_res, size, ttl)
+			} else {
+				cache.finish_build(claim, art_res, size, ttl)
+			}
+		}
+	}
+
+	/// Build and insert the artifact for `promise`.
+	///
+	/// This is an internal function.
+	///
+	/// There must be no artifact in cache for the given builder.
+	///
+	/// Returns an interrupt of the build as error too, unless the builder
+	/// raised it via a plain `resolve*` method of its `Resolver`.
+	///
+	pub(super) fn build<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&mut ArtCan, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let mut frame = match self.begin_build::<AP, B, Blocking>(promise)? {
+			Begun::Building(frame) => frame,
+			Begun::Done(art) => return Ok(art),
+		};
+
+		// Create Resolver prerequisites
+		let ent = BuilderEntry::new(promise);
+		#[cfg(feature = "diagnostics")]
+		let diag_builder = BuilderHandle::new(promise);
+
+		// Create a temporary resolver
+		let mut resolver = Resolver {
+			user: &ent,
+			cache: &mut frame,
+			#[cfg(feature = "diagnostics")]
+			diag_builder: &diag_builder,
+			_b: PhantomData,
+		};
+
+		// Construct the artifact
+		let art_res = promise.builder().builder.build(
+			&mut resolver,
+		);
+
+		Self::end_build::<AP, B, Blocking>(
+			frame,
+			promise,
+			#[cfg(feature = "diagnostics")]
+			&diag_builder,
+			art_res,
+		).map_err(ResolveError::Failed)
+	}
+
+	/// Build and insert the artifact for `promise` by an async builder.
+	///
+	/// This is the async counterpart of `build`. If the returned future is
+	/// dropped before it completes, the build is aborted by its frame.
+	///
+	async fn build_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&mut ArtCan, ResolveError<B::Err>>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let mut frame = match self.begin_build::<AP, B, NonBlocking>(promise)? {
+			Begun::Building(frame) => frame,
+			Begun::Done(art) => return Ok(art),
+		};
+
+		// Create Resolver prerequisites
+		let ent = BuilderEntry::new(promise);
+		#[cfg(feature = "diagnostics")]
+		let diag_builder = BuilderHandle::new(promise);
+
+		// Create a temporary resolver
+		let mut resolver = Resolver {
+			user: &ent,
+			cache: &mut frame,
+			#[cfg(feature = "diagnostics")]
+			diag_builder: &diag_builder,
+			_b: PhantomData,
+		};
+
+		// Construct the artifact
+		let art_res = promise.builder().builder.build(
+			&mut resolver,
+		).await;
+
+		Self::end_build::<AP, B, NonBlocking>(
+			frame,
+			promise,
+			#[cfg(feature = "diagnostics")]
+			&diag_builder,
+			art_res,
+		).map_err(ResolveError::Failed)
+	}
+
+	/// Rebuilds the artifact of the builder in the given can, see `Handlers`.
+	///
+	fn rebuild<AP, B: ?Sized, K>(&mut self, can: BCan) -> bool
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				ArtCan: CanSized<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		AP::from_canned(CannedAccessor { can })
+			.map(|promise| B::rebuild(self, &promise))
+			.unwrap_or(false)
+	}
+
+	/// Asks the builder in the given can whether its artifact is stale, see
+	/// `Handlers`.
+	///
+	fn is_stale<AP, B: ?Sized, K>(&self, can: BCan) -> bool
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		AP::from_canned(CannedAccessor { can })
+			.and_then(|promise| {
+				self.dyn_state_cast_ref(promise.id())
+					.map(|dyn_state| promise.builder().builder.policy().is_stale(dyn_state))
+			})
+			.unwrap_or(false)
+	}
+
+	/// Copies the dyn state of the builder in the given can for a forked
+	/// cache, see `dyn_state_forks`.
+	///
+	fn fork_dyn_state<AP, B: ?Sized, K>(&self, can: BCan) -> Option<AnyDynState>
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let promise = AP::from_canned(CannedAccessor { can })?;
+		let dyn_state: &B::DynState = self.dyn_state_cast_ref(promise.id())?;
+
+		// The unit type is trivially copied
+		if (dyn_state as &dyn Any).is::<()>() {
+			return Some(Box::new(()));
+		}
+
+		promise.builder().builder.policy().fork_dyn_state(dyn_state)
+			.map(|dyn_state| Box::new(dyn_state) as AnyDynState)
+	}
+
+	/// Registers whether the dyn state of the given builder can be copied
+	/// for a forked cache, see `dyn_state_forks`.
+	///
+	/// Whether a builder copies its dyn states is tested once on a new dyn
+	/// state, because it must not depend on the dyn state.
+	///
+	fn register_dyn_state_fork<AP, B: ?Sized, K>(&mut self, promise: &AP)
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.dyn_state_forks.entry(promise.id()).or_insert_with(|| {
+			let builder = promise.builder().builder;
+			let dyn_state = builder.init_dyn_state();
+
+			let forkable = (&dyn_state as &dyn Any).is::<()>()
+				|| builder.policy().fork_dyn_state(&dyn_state).is_some();
+
+			if forkable {
+				Some(Self::fork_dyn_state::<AP, B, K>)
+			} else {
+				None
+			}
+		});
+	}
+
+	/// Gives this cache its own copy of the dyn state of the given builder,
+	/// if it is shared with a fork, see `BuildPolicy::fork_dyn_state`.
+	///
+	/// If it can not be copied, the builder starts over with a new dyn
+	/// state, thus its artifact and the ones of its dependents are dropped.
+	///
+	fn unshare_dyn_state(&mut self, bid: BuilderId) {
+		let shared = self.dyn_states.get(&bid)
+			.is_some_and(|dyn_state| Rc::strong_count(dyn_state) > 1);
+
+		if !shared {
+			return;
+		}
+
+		let copy = self.dyn_state_forks.get(&bid).copied().flatten()
+			.and_then(|fork_dyn_state| {
+				let can = BCan::upgrade_from_weak(self.known_builders.get(&bid)?)?;
+				fork_dyn_state(self, can)
+			});
+
+		match copy {
+			Some(dyn_state) => {
+				self.dyn_states.insert(bid, dyn_state.into());
+			}
+			None => {
+				self.dyn_states.remove(&bid);
+				self.invalidate_by_id(&bid, DropReason::Invalidated);
+			}
+		}
+	}
+
+	/// Describes the artifact of given builder for the upstream.
+	///
+	/// The builder must have an artifact.
+	///
+	fn built(&self, bid: BuilderId) -> Built<'_, ArtCan, BCan> {
+		Built {
+			artifact: &self.artifacts[&bid],
+			builder: &self.known_builders[&bid],
+			dependencies: self.graph.dependencies_of(bid)
+				.map(|dep| {
+					(dep, &self.known_builders[&dep], self.artifacts.get(&dep))
+				})
+				.collect(),
+		}
+	}
+
+
+	/// Gets the bin with the artifact of the given builder.
+	///
+	/// This is the common implementation of all accessors. Any interrupt is
+	/// returned as error, except those raised by the builders on the way,
+	/// see `Interrupt`.
+	///
+	pub(crate) fn fetch<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if let Some(err) = self.cached_error::<AP, B, Blocking>(promise) {
+			return Err(ResolveError::Failed(err));
+		}
+
+		if let Some(art) = self.lookup(promise) {
+			Ok(art)
+
+		} else {
+			self.build(promise).map(|art| {
+				art.clone().downcast_can()
+				.expect("Just build artifact is of invalid type")
+			})
+		}
+	}
+
+	/// Gets the bin with the artifact of the given builder.
+	///
+	/// Panics on any interrupt, e.g. a dependency cycle.
+	///
+	pub(crate) fn get<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, B::Err>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.try_get(promise).map_err(ResolveError::or_panic)
+	}
+
+	/// Gets the bin with the artifact of the given builder, reporting any
+	/// interrupt as error.
+	///
+	pub(crate) fn try_get<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		Interrupt::catch(|| self.fetch(promise))
+			.unwrap_or_else(|interrupt| Err(interrupt.into()))
+	}
+
+	/// Gets the artifact of the given builder, passing the given token to all
+	/// builds on the way.
+	///
+	pub(crate) fn get_cancellable<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			token: &CancellationToken,
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let previous = self.cancellation.replace(token.clone());
+		let res = self.try_get(promise);
+		self.cancellation = previous;
+
+		res
+	}
+
+	/// Gets the artifact of the given builder, skipping all builds on the way
+	/// which would start after the given deadline.
+	///
+	pub(crate) fn get_with_deadline<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP,
+			deadline: Instant,
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		// An outer deadline can not be extended
+		let previous = self.deadline;
+		self.deadline = Some(previous.map_or(deadline, |prev| prev.min(deadline)));
+
+		let res = self.try_get(promise);
+
+		self.deadline = previous;
+
+		res
+	}
+
+	/// Returns the current time of this cache's clock.
+	///
+	pub(crate) fn now(&self) -> Instant {
+		self.clock.now()
+	}
+
+	/// Returns the time left until the deadline of the current builds, if
+	/// any.
+	///
+	pub(crate) fn remaining_budget(&self) -> Option<Duration> {
+		self.deadline.map(|deadline| {
+			deadline.saturating_duration_since(self.clock.now())
+		})
+	}
+
+	/// Tests whether the deadline of the current builds has passed.
+	///
+	fn is_overdue(&self) -> bool {
+		self.remaining_budget() == Some(Duration::from_secs(0))
+	}
+
+	/// Checks that the given builder needs no build after the deadline.
+	///
+	/// Cached artifacts and errors are fine, even after the deadline.
+	///
+	pub(crate) fn check_deadline<AP>(
+			&mut self,
+			promise: &AP
+		) -> Result<(), TimeoutError>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan>  {
+
+		if !self.is_overdue() {
+			return Ok(());
+		}
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if self.contains_artifact(promise) || self.errors.contains_key(&promise.id()) {
+			Ok(())
+		} else {
+			Err(TimeoutError::new(BuilderInfo::new(promise)))
+		}
+	}
+
+	/// Tests whether the current builds have been cancelled.
+	///
+	pub(crate) fn is_cancelled(&self) -> bool {
+		self.cancellation.as_ref()
+			.map(CancellationToken::is_cancelled)
+			.unwrap_or(false)
+	}
+
+	/// Checks that the given builder needs no build after the cancellation.
+	///
+	/// Cached artifacts and errors are fine, even after the cancellation.
+	///
+	pub(crate) fn check_cancelled<AP>(
+			&mut self,
+			promise: &AP
+		) -> Result<(), CancelledError>
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan>  {
+
+		if !self.is_cancelled() {
+			return Ok(());
+		}
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if self.contains_artifact(promise) || self.errors.contains_key(&promise.id()) {
+			Ok(())
+		} else {
+			Err(CancelledError::new(BuilderInfo::new(promise)))
+		}
+	}
+
+	/// Gets the artifact of the given builder along with its version.
+	///
+	pub(crate) fn get_versioned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<(ArtCan::Bin, ArtifactVersion), B::Err>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let bin = self.get(promise)?;
+
+		// Just index, since there is a version for each artifact
+		Ok((bin, self.versions[&promise.id()]))
+	}
+
+	/// Gets a reference to the artifact of the given builder.
+	///
+	/// This is the common implementation of the accessors by reference, see
+	/// `fetch`.
+	///
+	pub(crate) fn fetch_ref<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&B::Artifact, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
 				ArtCan: CanRef<B::Artifact>,
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
 
+		self.expire();
+		self.revalidate(promise.id());
+
+		if let Some(err) = self.cached_error::<AP, B, Blocking>(promise) {
+			return Err(ResolveError::Failed(err));
+		}
+
 		if self.lookup_ref(promise).is_some() {
 			// Here, requires a second look up because due to the build in the
 			// else case, an `if let Some(_)` won't work due to lifetime issues
@@ -548,10 +1699,47 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 		}
 	}
 
+	/// Gets a reference to the artifact of the given builder.
+	///
+	/// Panics on any interrupt, e.g. a dependency cycle.
+	///
+	pub(crate) fn get_ref<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&B::Artifact, B::Err>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanRef<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.try_get_ref(promise).map_err(ResolveError::or_panic)
+	}
+
+	/// Gets a reference to the artifact of the given builder, reporting any
+	/// interrupt as error.
+	///
+	pub(crate) fn try_get_ref<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&B::Artifact, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanRef<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		Interrupt::catch(move || self.fetch_ref(promise))
+			.unwrap_or_else(|interrupt| Err(interrupt.into()))
+	}
+
 	/// Gets a mutable reference to the artifact of the given builder.
 	///
 	/// Unstable!
 	///
+	/// Panics on any interrupt, e.g. a dependency cycle.
+	///
+	#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
 	pub(crate) fn get_mut<AP, B: ?Sized>(
 			&mut self,
 			promise: &AP
@@ -562,6 +1750,50 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
+		self.try_get_mut(promise).map_err(ResolveError::or_panic)
+	}
+
+	/// Gets a mutable reference to the artifact of the given builder,
+	/// reporting any interrupt as error.
+	///
+	/// Unstable!
+	///
+	#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
+	pub(crate) fn try_get_mut<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&mut B::Artifact, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanRefMut<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		Interrupt::catch(move || self.fetch_mut(promise))
+			.unwrap_or_else(|interrupt| Err(interrupt.into()))
+	}
+
+	/// Gets a mutable reference to the artifact of the given builder, see
+	/// `fetch`.
+	///
+	#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
+	fn fetch_mut<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<&mut B::Artifact, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				ArtCan: CanRefMut<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if let Some(err) = self.cached_error::<AP, B, Blocking>(promise) {
+			return Err(ResolveError::Failed(err));
+		}
 
 		if self.lookup_mut(promise).is_some() {
 			// Here, requires a second look up because due to the build in the
@@ -589,31 +1821,290 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				BCan: Can<AP::Builder>,
 				AP: Promise<Builder = B, BCan = BCan>  {
 
-		self.get_ref(promise).map(|art| {
-			art.clone()
+		self.get_ref(promise).cloned()
+	}
+
+	/// Get a clone of the artifact of the given builder, reporting any
+	/// interrupt as error.
+	///
+	pub(crate) fn try_get_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, ResolveError<B::Err>>
+			where
+				B: Builder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				ArtCan: CanRef<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.try_get_ref(promise).cloned()
+	}
+
+	/// Gets the bin with the artifact of the given async builder, see
+	/// `fetch`.
+	///
+	pub(crate) async fn fetch_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if let Some(err) = self.cached_error::<AP, B, NonBlocking>(promise) {
+			return Err(ResolveError::Failed(err));
+		}
+
+		if let Some(art) = self.artifacts.get(&promise.id()) {
+			self.eviction.get_mut().touch(promise.id());
+
+			Ok(art.clone().downcast_can()
+				.expect("Cached artifact is of invalid type"))
+
+		} else {
+			self.build_async(promise).await.map(|art| {
+				art.clone().downcast_can()
+				.expect("Just build artifact is of invalid type")
+			})
+		}
+	}
+
+	/// Gets a clone of the artifact of the given async builder, see `fetch`.
+	///
+	pub(crate) async fn fetch_async_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, ResolveError<B::Err>>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				ArtCan: CanRef<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.expire();
+		self.revalidate(promise.id());
+
+		if let Some(err) = self.cached_error::<AP, B, NonBlocking>(promise) {
+			return Err(ResolveError::Failed(err));
+		}
+
+		if self.artifacts.contains_key(&promise.id()) {
+			self.eviction.get_mut().touch(promise.id());
+		} else {
+			self.build_async(promise).await?;
+		}
+
+		Ok(self.artifacts[&promise.id()].downcast_can_ref()
+			.expect("Cached artifact is of invalid type")
+			.clone())
+	}
+
+	/// Gets the bin with the artifact of the given async builder.
+	///
+	/// Panics on any interrupt, e.g. a dependency cycle.
+	///
+	pub(crate) async fn get_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, B::Err>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.try_get_async(promise).await.map_err(ResolveError::or_panic)
+	}
+
+	/// Gets the bin with the artifact of the given async builder, reporting
+	/// any interrupt as error.
+	///
+	pub(crate) async fn try_get_async<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, ResolveError<B::Err>>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				ArtCan: CanSized<B::Artifact>,
+				ArtCan: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		interruptible(self.fetch_async(promise)).await
+	}
+
+	/// Gets a clone of the artifact of the given async builder.
+	///
+	/// Panics on any interrupt, e.g. a dependency cycle.
+	///
+	pub(crate) async fn get_async_cloned<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		) -> Result<B::Artifact, B::Err>
+			where
+				B: AsyncBuilder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				ArtCan: CanRef<B::Artifact>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		interruptible(self.fetch_async_cloned(promise)).await
+			.map_err(ResolveError::or_panic)
+	}
+
+
+	/// Returns a copy of the cached error of the given builder, if any.
+	///
+	fn cached_error<AP, B: ?Sized, K>(
+			&mut self,
+			promise: &AP
+		) -> Option<B::Err>
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let err = self.errors.get(&promise.id())
+			.map(|err| {
+				err.downcast_ref()
+					.expect("Cached error is of invalid type")
+			})
+			.and_then(|err| promise.builder().builder.policy().cached_error(err));
+
+		if err.is_some() {
+			self.failed = Some(promise.id());
+		}
+
+		err
+	}
+
+	/// Traces the error of the given failed builder back to its origin.
+	///
+	/// Returns the builders the error passed through, starting with the one
+	/// it originates from and ending with the given one.
+	///
+	pub(crate) fn failure_path(&self, bid: BuilderId) -> Vec<BuilderInfo> {
+		let mut path = vec![self.builder_info(bid)];
+
+		let mut next = self.failures.get(&bid).copied().flatten();
+		while let Some(cause) = next {
+			// Purged builders are unknown, and a builder occurs at most once
+			if !self.type_names.contains_key(&cause)
+					|| path.iter().any(|info| info.id() == cause) {
+				break;
+			}
+
+			path.push(self.builder_info(cause));
+			next = self.failures.get(&cause).copied().flatten();
+		}
+
+		path.reverse();
+		path
+	}
+
+	/// Ensure given dyn state exists and return it by reference.
+	///
+	fn ensure_dyn_state<AP, B: ?Sized>(
+			&mut self, promise: &AP
+		) -> &mut B::DynState
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		self.make_builder_known(promise);
+
+		// Allow to fork the dyn state, even if the builder is never built
+		self.register_dyn_state_fork::<AP, B, Blocking>(promise);
+
+		self.ensure_dyn_state_with(promise.id(), || {
+			promise.builder().builder.init_dyn_state()
 		})
 	}
 
+	/// Moves the dyn state of the given builder to the borrowed ones, if the
+	/// upstream keeps dyn states and the builder can send it, so that it is
+	/// handed to the upstream when the build is released.
+	///
+	fn lend_dyn_state<AP, B: ?Sized, K>(&mut self, promise: &AP)
+			where
+				B: BuildHooks<ArtCan, BCan, K>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan> {
+
+		let bid = promise.id();
+
+		let keeps = self.upstream.as_ref()
+			.is_some_and(|upstream| upstream.keeps_dyn_states());
+
+		if !keeps || self.borrowed_dyn_states.contains_key(&bid) {
+			return;
+		}
+
+		if let Some(dyn_state) = self.dyn_states.remove(&bid) {
+			let dyn_state = dyn_state.downcast::<B::DynState>()
+				.expect("Cached dyn state is of invalid type");
+
+			// A dyn state shared with a fork stays here
+			let dyn_state = match Rc::try_unwrap(dyn_state) {
+				Ok(dyn_state) => dyn_state,
+				Err(dyn_state) => {
+					self.dyn_states.insert(bid, dyn_state);
+					return;
+				}
+			};
+
+			match promise.builder().builder.policy().send_dyn_state(dyn_state) {
+				Ok(dyn_state) => {
+					self.borrowed_dyn_states.insert(bid, dyn_state);
+				}
+				Err(dyn_state) => {
+					self.dyn_states.insert(bid, Rc::new(dyn_state));
+				}
+			}
+		}
+	}
 
-	/// Ensure given dyn state exists and return it by reference.
+	/// Ensure the dyn state of the given known builder exists, using `init`
+	/// to create it, and return it by reference.
 	///
-	fn ensure_dyn_state<AP, B: ?Sized>(
-			&mut self, promise: &AP
-		) -> &mut B::DynState
-			where
-				B: Builder<ArtCan, BCan>,
-				BCan: Can<AP::Builder>,
-				AP: Promise<Builder = B, BCan = BCan> {
+	/// `T` must be the correct type of the dynamic state of `bid`,
+	/// or this panics.
+	///
+	fn ensure_dyn_state_with<T: 'static>(
+			&mut self,
+			bid: BuilderId,
+			init: impl FnOnce() -> T
+		) -> &mut T {
 
-		self.make_builder_known(promise);
+		if self.borrowed_dyn_states.contains_key(&bid) {
+			return self.borrowed_dyn_states.get_mut(&bid).unwrap()
+				.downcast_mut()
+				.expect("Borrowed dyn state is of invalid type");
+		}
 
-		self.dyn_states
-			.entry(promise.id())
+		// The dyn state is about to be changed
+		self.unshare_dyn_state(bid);
+
+		let dyn_state = self.dyn_states
+			.entry(bid)
 			// Access entry or insert it with builder's default
 			.or_insert_with(
-				|| Box::new(promise.builder().builder.init_dyn_state())
-			)
-			// Ensure state type, it's safe because we have the builder's AP
+				|| Rc::new(init())
+			);
+
+		Rc::get_mut(dyn_state)
+			.expect("Cached dyn state is shared")
+			// Ensure state type
 			.downcast_mut()
 			.expect("Cached dyn state is of invalid type")
 	}
@@ -640,12 +2131,19 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 			|| self.is_builder_known_by_id(bid),
 				"Found dyn state, but the builder is not known.");
 
+		if self.borrowed_dyn_states.contains_key(&bid) {
+			return self.borrowed_dyn_states.get_mut(&bid)
+				.and_then(|b| b.downcast_mut());
+		}
+
 		self.dyn_states.get_mut(&bid)
 		.map(
 			|b| {
 
 				// Ensure state type, might fail if given wrong argument
-				b.downcast_mut()
+				Rc::get_mut(b)
+					.expect("Cached dyn state is shared")
+					.downcast_mut()
 					.expect("Cached dyn state is of invalid type")
 			}
 		)
@@ -665,6 +2163,10 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 			bid: BuilderId
 		) -> Option<&T> {
 
+		if let Some(b) = self.borrowed_dyn_states.get(&bid) {
+			return b.downcast_ref();
+		}
+
 		self.dyn_states.get(&bid).map(
 			|b| {
 				// Ensure that the builder to the dyn state is known
@@ -698,164 +2200,703 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 		self.ensure_dyn_state(promise)
 	}
 
-	/// Gets the dynamic state of the given builder.
+	/// Updates the dynamic state of the given builder via the given closure.
+	///
+	/// The artifact of the builder is only invalidated, if the closure
+	/// reports a change by returning `true`.
+	///
+	pub(crate) fn update_dyn_state<AP, B: ?Sized, F>(
+			&mut self,
+			promise: &AP,
+			update: F,
+		) -> bool
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>,
+				F: FnOnce(&mut B::DynState) -> bool {
+
+		let changed = update(self.ensure_dyn_state(promise));
+
+		if changed {
+			self.invalidate(promise);
+		}
+
+		changed
+	}
+
+	/// Returns the dependency cycle, if given builder is currently building.
+	///
+	/// The returned cycle starts at the building instance of `bid` and ends
+	/// with `bid` again.
+	///
+	pub(super) fn find_cycle(&self, bid: BuilderId) -> Option<CycleError> {
+		self.build_stack.iter()
+			.position(|info| info.id() == bid)
+			.map(|pos| {
+				let mut chain = self.build_stack[pos..].to_vec();
+				chain.push(self.build_stack[pos]);
+
+				CycleError::new(chain)
+			})
+	}
+
+	/// Gets the dynamic state of the given builder.
+	///
+	pub(crate) fn dyn_state<AP, B: ?Sized>(
+			&mut self, promise: &AP
+		) -> &B::DynState
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		// Here, no invalidation, because we do not allow the user to modify the
+		// dyn state.
+
+		// Coerce to shared ref (`&`) and return
+		self.ensure_dyn_state(promise)
+	}
+
+	/// Gets the dynamic state of the given builder, as read by the building
+	/// `reader`.
+	///
+	/// The dependency of the `reader` must have been tracked already.
+	///
+	pub(super) fn read_dyn_state<AP, B: ?Sized>(
+			&mut self,
+			reader: BuilderId,
+			promise: &AP,
+		) -> &B::DynState
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		// With the early cutoff, an unchanged artifact would not cause the
+		// reader to be rebuilt
+		self.revisions.read_dyn_state(reader, promise.id());
+
+		self.ensure_dyn_state(promise)
+	}
+
+	/// Gets the dynamic state of the given builder, if it exists.
+	///
+	pub(crate) fn get_dyn_state<AP, B: ?Sized>(
+			&self, promise: &AP
+		) -> Option<&B::DynState>
+			where
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.dyn_state_cast_ref(promise.id())
+	}
+
+	/// Deletes the artifact and dynamic state of the given builder.
+	///
+	pub(crate) fn purge<AP, B: ?Sized>(
+			&mut self,
+			promise: &AP
+		)
+			where
+				B: Debug + 'static,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let bid = promise.id();
+
+		// Remove weak reference of builder since we will remove all references
+		// to it
+		self.known_builders.remove(&bid);
+		self.type_names.remove(&bid);
+
+		// Purge artifact & dyn state
+		self.remove_artifact(bid, DropReason::Purged);
+		self.dyn_states.remove(&bid);
+
+		// Invalidate dependents
+		self.invalidate_by_id(&promise.id(), DropReason::Purged);
+
+		// Forget the builder entirely
+		self.graph.remove(bid);
+		self.handlers.remove(&bid);
+		self.dyn_state_forks.remove(&bid);
+		self.failures.remove(&bid);
+		self.listeners.remove(bid);
+
+		#[cfg(feature = "persistence")]
+		self.persisted.forget(bid);
+
+		#[cfg(feature = "diagnostics")]
+		self.doctor.invalidate(&BuilderHandle::new(promise));
+	}
+
+	/// Deletes all artifacts of this cache.
+	///
+	pub(crate) fn clear_artifacts(&mut self) {
+		self.notify_cleared();
+		self.artifacts.clear();
+		self.eviction.get_mut().clear();
+		self.expiry.clear();
+		self.stale.clear();
+		self.revisions.clear();
+		self.versions.clear();
+		self.acquired.clear();
+		self.errors.clear();
+		self.inputs.clear();
+
+		#[cfg(feature = "persistence")]
+		self.persisted.clear();
+
+		// Now, all know builders are leafs!
+		self.graph.clear_dependencies(self.known_builders.keys().copied());
+	}
+
+	/// Clears the entire cache including all kept promise, artifacts and
+	/// dynamic states.
+	///
+	pub(crate) fn clear_all(&mut self) {
+		self.notify_cleared();
+		self.artifacts.clear();
+		self.eviction.get_mut().clear();
+		self.expiry.clear();
+		self.stale.clear();
+		self.revisions.clear();
+		self.handlers.clear();
+		self.dyn_state_forks.clear();
+		self.versions.clear();
+		self.acquired.clear();
+		self.errors.clear();
+		self.inputs.clear();
+		self.failures.clear();
+
+		#[cfg(feature = "persistence")]
+		self.persisted.forget_all();
+		self.dyn_states.clear();
+		self.graph.clear();
+		self.known_builders.clear();
+		self.type_names.clear();
+		self.listeners.clear();
+
+		#[cfg(feature = "diagnostics")]
+		self.doctor.clear();
+	}
+
+	/// Turns the given empty cache into a fork of this cache.
+	///
+	/// The fork shares the artifacts and the dyn states, which can be copied
+	/// once either cache changes them. Artifacts whose dyn states can not be
+	/// copied are dropped in the fork. Listeners, cached errors, the upstream and the persistence
+	/// are not forked.
+	///
+	pub(crate) fn fork_into(&self, fork: &mut RawCache<ArtCan, BCan>)
+			where
+				ArtCan: Clone,
+				BCan::CanWeak: Clone {
+
+		debug_assert!(self.build_stack.is_empty(), "Forked a cache while building");
+
+		fork.artifacts = self.artifacts.clone();
+		fork.graph = self.graph.clone();
+		fork.known_builders = self.known_builders.clone();
+		fork.type_names = self.type_names.clone();
+		*fork.eviction.get_mut() = self.eviction.borrow().clone();
+		fork.expiry = self.expiry.clone();
+		fork.clock = self.clock.clone();
+		fork.revisions = self.revisions.clone();
+		fork.stale = self.stale.clone();
+		fork.handlers = self.handlers.clone();
+		fork.dyn_state_forks = self.dyn_state_forks.clone();
+		fork.builds = self.builds;
+		fork.inputs = self.inputs.clone();
+		fork.versions = self.versions.clone();
+		fork.last_version = self.last_version;
+
+		let mut lost = Vec::new();
+
+		// The dyn states are shared until either cache changes them
+		for (&bid, dyn_state) in &self.dyn_states {
+			match self.dyn_state_forks.get(&bid) {
+				Some(Some(_)) => {
+					fork.dyn_states.insert(bid, dyn_state.clone());
+				}
+				_ => lost.push(bid),
+			}
+		}
+
+		// The artifacts might depend on the lost dyn states
+		for bid in lost {
+			fork.invalidate_by_id(&bid, DropReason::Invalidated);
+		}
+	}
+
+	/// Notifies the listeners of all artifacts, that they are cleared.
+	///
+	fn notify_cleared(&mut self) {
+		for bid in self.artifacts.keys() {
+			self.listeners.notify(*bid, DropReason::Cleared);
+		}
+	}
+
+	/// Registers a listener for the artifact of the given builder.
+	///
+	pub(crate) fn subscribe<AP: ?Sized>(
+			&mut self,
+			promise: &AP,
+			listener: Box<dyn FnMut(DropReason)>,
+		) -> ListenerId
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.listeners.subscribe(promise.id(), listener)
+	}
+
+	/// Removes the given listener.
+	///
+	pub(crate) fn unsubscribe(&mut self, listener: ListenerId) -> bool {
+		self.listeners.unsubscribe(listener)
+	}
+
+	/// Removes the artifact of the given builder, if any.
+	///
+	/// Unlike invalidating, this keeps the dependents. The listeners are
+	/// notified with the given `reason`, if there was an artifact.
+	///
+	fn remove_artifact(&mut self, bid: BuilderId, reason: DropReason) {
+		if self.artifacts.remove(&bid).is_some() {
+			self.listeners.notify(bid, reason);
+		}
+		self.eviction.get_mut().remove(bid);
+		self.expiry.remove(&bid);
+		self.stale.remove(&bid);
+		self.revisions.remove(bid);
+		self.errors.remove(&bid);
+		self.inputs.remove(&bid);
+		self.versions.remove(&bid);
+		self.acquired.remove(&bid);
+
+		#[cfg(feature = "persistence")]
+		self.persisted.remove(bid);
+	}
+
+	/// Gives the artifact of the given builder a new version.
+	///
+	fn new_version(&mut self, bid: BuilderId) {
+		self.last_version += 1;
+		self.versions.insert(bid, ArtifactVersion::new(self.last_version));
+	}
+
+	/// Tests whether the given version is the one of the valid artifact of
+	/// the given builder.
+	///
+	pub(crate) fn is_current<AP: ?Sized>(
+			&self,
+			promise: &AP,
+			version: ArtifactVersion,
+		) -> bool
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.contains_artifact(promise)
+			&& self.versions.get(&promise.id()) == Some(&version)
+	}
+
+	/// Marks the artifact of the given builder as used, if it exists.
+	///
+	fn touch(&self, bid: BuilderId) {
+		self.eviction.borrow_mut().touch(bid);
+	}
+
+	/// Evicts the least recently used artifacts until the capacity is no
+	/// longer exceeded.
+	///
+	/// The artifact of `keep` is never evicted. The evicted artifacts remain
+	/// in the dependency graph, because their dependents keep their own
+	/// artifacts, which still need to be invalidated along with them. For the
+	/// same reason, their expiry is kept.
+	///
+	fn evict(&mut self, keep: Option<BuilderId>) {
+		for bid in self.eviction.get_mut().evict(keep) {
+			if self.artifacts.remove(&bid).is_some() {
+				self.listeners.notify(bid, DropReason::Evicted);
+			}
+		}
+	}
+
+	/// Returns the maximum number of cached artifacts, if any.
+	///
+	pub(crate) fn max_artifacts(&self) -> Option<usize> {
+		self.eviction.borrow().max_artifacts()
+	}
+
+	/// Sets the maximum number of cached artifacts, evicting the least
+	/// recently used artifacts if necessary.
+	///
+	pub(crate) fn set_max_artifacts(&mut self, max: Option<usize>) {
+		self.eviction.get_mut().set_max_artifacts(max);
+		self.evict(None);
+	}
+
+	/// Returns the maximum sum of artifact sizes, if any.
+	///
+	pub(crate) fn max_size(&self) -> Option<usize> {
+		self.eviction.borrow().max_size()
+	}
+
+	/// Sets the maximum sum of artifact sizes, evicting the least recently
+	/// used artifacts if necessary.
+	///
+	pub(crate) fn set_max_size(&mut self, max: Option<usize>) {
+		self.eviction.get_mut().set_max_size(max);
+		self.evict(None);
+	}
+
+	/// Returns the sum of the sizes of all cached artifacts.
+	///
+	pub(crate) fn artifacts_size(&self) -> usize {
+		self.eviction.borrow().total_size()
+	}
+
+	/// Lets the artifact of the given builder expire immediately.
+	///
+	#[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+	fn outdate(&mut self, bid: BuilderId) {
+		let now = self.clock.now();
+		self.expiry.insert(bid, now);
+	}
+
+	/// Sets the source of the current time for the expiry of artifacts.
+	///
+	pub(crate) fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+		self.clock = clock;
+	}
+
+	/// Tests whether the artifact of the given builder has expired.
+	///
+	/// While building, the time stands still, i.e. no artifact expires
+	/// until the outermost build is over.
+	///
+	fn is_expired(&self, bid: BuilderId) -> bool {
+		self.build_stack.is_empty()
+			&& (
+				self.expiry.get(&bid)
+					.map(|expiry| *expiry <= self.clock.now())
+					.unwrap_or(false)
+				|| self.is_outdated(bid)
+			)
+	}
+
+	/// Tests whether the artifact of the given builder or of any of its
+	/// dependencies has been taken from the upstream, and is no longer
+	/// valid there.
+	///
+	fn is_outdated(&self, bid: BuilderId) -> bool {
+		let upstream = match &self.upstream {
+			Some(upstream) if !self.acquired.is_empty() => upstream,
+			_ => return false,
+		};
+
+		self.graph.dependencies_closure(bid).into_iter()
+			.filter(|dep| self.acquired.contains(dep))
+			.any(|dep| {
+				self.artifacts.get(&dep)
+					.is_some_and(|art| !upstream.is_current(dep, art))
+			})
+	}
+
+	/// Invalidates all expired artifacts including their dependents.
+	///
+	/// Also, artifacts taken from the upstream, which are no longer valid
+	/// there, are invalidated including their dependents.
+	///
+	/// This does nothing while building, see `is_expired`.
+	///
+	fn expire(&mut self) {
+		if !self.build_stack.is_empty() {
+			return;
+		}
+
+		if !self.expiry.is_empty() {
+			let now = self.clock.now();
+			let expired: Vec<_> = self.expiry.iter()
+				.filter(|(_, expiry)| **expiry <= now)
+				.map(|(bid, _)| *bid)
+				.collect();
+
+			for bid in expired {
+				self.invalidate_by_id(&bid, DropReason::Expired);
+			}
+		}
+
+		if let Some(upstream) = self.upstream.clone() {
+			let outdated: Vec<_> = self.acquired.iter()
+				.filter(|bid| {
+					self.artifacts.get(bid)
+						.is_some_and(|art| !upstream.is_current(**bid, art))
+				})
+				.copied()
+				.collect();
+
+			for bid in outdated {
+				self.invalidate_by_id(&bid, DropReason::Outdated);
+			}
+		}
+	}
+
+	/// Returns the valid artifact of the given builder, if any, to be shared
+	/// with another cache.
+	///
+	pub(crate) fn shared_artifact(&self, bid: BuilderId) -> Option<ArtCan>
+			where ArtCan: Clone {
+
+		if self.is_expired(bid) {
+			return None;
+		}
+
+		self.artifacts.get(&bid).cloned()
+	}
+
+	/// Returns the number of cached artifacts.
+	///
+	pub(crate) fn number_of_artifacts(&self) -> usize {
+		self.artifacts.len()
+	}
+
+	/// Returns the number of builds which ran so far.
+	///
+	pub(crate) fn number_of_builds(&self) -> usize {
+		self.builds
+	}
+
+	/// Auxiliary invalidation function using an untyped (aka `dyn Any`)
+	/// `BuilderId`.
+	///
+	/// The artifact of the given builder is dropped for the given `reason`,
+	/// and those of its dependents because of their dependency.
+	///
+	fn invalidate_by_id(&mut self, builder: &BuilderId, reason: DropReason) {
+		let invalidated = self.graph.invalidate(*builder);
+
+		for &bid in &invalidated {
+			if bid == *builder {
+				self.remove_artifact(bid, reason);
+			} else {
+				self.remove_artifact(bid, DropReason::Dependency);
+			}
+		}
+
+		// Expired, collected and outdated artifacts are not invalid in the
+		// upstream
+		match reason {
+			DropReason::Expired | DropReason::Collected | DropReason::Outdated => (),
+			_ => self.detach(&invalidated),
+		}
+	}
+
+	/// Stops taking the artifacts of the given invalidated builders from the
+	/// upstream, and invalidates their dependents known only to the upstream.
 	///
-	pub(crate) fn dyn_state<AP, B: ?Sized>(
-			&mut self, promise: &AP
-		) -> &B::DynState
-			where
-				B: Builder<ArtCan, BCan>,
-				BCan: Can<AP::Builder>,
-				AP: Promise<Builder = B, BCan = BCan>  {
+	fn detach(&mut self, builders: &[BuilderId]) {
+		let upstream = match self.upstream.clone() {
+			Some(upstream) => upstream,
+			None => return,
+		};
 
-		// Here, no invalidation, because we do not allow the user to modify the
-		// dyn state.
+		let dependents: Vec<_> = builders.iter()
+			.flat_map(|bid| upstream.detach(*bid))
+			.collect();
 
-		// Coerce to shared ref (`&`) and return
-		self.ensure_dyn_state(promise)
+		for bid in dependents {
+			self.invalidate_by_id(&bid, DropReason::Dependency);
+		}
 	}
 
-	/// Gets the dynamic state of the given builder, if it exists.
+	/// Auxiliary invalidation function using an untyped (aka `dyn Any`)
+	/// `BuilderId`, only invalidates dependents not the given build itself.
 	///
-	pub(crate) fn get_dyn_state<AP, B: ?Sized>(
-			&self, promise: &AP
-		) -> Option<&B::DynState>
-			where
-				B: Builder<ArtCan, BCan>,
-				BCan: Can<AP::Builder>,
-				AP: Promise<Builder = B, BCan = BCan>  {
+	#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
+	fn invalidate_dependents(&mut self, builder: &BuilderId) {
+		let mut invalidated = self.graph.invalidate_dependents(*builder);
 
-		self.dyn_state_cast_ref(promise.id())
+		for &bid in &invalidated {
+			self.remove_artifact(bid, DropReason::Dependency);
+		}
+
+		invalidated.push(*builder);
+		self.detach(&invalidated);
 	}
 
-	/// Deletes the artifact and dynamic state of the given builder.
+	/// Invalidates the given builder including its dependents, keeping their
+	/// artifacts as dirty if the early cutoff is enabled.
 	///
-	pub(crate) fn purge<AP, B: ?Sized>(
-			&mut self,
-			promise: &AP
-		)
-			where
-				B: Debug + 'static,
-				BCan: Can<AP::Builder>,
-				AP: Promise<Builder = B, BCan = BCan>  {
+	/// The given builder has to be rebuilt, while its dependents are only
+	/// rebuilt on demand, if any of their dependencies changed, see
+	/// `revalidate`.
+	///
+	fn mark_dirty(&mut self, builder: BuilderId) {
+		if !self.revisions.is_enabled() {
+			self.invalidate_by_id(&builder, DropReason::Invalidated);
+			return;
+		}
 
-		let bid = promise.id();
+		let dirty = self.graph.dependents_closure(builder);
 
-		// Remove weak reference of builder since we will remove all references
-		// to it
-		self.known_builders.remove(&bid);
+		for &bid in &dirty {
+			// Errors are just dropped, thus the build is retried
+			self.errors.remove(&bid);
 
-		// Purge artifact & dyn state
-		self.artifacts.remove(&bid);
-		self.dyn_states.remove(&bid);
+			if let Some(art) = self.artifacts.remove(&bid) {
+				let size = self.eviction.get_mut().size(bid).unwrap_or(0);
+				self.eviction.get_mut().remove(bid);
 
-		// Invalidate dependents
-		self.invalidate_by_id(&promise.id());
+				if bid == builder {
+					self.listeners.notify(bid, DropReason::Invalidated);
+				} else {
+					self.listeners.notify(bid, DropReason::Dependency);
+				}
 
-		#[cfg(feature = "diagnostics")]
-		self.doctor.invalidate(&BuilderHandle::new(promise));
+				self.stale.insert(bid, Some((art, size)));
+			} else {
+				// An evicted artifact is dirty too, it is just gone
+				self.stale.entry(bid).or_insert(None);
+			}
+		}
+
+		self.revisions.force(builder);
+		self.detach(&dirty);
 	}
 
-	/// Deletes all artifacts of this cache.
+	/// Takes the dirty artifact of the given builder, which is about to be
+	/// rebuilt.
 	///
-	pub(crate) fn clear_artifacts(&mut self) {
-		self.artifacts.clear();
-		self.dependents.clear();
-		self.dependencies.clear();
+	fn take_stale(&mut self, bid: BuilderId) -> Option<ArtCan> {
+		let old = self.stale.remove(&bid)?;
 
-		// Now, all know builders are leafs!
-		self.known_leaf_builder.extend(self.known_builders.keys());
+		// The rebuilt artifact records its dependencies anew
+		self.graph.remove_dependencies(bid);
 
+		old.map(|(art, _)| art)
 	}
 
-	/// Clears the entire cache including all kept promise, artifacts and
-	/// dynamic states.
+	/// Reuses the dirty artifact of the given builder, if none of its
+	/// dependencies changed since it has been built.
 	///
-	pub(crate) fn clear_all(&mut self) {
-		self.artifacts.clear();
-		self.dyn_states.clear();
-		self.dependents.clear();
-		self.dependencies.clear();
-		self.known_builders.clear();
-		self.known_leaf_builder.clear();
+	/// The dirty dependencies are brought up to date first, by reusing or
+	/// rebuilding them. Returns whether the artifact has been reused.
+	///
+	fn revalidate(&mut self, bid: BuilderId) -> bool {
+		if self.revisions.is_forced(bid) {
+			return false;
+		}
 
-		#[cfg(feature = "diagnostics")]
-		self.doctor.clear();
-	}
+		let verified = match (self.stale.get(&bid), self.revisions.verified_at(bid)) {
+			(Some(Some(_)), Some(verified)) => verified,
+			_ => return false,
+		};
 
-	/// Auxiliary invalidation function using an untyped (aka `dyn Any`)
-	/// `BuilderId`.
-	///
-	fn invalidate_by_id(&mut self, builder: &BuilderId) {
+		let dependencies: Vec<_> = self.graph.dependencies_of(bid).collect();
+		for dep in dependencies {
+			let unchanged = self.refresh(dep)
+				.map(|changed| changed <= verified)
+				.unwrap_or(false);
 
-		// Remember already processed builders, because they have no more
-		// dependencies mapping.
-		let mut processed = HashSet::new();
-		processed.insert(*builder);
+			if !unchanged {
+				return false;
+			}
+		}
 
-		// Stack of builder to be invalidated.
-		let mut pending = Vec::new();
-		pending.push(*builder);
+		// Just unwrap, since it has been checked above
+		let (art, size) = self.stale.remove(&bid).unwrap().unwrap();
+		self.artifacts.insert(bid, art);
+		self.revisions.verify(bid);
 
+		self.eviction.get_mut().insert(bid, size);
+		self.evict(Some(bid));
 
-		while let Some(bid) = pending.pop() {
-			// Mark builder as processed
-			processed.insert(bid);
+		true
+	}
 
-			// Get all dependents and invalidate them too
-			if let Some(set) = self.dependents.remove(&bid) {
-				for dep in set {
-					pending.push(dep);
-				}
-			}
+	/// Brings the artifact of the given builder up to date, if it is dirty.
+	///
+	/// Returns the revision when it last changed, or `None` if unknown, e.g.
+	/// because the rebuild failed.
+	///
+	fn refresh(&mut self, bid: BuilderId) -> Option<u64> {
+		if self.stale.contains_key(&bid) && !self.revalidate(bid) {
+			let rebuild = self.handlers.get(&bid)?.rebuild;
+			let can = BCan::upgrade_from_weak(self.known_builders.get(&bid)?)?;
 
-			// Remove dependencies too
-			if let Some(set) = self.dependencies.remove(&bid) {
-				for dep in set {
-					// For each dependency ensure that either it had been
-					// processed before, or it has a counterpart mapping.
-					// In the latter case, remove the dependent relation.
-					if !processed.contains(&dep) {
-						let found = self.dependents.get_mut(&dep)
-								.expect("Mapped dependency has no dependents counterpart map.")
-								.remove(&bid);
-
-						// Notice the above code has important side-effects, thus
-						// only the return value is tested in the assert macro.
-						debug_assert!(found);
-
-						// Check whether this depenencies has other dependents left, or whether it
-						// became a leaf now.
-						let is_leaf = self.dependents[&dep].is_empty();
-						if is_leaf {
-							self.known_leaf_builder.insert(dep);
-						}
-					}
-				}
+			if !rebuild(self, can) {
+				return None;
 			}
+		}
+
+		self.revisions.changed_at(bid)
+	}
+
+	/// Watches the given file for the artifact of the given builder.
+	///
+	pub(super) fn watch_file(&mut self, bid: BuilderId, stamp: FileStamp) {
+		self.inputs.entry(bid).or_default().push(stamp);
+	}
+
+	/// Invalidates the artifacts whose watched files changed.
+	///
+	pub(crate) fn refresh_inputs(&mut self) {
+		let changed: Vec<_> = self.inputs.iter_mut()
+			.filter_map(|(bid, stamps)| {
+				stamps.iter_mut().any(FileStamp::has_changed).then_some(*bid)
+			})
+			.collect();
+
+		for bid in changed {
+			self.mark_dirty(bid);
+		}
+	}
 
-			// bid it self has been invalidated, means it has no more dependents, and thus it is a
-			// leaf now!
-			self.known_leaf_builder.insert(bid);
+	/// Invalidates the artifacts whose builders consider them stale.
+	///
+	pub(crate) fn validate(&mut self) {
+		let stale: Vec<_> = self.artifacts.keys()
+			.copied()
+			.filter(|bid| {
+				let is_stale = self.handlers.get(bid).map(|handlers| handlers.is_stale);
+				let can = self.known_builders.get(bid).and_then(BCan::upgrade_from_weak);
 
-			self.artifacts.remove(&bid);
+				match (is_stale, can) {
+					(Some(is_stale), Some(can)) => is_stale(self, can),
+					_ => false,
+				}
+			})
+			.collect();
 
+		for bid in stale {
+			self.mark_dirty(bid);
 		}
+	}
 
+	/// Returns whether the early cutoff is enabled.
+	///
+	pub(crate) fn early_cutoff(&self) -> bool {
+		self.revisions.is_enabled()
 	}
 
-	/// Auxiliary invalidation function using an untyped (aka `dyn Any`)
-	/// `BuilderId`, only invalidates dependents not the given build itself.
+	/// Enables or disables the early cutoff.
 	///
-	fn invalidate_dependents(&mut self, builder: &BuilderId) {
-		if let Some(set) = self.dependents.remove(builder) {
-			for dep in set {
-				self.invalidate_by_id(&dep);
+	/// Disabling invalidates all dirty artifacts.
+	///
+	pub(crate) fn set_early_cutoff(&mut self, enabled: bool) {
+		if !enabled {
+			let dirty: Vec<_> = self.stale.keys().copied().collect();
+			for bid in dirty {
+				self.invalidate_by_id(&bid, DropReason::Invalidated);
 			}
 		}
 
-		// Now, `builder` has no more depenencies, i.e. it is a leaf
-		self.known_leaf_builder.insert(*builder);
+		self.revisions.set_enabled(enabled);
 	}
 
 	/// Removes the given promise with its cached artifact from the cache and
@@ -871,7 +2912,7 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 				AP: Promise<Builder = B, BCan = BCan>  {
 
 
-		self.invalidate_by_id(&promise.id());
+		self.mark_dirty(promise.id());
 
 		#[cfg(feature = "diagnostics")]
 		self.doctor.invalidate(&BuilderHandle::new(promise));
@@ -889,24 +2930,30 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 			// dependent.
 			self.known_builders.keys()
 				// Fine if registered as leaf_builder
-				.filter(|bid| !self.known_leaf_builder.contains(bid))
+				.filter(|bid| !self.graph.is_leaf(**bid))
 				// Enuse the others have a depenent
-				.all(|bid| !self.dependents[bid].is_empty())
+				.all(|bid| self.graph.has_dependents(*bid))
 		);
 
 		// Only check the leaf builders
-		let unreachable_builder_ids: Vec<_> = self.known_leaf_builder.iter()
+		let unreachable_builder_ids: Vec<_> = self.graph.leafs()
 			// Only retain those which can't be upgraded (i.e. no strong
 			// references exist any more).
 			.filter(|bid| BCan::upgrade_from_weak(&self.known_builders[bid]).is_none())
-			.copied()
 			.collect();
 
 		for bid in unreachable_builder_ids {
-			self.invalidate_by_id(&bid);
+			self.invalidate_by_id(&bid, DropReason::Collected);
 			self.dyn_states.remove(&bid);
 			self.known_builders.remove(&bid);
-			self.known_leaf_builder.remove(&bid);
+			self.type_names.remove(&bid);
+			self.graph.remove(bid);
+			self.handlers.remove(&bid);
+			self.dyn_state_forks.remove(&bid);
+			self.listeners.remove(bid);
+
+			#[cfg(feature = "persistence")]
+			self.persisted.forget(bid);
 		}
 	}
 
@@ -922,13 +2969,15 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 
 		let bid = promise.id();
 
-		let leafs = &mut self.known_leaf_builder;
+		let graph = &mut self.graph;
+		let type_names = &mut self.type_names;
 
 		self.known_builders.entry(bid).or_insert_with(
 			|| {
 				// Here, the builder was not known befor!
 				// Thus it must be a leaf
-				leafs.insert(bid);
+				graph.insert(bid);
+				type_names.insert(bid, std::any::type_name::<AP::Builder>());
 
 				// Return downgraded can
 				promise.canned().can.downgrade()
@@ -941,6 +2990,213 @@ impl<ArtCan, BCan> RawCache<ArtCan, BCan>
 	pub(crate) fn number_of_known_builders(&self) -> usize {
 		self.known_builders.len()
 	}
+
+	/// Describes the given known builders, ordered by id.
+	///
+	fn describe(&self, builders: impl IntoIterator<Item = BuilderId>) -> Vec<BuilderInfo> {
+		let mut infos: Vec<_> = builders.into_iter()
+			.map(|bid| self.builder_info(bid))
+			.collect();
+
+		infos.sort_by_key(BuilderInfo::id);
+
+		infos
+	}
+
+	/// Describes the given known builder.
+	///
+	pub(crate) fn builder_info(&self, bid: BuilderId) -> BuilderInfo {
+		BuilderInfo::from_parts(bid, self.type_names[&bid])
+	}
+
+	/// Returns all known builders, ordered by id.
+	///
+	pub(crate) fn known_builders(&self) -> Vec<BuilderInfo> {
+		self.describe(self.known_builders.keys().copied())
+	}
+
+	/// Returns the direct dependencies of the given builder.
+	///
+	pub(crate) fn dependencies_of(&self, bid: BuilderId) -> Vec<BuilderInfo> {
+		self.describe(self.graph.dependencies_of(bid))
+	}
+
+	/// Returns the direct dependents of the given builder.
+	///
+	pub(crate) fn dependents_of(&self, bid: BuilderId) -> Vec<BuilderInfo> {
+		self.describe(self.graph.dependents_of(bid))
+	}
+
+	/// Returns the (transitive) dependencies of the given builder.
+	///
+	pub(crate) fn transitive_dependencies_of(&self, bid: BuilderId) -> Vec<BuilderInfo> {
+		self.describe(
+			self.graph.dependencies_closure(bid).into_iter().filter(|dep| *dep != bid)
+		)
+	}
+
+	/// Returns the given builder and its (transitive) dependents by id.
+	///
+	pub(crate) fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId> {
+		self.graph.dependents_closure(bid)
+	}
+
+	/// Returns the (transitive) dependents of the given builder.
+	///
+	pub(crate) fn transitive_dependents_of(&self, bid: BuilderId) -> Vec<BuilderInfo> {
+		self.describe(
+			self.graph.dependents_closure(bid).into_iter().filter(|dep| *dep != bid)
+		)
+	}
+
+	/// Takes a snapshot of the known builders and their dependencies.
+	///
+	pub(crate) fn snapshot(&self) -> Snapshot {
+		let builders = self.known_builders();
+
+		let entries = builders.iter()
+			.map(|info| {
+				let bid = info.id();
+
+				SnapshotEntry::new(
+					*info,
+					self.artifacts.contains_key(&bid) && !self.is_expired(bid),
+					self.dyn_states.contains_key(&bid)
+						|| self.borrowed_dyn_states.contains_key(&bid),
+				)
+			})
+			.collect();
+
+		let dependencies = builders.iter()
+			.flat_map(|info| {
+				let mut deps: Vec<_> = self.graph.dependencies_of(info.id())
+					.map(|dep| (info.id(), dep))
+					.collect();
+				deps.sort();
+				deps
+			})
+			.collect();
+
+		Snapshot::new(entries, dependencies)
+	}
+
+	/// Returns all known builders, each after all its dependencies.
+	///
+	pub(crate) fn topological_order(&self) -> Vec<BuilderInfo> {
+		// Start in the order of ids, to get a deterministic order
+		let builders: Vec<_> = self.known_builders().iter()
+			.map(BuilderInfo::id)
+			.collect();
+
+		self.graph.topological_order(&builders).into_iter()
+			.map(|bid| BuilderInfo::from_parts(bid, self.type_names[&bid]))
+			.collect()
+	}
+}
+
+
+
+#[cfg(feature = "persistence")]
+impl<ArtCan, BCan> RawCache<ArtCan, BCan>
+		where
+			ArtCan: Debug,
+			BCan: CanStrong {
+
+	/// Sets the directory to store persistent artifacts in.
+	///
+	pub(crate) fn set_store(&mut self, dir: &Path) -> io::Result<()> {
+		self.persisted.set_dir(dir)
+	}
+
+	/// Restores the stored dyn state of the given builder, unless it already
+	/// has a dyn state.
+	///
+	fn restore_dyn_state<T, S: Debug + 'static>(
+			&mut self,
+			bid: BuilderId,
+			persistence: Option<&Persistence<ArtCan, T, S>>,
+		)
+			where
+				ArtCan: Can<T> {
+
+		if self.dyn_states.contains_key(&bid)
+				|| self.borrowed_dyn_states.contains_key(&bid) {
+			return;
+		}
+
+		if let Some(state) = persistence.and_then(|p| self.persisted.restore_dyn_state(p)) {
+			self.dyn_states.insert(bid, Rc::new(state));
+		}
+	}
+
+	/// Restores the stored artifact of the given builder, if it is still
+	/// valid for its current dyn state, and records its dependencies.
+	///
+	fn restore_artifact<T, S: 'static>(
+			&mut self,
+			bid: BuilderId,
+			persistence: Option<&Persistence<ArtCan, T, S>>,
+		) -> Option<ArtCan::Bin>
+			where
+				ArtCan: Can<T> {
+
+		// Borrow only the dyn state fields, unlike `dyn_state_cast_ref`
+		let dyn_state = match self.borrowed_dyn_states.get(&bid) {
+			Some(state) => state.downcast_ref(),
+			None => self.dyn_states.get(&bid).and_then(|state| state.downcast_ref()),
+		};
+
+		let restored = self.persisted.restore(bid, persistence?, dyn_state)?;
+
+		for dep in restored.dependencies {
+			self.graph.add_dependency(bid, dep);
+		}
+
+		self.resolve_dependents(bid, restored.dependents);
+
+		Some(restored.artifact)
+	}
+
+	/// Stores the just built artifact of the given builder.
+	///
+	fn store_artifact<T, S: 'static>(
+			&mut self,
+			bid: BuilderId,
+			persistence: &Persistence<ArtCan, T, S>,
+			art_bin: &ArtCan::Bin,
+		)
+			where
+				ArtCan: Can<T> {
+
+		// Borrow only the dyn state fields, unlike `dyn_state_cast_ref`
+		let dyn_state = match self.borrowed_dyn_states.get(&bid) {
+			Some(state) => state.downcast_ref(),
+			None => self.dyn_states.get(&bid).and_then(|state| state.downcast_ref()),
+		};
+
+		let dependents = self.persisted.store(
+			bid,
+			persistence,
+			art_bin,
+			dyn_state,
+			self.graph.dependencies_of(bid),
+		);
+
+		self.resolve_dependents(bid, dependents);
+	}
+
+	/// Records the dependency of the given restored dependents on the given
+	/// builder, and lets the outdated ones expire.
+	///
+	fn resolve_dependents(&mut self, bid: BuilderId, dependents: Vec<(BuilderId, bool)>) {
+		for (dependent, outdated) in dependents {
+			self.graph.add_dependency(dependent, bid);
+
+			if outdated {
+				self.outdate(dependent);
+			}
+		}
+	}
 }
 
 
@@ -1055,7 +3311,7 @@ mod test {
 
 		assert!(!cache.contains_artifact(&bp));
 
-		cache.build(&bp).unpack();
+		cache.build(&bp).unwrap();
 
 		assert!(cache.contains_artifact(&bp));
 
@@ -1075,7 +3331,7 @@ mod test {
 
 		assert!(!cache.is_builder_known(&bp));
 
-		cache.build(&bp).unpack();
+		cache.build(&bp).unwrap();
 
 		assert!(cache.is_builder_known(&bp));
 
@@ -1130,7 +3386,7 @@ mod test {
 
 		assert!(cache.lookup(&bp).is_none());
 
-		let value = cache.build(&bp).unpack().clone().downcast_can().unwrap();
+		let value = cache.build(&bp).unwrap().clone().downcast_can().unwrap();
 
 		assert!(cache.lookup(&bp).is_some());
 		assert_eq!(cache.lookup(&bp), cache.lookup(&bp));
@@ -1156,7 +3412,7 @@ mod test {
 
 		assert!(cache.lookup_ref(&bp).is_none());
 
-		let value = cache.build(&bp).unpack().clone();
+		let value = cache.build(&bp).unwrap().clone();
 		let value = value.downcast_ref().unwrap();
 
 		assert!(cache.lookup_ref(&bp).is_some());
@@ -1186,7 +3442,7 @@ mod test {
 
 		assert!(cache.lookup_mut(&bp).is_none());
 
-		let mut value = cache.build(&bp).unpack().downcast_mut::<Leaf>().unwrap().clone();
+		let mut value = cache.build(&bp).unwrap().downcast_mut::<Leaf>().unwrap().clone();
 
 		assert!(cache.lookup_mut(&bp).is_some());
 		assert_eq!(
@@ -1218,7 +3474,7 @@ mod test {
 
 		assert!(cache.lookup_cloned(&bp).is_none());
 
-		let value = cache.build(&bp).unpack().downcast_ref::<Leaf>().unwrap().clone();
+		let value = cache.build(&bp).unwrap().downcast_ref::<Leaf>().unwrap().clone();
 
 		assert!(cache.lookup_cloned(&bp).is_some());
 		assert_eq!(cache.lookup_cloned(&bp), cache.lookup_cloned(&bp));
@@ -1240,7 +3496,7 @@ mod test {
 		assert!(!cache.contains_artifact(&bp));
 		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
-		let value_1 = cache.build(&bp).unpack().clone().downcast_can().unwrap();
+		let value_1 = cache.build(&bp).unwrap().clone().downcast_can().unwrap();
 
 		assert!(cache.contains_artifact(&bp));
 		assert!(cache.dyn_states.contains_key(&bp.id()));
@@ -1249,7 +3505,7 @@ mod test {
 
 		cache.invalidate(&bp);
 		assert!(!cache.contains_artifact(&bp));
-		let value_2 = cache.build(&bp).unpack().clone().downcast_can().unwrap();
+		let value_2 = cache.build(&bp).unwrap().clone().downcast_can().unwrap();
 		assert!(cache.contains_artifact(&bp));
 
 		assert!(Rc::ptr_eq(&value_2, &cache.lookup(&bp).unwrap()));
@@ -1269,14 +3525,14 @@ mod test {
 		// inverse assertion
 		if cache.contains_artifact(&bp) {return}
 
-		cache.build(&bp).unpack();
+		cache.build(&bp).unwrap();
 
 		// inverse assertion
 		if !cache.contains_artifact(&bp) {return}
 
 		// Now there is already an artifact, no new may be build.
 		// This build should panic
-		cache.build(&bp).unpack();
+		cache.build(&bp).unwrap();
 	}
 
 	#[test]
@@ -1293,7 +3549,7 @@ mod test {
 		*cache.dyn_state_mut(&bp) = false;
 
 		// Building must fail
-		if let Err(()) = cache.build(&bp) {
+		if let Err(ResolveError::Failed(())) = cache.build(&bp) {
 			// fine
 		} else {
 			panic!("builder returned unexpectedly something");
@@ -1303,7 +3559,7 @@ mod test {
 		assert!(!cache.contains_artifact(&bp));
 
 		// And it continues to fail
-		if let Err(()) = cache.build(&bp) {
+		if let Err(ResolveError::Failed(())) = cache.build(&bp) {
 			// fine
 		} else {
 			panic!("builder returned unexpectedly something");
@@ -1370,7 +3626,7 @@ mod test {
 		let art_n_ptr = ptr(cache.get_ref(&bp).unpack());
 		assert_ne!(ptr(old_art.as_ref()), art_n_ptr);
 
-		assert_eq!(Some(art_n_ptr), cache.lookup_ref(&bp).map(|l| ptr(l)));
+		assert_eq!(Some(art_n_ptr), cache.lookup_ref(&bp).map(ptr));
 	}
 
 	#[test]
@@ -1431,7 +3687,7 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		let value = *cache.ensure_dyn_state(&bp);
 		assert_eq!(value, *cache.dyn_state(&bp));
@@ -1459,7 +3715,7 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		assert!(cache.dyn_state_cast_mut::<bool>(bp.id()).is_none());
 
@@ -1477,7 +3733,7 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		assert!(cache.dyn_state_cast_ref::<bool>(bp.id()).is_none());
 
@@ -1494,7 +3750,7 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		let dyn_state_ptr = ptr(cache.dyn_state(&bp));
 
@@ -1509,7 +3765,7 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		let dyn_state_ptr = ptr(cache.dyn_state_mut(&bp));
 
@@ -1524,13 +3780,13 @@ mod test {
 		let mut cache_owned = new_cache_rc();
 		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
 
-		assert!(cache.dyn_states.get(&bp.id()).is_none());
+		assert!(!cache.dyn_states.contains_key(&bp.id()));
 
 		assert!(cache.get_dyn_state(&bp).is_none());
 
 		let dyn_state_ptr = ptr(cache.ensure_dyn_state(&bp));
 
-		assert_eq!(Some(dyn_state_ptr), cache.get_dyn_state(&bp).map(|d| ptr(d)));
+		assert_eq!(Some(dyn_state_ptr), cache.get_dyn_state(&bp).map(ptr));
 	}
 
 	#[test]
@@ -1551,7 +3807,7 @@ mod test {
 
 		assert!(!cache.is_builder_known(&bp));
 		assert!(!cache.contains_artifact(&bp));
-		assert!(!cache.get_dyn_state(&bp).is_some());
+		assert!(cache.get_dyn_state(&bp).is_none());
 
 	}
 
@@ -1591,13 +3847,61 @@ mod test {
 
 		assert!(!cache.is_builder_known(&mid_bp));
 		assert!(!cache.contains_artifact(&mid_bp));
-		assert!(!cache.get_dyn_state(&mid_bp).is_some());
+		assert!(cache.get_dyn_state(&mid_bp).is_none());
 
 		assert!(cache.is_builder_known(&end_bp));
 		assert!(!cache.contains_artifact(&end_bp));
 		assert!(cache.get_dyn_state(&end_bp).is_some());
 	}
 
+	#[test]
+	fn purge_garbage_collection() {
+		let base_bp = Blueprint::new(BuilderLeafFallible::new());
+
+		let builder = BuilderVariableNode::new::<Rc<dyn Any>, Rc<dyn Any>>(base_bp.clone());
+		let mid_bp = Blueprint::new(builder);
+
+		let mut cache_owned = new_cache_rc();
+		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
+
+		cache.get(&mid_bp).unwrap();
+		cache.purge(&mid_bp);
+
+		// The purged builder must not be collected again
+		cache.garbage_collection();
+
+		assert!(cache.is_builder_known(&base_bp));
+		assert!(!cache.is_builder_known(&mid_bp));
+		assert_eq!(cache.number_of_known_builders(), 1);
+	}
+
+	#[test]
+	fn invalidate_dependents() {
+		let base_bp = Blueprint::new(BuilderLeafFallible::new());
+
+		let builder = BuilderVariableNode::new::<Rc<dyn Any>, Rc<dyn Any>>(base_bp.clone());
+		let mid_bp = Blueprint::new(builder);
+
+		let builder = BuilderVariableNode::new::<Rc<dyn Any>, Rc<dyn Any>>(mid_bp.clone());
+		let end_bp = Blueprint::new(builder);
+
+		let mut cache_owned = new_cache_rc();
+		let cache: &mut RawCache<Rc<dyn Any>, Rc<dyn Any>> = &mut cache_owned;
+
+		cache.get(&end_bp).unwrap();
+
+		cache.invalidate_dependents(&base_bp.id());
+
+		assert!(cache.contains_artifact(&base_bp));
+		assert!(!cache.contains_artifact(&mid_bp));
+		assert!(!cache.contains_artifact(&end_bp));
+
+		// All are still known, but have no dependencies any more
+		assert!(cache.is_builder_known(&mid_bp));
+		cache.garbage_collection();
+		assert_eq!(cache.number_of_known_builders(), 3);
+	}
+
 	#[test]
 	fn clear_artifacts() {
 		let builder = BuilderLeaf::new();
@@ -1681,7 +3985,7 @@ mod test {
 
 		assert!(!cache.is_builder_known(&bp));
 		assert!(!cache.contains_artifact(&bp));
-		assert!(!cache.get_dyn_state(&bp).is_some());
+		assert!(cache.get_dyn_state(&bp).is_none());
 
 	}
 
@@ -1717,15 +4021,15 @@ mod test {
 
 		assert!(!cache.is_builder_known(&base_bp));
 		assert!(!cache.contains_artifact(&base_bp));
-		assert!(!cache.get_dyn_state(&base_bp).is_some());
+		assert!(cache.get_dyn_state(&base_bp).is_none());
 
 		assert!(!cache.is_builder_known(&mid_bp));
 		assert!(!cache.contains_artifact(&mid_bp));
-		assert!(!cache.get_dyn_state(&mid_bp).is_some());
+		assert!(cache.get_dyn_state(&mid_bp).is_none());
 
 		assert!(!cache.is_builder_known(&end_bp));
 		assert!(!cache.contains_artifact(&end_bp));
-		assert!(!cache.get_dyn_state(&end_bp).is_some());
+		assert!(cache.get_dyn_state(&end_bp).is_none());
 	}
 
 	#[test]
@@ -1817,7 +4121,7 @@ mod test {
 
 		assert!(!cache.is_builder_known(&base_bp_2));
 		assert!(!cache.contains_artifact(&base_bp_2));
-		assert!(!cache.get_dyn_state(&base_bp_2).is_some());
+		assert!(cache.get_dyn_state(&base_bp_2).is_none());
 
 		assert!(cache.is_builder_known(&mid_bp));
 		assert!(cache.contains_artifact(&mid_bp));
@@ -1837,7 +4141,7 @@ mod test {
 
 		assert!(!cache.is_builder_known(&base_bp_2));
 		assert!(!cache.contains_artifact(&base_bp_2));
-		assert!(!cache.get_dyn_state(&base_bp_2).is_some());
+		assert!(cache.get_dyn_state(&base_bp_2).is_none());
 
 		assert!(cache.is_builder_known(&mid_bp));
 		assert!(!cache.contains_artifact(&mid_bp));
@@ -1955,6 +4259,3 @@ mod test {
 	}
 
 }
-
-
-
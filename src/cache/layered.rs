@@ -0,0 +1,204 @@
+//!
+//! Layering a `Cache` upon a parent cache.
+//!
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use cfg_if::cfg_if;
+
+use crate::canning::CanBase;
+use crate::CanStrong;
+
+use crate::BuilderId;
+
+use super::internal::Acquired;
+use super::internal::Built;
+use super::internal::Upstream;
+use super::BuilderInfo;
+use super::Cache;
+use super::CycleError;
+use super::SyncCache;
+
+
+
+/// A cache, upon which a `Cache` can be layered, see [`Cache::with_parent`].
+///
+/// This is implemented for a `Cache` shared by an `Rc`, with any `Doctor`
+/// when the **`diagnostics`** feature is active, and for a
+/// `SyncCache` shared by an `Arc`. The latter allows to layer caches of
+/// different threads upon the same parent, which may still be changed
+/// through its shared reference.
+///
+/// This trait is sealed, it can not be implemented outside of this crate.
+///
+/// [`Cache::with_parent`]: struct.Cache.html#method.with_parent
+///
+pub trait ParentCache<ArtCan, BCan>: sealed::Parent<ArtCan, BCan> {}
+
+mod sealed {
+	use crate::BuilderId;
+
+	/// The methods of a `ParentCache` used by the `Parent` upstream.
+	///
+	pub trait Parent<ArtCan, BCan> {
+		/// Returns the valid artifact of the given builder, if any.
+		///
+		fn artifact(&self, bid: BuilderId) -> Option<ArtCan>;
+
+		/// Returns the given builder and its (transitive) dependents by id.
+		///
+		fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId>;
+	}
+}
+
+impl<ArtCan, BCan> sealed::Parent<ArtCan, BCan> for Rc<Cache<ArtCan, BCan>>
+	where
+		ArtCan: Clone + Debug,
+		BCan: CanStrong {
+
+	fn artifact(&self, bid: BuilderId) -> Option<ArtCan> {
+		self.inner.shared_artifact(bid)
+	}
+
+	fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId> {
+		self.inner.dependents_closure(bid)
+	}
+}
+
+impl<ArtCan, BCan> ParentCache<ArtCan, BCan> for Rc<Cache<ArtCan, BCan>>
+	where
+		ArtCan: Clone + Debug,
+		BCan: CanStrong {}
+
+cfg_if! {
+	if #[cfg(feature = "diagnostics")] {
+		use crate::Doctor;
+
+		impl<ArtCan, BCan, Doc> sealed::Parent<ArtCan, BCan> for Rc<Cache<ArtCan, BCan, Doc>>
+			where
+				ArtCan: Clone + Debug,
+				BCan: CanStrong,
+				Doc: Doctor<ArtCan, BCan> + 'static {
+
+			fn artifact(&self, bid: BuilderId) -> Option<ArtCan> {
+				let cache: &Cache<ArtCan, BCan> = self;
+
+				cache.inner.shared_artifact(bid)
+			}
+
+			fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId> {
+				let cache: &Cache<ArtCan, BCan> = self;
+
+				cache.inner.dependents_closure(bid)
+			}
+		}
+
+		impl<ArtCan, BCan, Doc> ParentCache<ArtCan, BCan> for Rc<Cache<ArtCan, BCan, Doc>>
+			where
+				ArtCan: Clone + Debug,
+				BCan: CanStrong,
+				Doc: Doctor<ArtCan, BCan> + 'static {}
+	}
+}
+
+impl<ArtCan, BCan> sealed::Parent<ArtCan, BCan> for Arc<SyncCache<ArtCan, BCan>>
+	where
+		ArtCan: CanBase + Clone + Debug,
+		BCan: CanStrong + Debug,
+		BCan::CanWeak: Clone {
+
+	fn artifact(&self, bid: BuilderId) -> Option<ArtCan> {
+		self.shared_artifact(bid)
+	}
+
+	fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId> {
+		SyncCache::dependents_closure(self, bid)
+	}
+}
+
+impl<ArtCan, BCan> ParentCache<ArtCan, BCan> for Arc<SyncCache<ArtCan, BCan>>
+	where
+		ArtCan: CanBase + Clone + Debug,
+		BCan: CanStrong + Debug,
+		BCan::CanWeak: Clone {}
+
+/// A parent cache, as upstream of a `RawCache` layered upon it.
+///
+/// The parent is never modified, instead the layered cache builds any
+/// artifact the parent does not have. Builders invalidated in the layered
+/// cache are detached from the parent, i.e. their artifacts in the parent
+/// are not used any more.
+///
+/// The artifacts taken from the parent are checked on each access of the
+/// layered cache, thus artifacts which expired or have been invalidated in
+/// the parent are dropped in the layered cache too.
+///
+pub(super) struct Parent<P> {
+	cache: P,
+	detached: RefCell<HashSet<BuilderId>>,
+}
+
+impl<P> Parent<P> {
+	/// Creates a new upstream for the given parent cache.
+	///
+	pub(super) fn new(cache: P) -> Self {
+		Parent {
+			cache,
+			detached: RefCell::new(HashSet::new()),
+		}
+	}
+}
+
+impl<ArtCan, BCan, P> Upstream<ArtCan, BCan> for Parent<P>
+	where
+		ArtCan: CanBase,
+		BCan: CanStrong,
+		P: ParentCache<ArtCan, BCan> + 'static {
+
+	fn acquire(&self, builder: BuilderInfo) -> Result<Acquired<ArtCan>, CycleError> {
+		let bid = builder.id();
+
+		if !self.detached.borrow().contains(&bid) {
+			if let Some(art) = self.cache.artifact(bid) {
+				return Ok(Acquired::Artifact(art));
+			}
+		}
+
+		Ok(Acquired::Claimed(None))
+	}
+
+	fn release(
+			&self,
+			_builder: BuilderId,
+			_built: Option<Built<'_, ArtCan, BCan>>,
+			_dyn_state: Option<Box<dyn Any + Send>>,
+		) {
+
+		// New artifacts are only kept by the layered cache
+	}
+
+	fn detach(&self, builder: BuilderId) -> Vec<BuilderId> {
+		let mut detached = self.detached.borrow_mut();
+
+		// The artifacts of the dependents in the parent were built from the
+		// now invalid artifact, thus they are invalid too.
+		self.cache.dependents_closure(builder).into_iter()
+			.filter(|bid| detached.insert(*bid) && *bid != builder)
+			.collect()
+	}
+
+	fn is_current(&self, builder: BuilderId, artifact: &ArtCan) -> bool {
+		self.cache.artifact(builder).is_some_and(|current| {
+			current.can_as_ptr() as *const () == artifact.can_as_ptr() as *const ()
+		})
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
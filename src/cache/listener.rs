@@ -0,0 +1,134 @@
+//!
+//! Listeners notified about dropped artifacts.
+//!
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Debug;
+
+use crate::BuilderId;
+
+
+
+/// The reason why an Artifact has been dropped from a `Cache`.
+///
+/// This is passed to the listeners registered via [`Cache::subscribe`].
+///
+/// [`Cache::subscribe`]: struct.Cache.html#method.subscribe
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DropReason {
+	/// The Builder has been invalidated itself, e.g. via `Cache::invalidate`
+	/// or because one of its watched files changed.
+	Invalidated,
+	/// A (transitive) dependency of the Builder has been dropped.
+	Dependency,
+	/// The time to live of the Artifact is over.
+	Expired,
+	/// The Artifact has been evicted to stay within the capacity.
+	Evicted,
+	/// The Builder has been purged via `Cache::purge`.
+	Purged,
+	/// The Builder has been garbage collected, because it is no longer
+	/// referenced.
+	Collected,
+	/// All Artifacts have been cleared.
+	Cleared,
+	/// The Artifact taken from the parent cache is no longer valid there,
+	/// see `Cache::with_parent`.
+	Outdated,
+}
+
+/// Identifies a listener registered via [`Cache::subscribe`].
+///
+/// [`Cache::subscribe`]: struct.Cache.html#method.subscribe
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+/// A registered listener.
+///
+type Listener = (ListenerId, Box<dyn FnMut(DropReason)>);
+
+/// The listeners of a cache, by the builder they listen to.
+///
+#[derive(Default)]
+pub(crate) struct Listeners {
+	/// The id of the latest listener.
+	///
+	last_id: u64,
+
+	/// Maps builder id to the listeners of its artifact.
+	///
+	by_builder: HashMap<BuilderId, Vec<Listener>>,
+}
+
+impl Debug for Listeners {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Listeners {{ builders: {:?}, ... }}", self.by_builder.keys())
+	}
+}
+
+impl Listeners {
+	/// Creates a new empty set of listeners.
+	///
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers a listener for the artifact of the given builder.
+	///
+	pub(crate) fn subscribe(
+			&mut self,
+			bid: BuilderId,
+			listener: Box<dyn FnMut(DropReason)>,
+		) -> ListenerId {
+
+		self.last_id += 1;
+		let id = ListenerId(self.last_id);
+
+		self.by_builder.entry(bid).or_default().push((id, listener));
+
+		id
+	}
+
+	/// Removes the given listener.
+	///
+	/// Returns whether the listener was registered.
+	///
+	pub(crate) fn unsubscribe(&mut self, id: ListenerId) -> bool {
+		let mut found = false;
+
+		self.by_builder.retain(|_, listeners| {
+			let len = listeners.len();
+			listeners.retain(|(lid, _)| *lid != id);
+			found |= listeners.len() < len;
+
+			!listeners.is_empty()
+		});
+
+		found
+	}
+
+	/// Notifies the listeners of the given builder, that its artifact has
+	/// been dropped.
+	///
+	pub(crate) fn notify(&mut self, bid: BuilderId, reason: DropReason) {
+		for (_, listener) in self.by_builder.get_mut(&bid).into_iter().flatten() {
+			listener(reason);
+		}
+	}
+
+	/// Removes all listeners of the given builder.
+	///
+	pub(crate) fn remove(&mut self, bid: BuilderId) {
+		self.by_builder.remove(&bid);
+	}
+
+	/// Removes all listeners.
+	///
+	pub(crate) fn clear(&mut self) {
+		self.by_builder.clear();
+	}
+}
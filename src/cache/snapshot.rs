@@ -0,0 +1,188 @@
+//!
+//! Snapshots of the state of a `Cache`.
+//!
+
+use std::fmt::Write;
+
+use crate::BuilderId;
+
+use super::BuilderInfo;
+
+
+
+/// The state of a `Cache` at some point in time.
+///
+/// A snapshot lists all Builders known to the `Cache`, whether they have an
+/// Artifact or dynamic state, and the dependencies between them. It can be
+/// exported as DOT graph via [`to_dot`] or as JSON via [`to_json`], e.g. for
+/// inspecting a long-running `Cache`.
+///
+/// [`to_dot`]: struct.Snapshot.html#method.to_dot
+/// [`to_json`]: struct.Snapshot.html#method.to_json
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+	builders: Vec<SnapshotEntry>,
+	dependencies: Vec<(BuilderId, BuilderId)>,
+}
+
+/// The state of a single Builder in a [`Snapshot`].
+///
+/// [`Snapshot`]: struct.Snapshot.html
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotEntry {
+	info: BuilderInfo,
+	has_artifact: bool,
+	has_dyn_state: bool,
+}
+
+impl SnapshotEntry {
+	/// Creates a new entry.
+	///
+	pub(crate) fn new(info: BuilderInfo, has_artifact: bool, has_dyn_state: bool) -> Self {
+		SnapshotEntry {
+			info,
+			has_artifact,
+			has_dyn_state,
+		}
+	}
+
+	/// Returns the id and type name of the Builder.
+	///
+	pub fn info(&self) -> BuilderInfo {
+		self.info
+	}
+
+	/// Tests whether the Builder had a valid Artifact.
+	///
+	pub fn has_artifact(&self) -> bool {
+		self.has_artifact
+	}
+
+	/// Tests whether the Builder had a dynamic state.
+	///
+	pub fn has_dyn_state(&self) -> bool {
+		self.has_dyn_state
+	}
+}
+
+impl Snapshot {
+	/// Creates a new snapshot.
+	///
+	pub(crate) fn new(
+			builders: Vec<SnapshotEntry>,
+			dependencies: Vec<(BuilderId, BuilderId)>,
+		) -> Self {
+
+		Snapshot {
+			builders,
+			dependencies,
+		}
+	}
+
+	/// Returns the known Builders, ordered by id.
+	///
+	pub fn builders(&self) -> &[SnapshotEntry] {
+		&self.builders
+	}
+
+	/// Returns the dependencies as pairs of the depending Builder and the
+	/// Builder it depends on.
+	///
+	pub fn dependencies(&self) -> &[(BuilderId, BuilderId)] {
+		&self.dependencies
+	}
+
+	/// Renders this snapshot as DOT graph, e.g. for Graphviz.
+	///
+	/// Each Builder is a node labeled with its type name, pointing to the
+	/// Builders it depends on. Builders with an Artifact are filled, and
+	/// Builders with a dynamic state have a double border.
+	///
+	pub fn to_dot(&self) -> String {
+		let mut dot = String::new();
+
+		writeln!(dot, "strict digraph {{").unwrap();
+
+		for entry in &self.builders {
+			write!(dot,
+				r#"  "{:p}" [label = {:?}"#,
+				entry.info.id(),
+				entry.info.type_name()
+			).unwrap();
+
+			if entry.has_artifact {
+				write!(dot, ", style = filled").unwrap();
+			}
+			if entry.has_dyn_state {
+				write!(dot, ", peripheries = 2").unwrap();
+			}
+
+			writeln!(dot, "]").unwrap();
+		}
+
+		for &(user, dependency) in &self.dependencies {
+			writeln!(dot, r#"  "{:p}" -> "{:p}""#, user, dependency).unwrap();
+		}
+
+		writeln!(dot, "}}").unwrap();
+
+		dot
+	}
+
+	/// Renders this snapshot as JSON.
+	///
+	/// The JSON object has a `builders` array with the `id`, `type_name`,
+	/// `artifact` and `dyn_state` of each Builder, and a `dependencies` array
+	/// with the `user` and `dependency` ids of each dependency. The ids are
+	/// given as hexadecimal strings.
+	///
+	pub fn to_json(&self) -> String {
+		let builders: Vec<_> = self.builders.iter()
+			.map(|entry| {
+				format!(
+					r#"{{"id":"{:p}","type_name":{},"artifact":{},"dyn_state":{}}}"#,
+					entry.info.id(),
+					json_string(entry.info.type_name()),
+					entry.has_artifact,
+					entry.has_dyn_state,
+				)
+			})
+			.collect();
+
+		let dependencies: Vec<_> = self.dependencies.iter()
+			.map(|&(user, dependency)| {
+				format!(r#"{{"user":"{:p}","dependency":"{:p}"}}"#, user, dependency)
+			})
+			.collect();
+
+		format!(
+			r#"{{"builders":[{}],"dependencies":[{}]}}"#,
+			builders.join(","),
+			dependencies.join(",")
+		)
+	}
+}
+
+/// Quotes and escapes the given string as JSON string.
+///
+fn json_string(s: &str) -> String {
+	let mut quoted = String::with_capacity(s.len() + 2);
+	quoted.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			'\n' => quoted.push_str("\\n"),
+			'\r' => quoted.push_str("\\r"),
+			'\t' => quoted.push_str("\\t"),
+			c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+			c => quoted.push(c),
+		}
+	}
+
+	quoted.push('"');
+	quoted
+}
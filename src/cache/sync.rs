@@ -0,0 +1,1017 @@
+//!
+//! A thread-safe variant of the `Cache`.
+//!
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use cfg_if::cfg_if;
+
+use crate::canning::CanBase;
+use crate::CanStrong;
+use crate::CanSized;
+use crate::CanRef;
+use crate::Can;
+
+use crate::Promise;
+
+use crate::Builder;
+use crate::BuilderId;
+
+use super::BuilderInfo;
+use super::CycleError;
+use super::ResolveError;
+use super::graph::DependencyGraph;
+use super::internal::Acquired;
+use super::internal::Built;
+use super::internal::RawCache;
+use super::internal::Upstream;
+
+
+
+/// Thread-safe structure for building, caching and dependency tracking of
+/// artifacts.
+///
+/// This is the concurrent counterpart of the [`Cache`], which is usable
+/// through a shared reference (`&self`), thus it can be shared between
+/// threads e.g. by an `Arc`. It is intended to be used with the [`arc`]
+/// module, which provides `Send + Sync` Cans, for which it is aliased as
+/// `arc::SyncCache`.
+///
+/// Looking up Artifacts only needs a shared lock, thus lookups may happen in
+/// parallel. If an Artifact needs to be built, the building happens without
+/// holding any lock, so multiple Artifacts can be built in parallel.
+/// However, if multiple threads request the same Artifact at once, only one
+/// of them builds it, while the others wait for its result.
+///
+/// The Artifacts and their dependencies are kept consistent under
+/// concurrency. An Artifact which has been built while one of its
+/// dependencies was invalidated is returned to its requester, but it is not
+/// cached.
+///
+/// Notice, the dynamic state of a Builder can only be stored in this cache if
+/// it is `Send`, thus it is only kept for Builders, which send it back from
+/// their builds (see [`BuildPolicy::send_dyn_state`]), or whose dynamic state
+/// has been accessed through this cache e.g. by [`set_dyn_state`]. All other Builders get a fresh dynamic
+/// state for each build, which is dropped afterwards. Also notice, the
+/// dynamic state accessors wait for a running build of the respective
+/// Builder to finish.
+///
+/// When the **`diagnostics`** feature is active, builds of this cache are
+/// not reported to any `Doctor`.
+///
+/// [`Cache`]: struct.Cache.html
+/// [`BuildPolicy::send_dyn_state`]: ../trait.BuildPolicy.html#method.send_dyn_state
+/// [`set_dyn_state`]: struct.SyncCache.html#method.set_dyn_state
+///
+pub struct SyncCache<ArtCan, BCan> where BCan: CanStrong {
+	shared: Arc<SyncShared<ArtCan, BCan>>,
+}
+
+/// The shared part of the `SyncCache`, referenced by its workers.
+///
+/// Lock order: `builds` must be locked before `state`.
+///
+struct SyncShared<ArtCan, BCan> where BCan: CanStrong {
+	/// The cached artifacts and their dependencies.
+	///
+	state: RwLock<SyncState<ArtCan, BCan>>,
+
+	/// The running builds and the dyn states.
+	///
+	builds: Mutex<SyncBuilds>,
+
+	/// Notifies about finished builds.
+	///
+	built: Condvar,
+
+	/// The id for the next worker.
+	///
+	next_worker: AtomicUsize,
+
+	/// The maximum number of threads of each `Resolver::resolve_all`.
+	///
+	max_threads: AtomicUsize,
+}
+
+/// The cached artifacts of the `SyncCache`.
+///
+/// See `RawCache` for details about the fields.
+///
+struct SyncState<ArtCan, BCan> where BCan: CanStrong {
+	artifacts: HashMap<BuilderId, ArtCan>,
+	graph: DependencyGraph,
+	known_builders: HashMap<BuilderId, BCan::CanWeak>,
+}
+
+/// The running builds of the `SyncCache`.
+///
+struct SyncBuilds {
+	/// The builders currently building by their worker.
+	///
+	building: HashMap<BuilderId, Building>,
+
+	/// The builders claimed by each worker, in the order of claiming.
+	///
+	stacks: HashMap<usize, Vec<BuilderInfo>>,
+
+	/// The builder each waiting worker waits for.
+	///
+	waiting: HashMap<usize, BuilderId>,
+
+	/// The workers each worker waits for, see `Resolver::resolve_all`.
+	///
+	children: HashMap<usize, Vec<usize>>,
+
+	/// Maps builder id to their DynState value.
+	///
+	/// Notice, the DynState of a building builder is handed to its worker.
+	///
+	dyn_states: HashMap<BuilderId, Box<dyn Any + Send>>,
+}
+
+/// A running build.
+///
+struct Building {
+	/// The building worker.
+	worker: usize,
+	/// The builder being built.
+	info: BuilderInfo,
+	/// Whether the result must not be cached.
+	invalidated: bool,
+	/// Whether the builder got purged, so its DynState must be dropped.
+	purged: bool,
+}
+
+/// A worker of a `SyncCache`, as upstream of a `RawCache` building on
+/// behalf of the `SyncCache`.
+///
+struct SyncWorker<ArtCan, BCan> where BCan: CanStrong {
+	shared: Arc<SyncShared<ArtCan, BCan>>,
+	worker: usize,
+}
+
+
+impl<ArtCan, BCan> SyncShared<ArtCan, BCan> where BCan: CanStrong {
+	/// Lock the cached artifacts for reading.
+	///
+	fn read(&self) -> RwLockReadGuard<'_, SyncState<ArtCan, BCan>> {
+		self.state.read().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Lock the cached artifacts for writing.
+	///
+	fn write(&self) -> RwLockWriteGuard<'_, SyncState<ArtCan, BCan>> {
+		self.state.write().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Lock the running builds.
+	///
+	fn lock_builds(&self) -> MutexGuard<'_, SyncBuilds> {
+		self.builds.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Lock the running builds, once the given builder is not building.
+	///
+	fn lock_builds_idle(&self, bid: BuilderId) -> MutexGuard<'_, SyncBuilds> {
+		let mut builds = self.lock_builds();
+
+		while builds.building.contains_key(&bid) {
+			builds = self.built.wait(builds).unwrap_or_else(PoisonError::into_inner);
+		}
+
+		builds
+	}
+}
+
+impl<ArtCan, BCan> SyncState<ArtCan, BCan>
+	where
+		ArtCan: CanBase + Clone,
+		BCan: CanStrong,
+		BCan::CanWeak: Clone {
+
+	/// Enlist given builder as known builder.
+	///
+	fn make_builder_known(&mut self, bid: BuilderId, weak: &BCan::CanWeak) {
+		let graph = &mut self.graph;
+
+		self.known_builders.entry(bid).or_insert_with(|| {
+			// Here, the builder was not known before, thus it is a leaf
+			graph.insert(bid);
+
+			weak.clone()
+		});
+	}
+
+	/// Insert an artifact built by a worker, with its dependencies.
+	///
+	/// The artifact is only inserted, if it has been built from the
+	/// currently cached artifacts of its dependencies.
+	///
+	fn insert(&mut self, bid: BuilderId, built: Built<'_, ArtCan, BCan>) {
+		let up_to_date = built.dependencies.iter().all(|(dep, _, art)| {
+			match art {
+				Some(art) => {
+					self.artifacts.get(dep).map(|cached| {
+						cached.can_as_ptr() as *const () == art.can_as_ptr() as *const ()
+					}).unwrap_or(false)
+				}
+				// A failed dependency has no artifact to compare
+				None => true,
+			}
+		});
+
+		if !up_to_date {
+			return;
+		}
+
+		self.make_builder_known(bid, built.builder);
+
+		for (dep, weak, _) in built.dependencies {
+			self.make_builder_known(dep, weak);
+			self.graph.add_dependency(bid, dep);
+		}
+
+		self.artifacts.insert(bid, built.artifact.clone());
+	}
+
+	/// Invalidates the given builder and its dependents.
+	///
+	fn invalidate(&mut self, builds: &mut SyncBuilds, bid: BuilderId) {
+		for bid in self.graph.invalidate(bid) {
+			self.artifacts.remove(&bid);
+		}
+
+		// A running build of the builder itself might use the old DynState
+		if let Some(building) = builds.building.get_mut(&bid) {
+			building.invalidated = true;
+		}
+	}
+}
+
+impl SyncBuilds {
+	/// Returns the cycle, if `worker` waiting for `target` would form a
+	/// deadlock.
+	///
+	/// The returned chain lists the builders being built or waited for.
+	///
+	fn wait_cycle(&self, worker: usize, target: BuilderInfo) -> Option<CycleError> {
+		let holder = self.building.get(&target.id())?.worker;
+
+		let mut chain = vec![target];
+		let mut visited = HashSet::new();
+
+		if self.find_waiter(holder, Some(target.id()), worker, &mut chain, &mut visited) {
+			chain.push(target);
+			Some(CycleError::new(chain))
+		} else {
+			None
+		}
+	}
+
+	/// Searches `worker` among the workers `current` (transitively) waits
+	/// for.
+	///
+	/// `current` has been reached via its claimed builder `entry`, or as
+	/// child worker if `None`. On success, `chain` is extended by the builders
+	/// on the path.
+	///
+	fn find_waiter(
+			&self,
+			current: usize,
+			entry: Option<BuilderId>,
+			worker: usize,
+			chain: &mut Vec<BuilderInfo>,
+			visited: &mut HashSet<usize>,
+		) -> bool {
+
+		let len = chain.len();
+
+		// The builders `current` builds on behalf of `entry`
+		let stack = self.stacks.get(&current).map(Vec::as_slice).unwrap_or(&[]);
+		let start = entry.and_then(|bid| stack.iter().position(|info| info.id() == bid))
+			.map(|pos| pos + 1).unwrap_or(0);
+		chain.extend_from_slice(&stack[start..]);
+
+		if current == worker {
+			return true;
+		}
+
+		if visited.insert(current) {
+			if let Some(building) = self.waiting.get(&current)
+					.and_then(|bid| self.building.get(bid)) {
+
+				chain.push(building.info);
+
+				if self.find_waiter(building.worker, Some(building.info.id()), worker, chain, visited) {
+					return true;
+				}
+
+				chain.truncate(len + stack.len() - start);
+			}
+
+			for &child in self.children.get(&current).into_iter().flatten() {
+				if self.find_waiter(child, None, worker, chain, visited) {
+					return true;
+				}
+			}
+		}
+
+		chain.truncate(len);
+		false
+	}
+
+	/// Marks all running builds as invalidated.
+	///
+	fn invalidate_all(&mut self, purged: bool) {
+		for building in self.building.values_mut() {
+			building.invalidated = true;
+			building.purged |= purged;
+		}
+	}
+}
+
+impl<ArtCan, BCan> Upstream<ArtCan, BCan> for SyncWorker<ArtCan, BCan>
+	where
+		ArtCan: CanBase + Clone,
+		BCan: CanStrong,
+		BCan::CanWeak: Clone {
+
+	fn acquire(&self, builder: BuilderInfo) -> Result<Acquired<ArtCan>, CycleError> {
+		let bid = builder.id();
+
+		let mut builds = self.shared.lock_builds();
+
+		loop {
+			if let Some(art) = self.shared.read().artifacts.get(&bid) {
+				return Ok(Acquired::Artifact(art.clone()));
+			}
+
+			if let Entry::Vacant(entry) = builds.building.entry(bid) {
+				entry.insert(Building {
+					worker: self.worker,
+					info: builder,
+					invalidated: false,
+					purged: false,
+				});
+				builds.stacks.entry(self.worker).or_default().push(builder);
+
+				return Ok(Acquired::Claimed(builds.dyn_states.remove(&bid)));
+			}
+
+			// Another worker is building it, wait for its result, unless
+			// that worker waits for us.
+			if let Some(cycle) = builds.wait_cycle(self.worker, builder) {
+				return Err(cycle);
+			}
+
+			builds.waiting.insert(self.worker, bid);
+
+			while builds.building.contains_key(&bid) {
+				builds = self.shared.built.wait(builds)
+					.unwrap_or_else(PoisonError::into_inner);
+			}
+
+			builds.waiting.remove(&self.worker);
+
+			// If the build failed, we will try it our self
+		}
+	}
+
+	fn release(
+			&self,
+			bid: BuilderId,
+			built: Option<Built<'_, ArtCan, BCan>>,
+			dyn_state: Option<Box<dyn Any + Send>>,
+		) {
+
+		let mut builds = self.shared.lock_builds();
+
+		let building = builds.building.remove(&bid)
+			.expect("Released a builder which is not building");
+		debug_assert_eq!(building.worker, self.worker);
+
+		if let Entry::Occupied(mut stack) = builds.stacks.entry(self.worker) {
+			let top = stack.get_mut().pop();
+			debug_assert_eq!(top.map(|info| info.id()), Some(bid));
+
+			if stack.get().is_empty() {
+				stack.remove();
+			}
+		}
+
+		if let Some(dyn_state) = dyn_state {
+			if !building.purged {
+				builds.dyn_states.insert(bid, dyn_state);
+			}
+		}
+
+		if let Some(built) = built {
+			if !building.invalidated {
+				self.shared.write().insert(bid, built);
+			}
+		}
+
+		drop(builds);
+		self.shared.built.notify_all();
+	}
+
+	fn keeps_dyn_states(&self) -> bool {
+		true
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+impl<ArtCan, BCan> Default for SyncCache<ArtCan, BCan>
+	where BCan: CanStrong {
+
+	fn default() -> Self {
+		SyncCache::new()
+	}
+}
+
+impl<ArtCan, BCan> SyncCache<ArtCan, BCan>
+	where BCan: CanStrong {
+
+	/// Creates a new empty cache.
+	///
+	pub fn new() -> Self {
+		SyncCache {
+			shared: Arc::new(SyncShared {
+				state: RwLock::new(SyncState {
+					artifacts: HashMap::new(),
+					graph: DependencyGraph::new(),
+					known_builders: HashMap::new(),
+				}),
+				builds: Mutex::new(SyncBuilds {
+					building: HashMap::new(),
+					stacks: HashMap::new(),
+					waiting: HashMap::new(),
+					children: HashMap::new(),
+					dyn_states: HashMap::new(),
+				}),
+				built: Condvar::new(),
+				next_worker: AtomicUsize::new(0),
+				max_threads: AtomicUsize::new(
+					std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+				),
+			}),
+		}
+	}
+}
+
+impl<ArtCan, BCan> Debug for SyncCache<ArtCan, BCan>
+	where ArtCan: Debug, BCan: CanStrong + Debug {
+
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let state = self.shared.read();
+
+		write!(f, "SyncCache {{ cache: {:?}, dependents: {:?}, ... }}",
+			state.artifacts, state.graph.dependents)
+	}
+}
+
+impl<ArtCan, BCan> SyncCache<ArtCan, BCan>
+	where
+		ArtCan: CanBase + Clone + Debug,
+		BCan: CanStrong + Debug,
+		BCan::CanWeak: Clone {
+
+	/// Runs `f` with a new worker of this cache.
+	///
+	fn with_worker<R>(&self, f: impl FnOnce(&mut RawCache<ArtCan, BCan>) -> R) -> R {
+		let worker = self.shared.next_worker.fetch_add(1, Ordering::Relaxed);
+
+		run_worker(self.shared.clone(), worker, f)
+	}
+
+	/// Tests whether the artifact or dynamic state of the given builder is
+	/// recorded in this cache.
+	///
+	/// Also see the corresponding [`is_builder_known`] of the `Cache`.
+	///
+	/// [`is_builder_known`]: struct.Cache.html#method.is_builder_known
+	///
+	pub fn is_builder_known<AP: ?Sized>(
+			&self,
+			promise: &AP
+		) -> bool
+			where
+				BCan: Can<AP::Builder>,
+				AP: Promise<BCan = BCan> {
+
+		self.shared.read().known_builders.contains_key(&promise.id())
+	}
+
+	/// Gets the stored Artifact in its Bin, if it exists.
+	///
+	/// Also see the corresponding [`lookup`] of the `Cache`.
+	///
+	/// [`lookup`]: struct.Cache.html#method.lookup
+	///
+	pub fn lookup<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> Option<ArtCan::Bin>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.shared.read().artifacts.get(&promise.id()).map(|can| {
+			can.clone().downcast_can()
+				.expect("Cached artifact is of invalid type")
+		})
+	}
+
+	/// Gets a clone of the stored Artifact, if it exists.
+	///
+	/// Also see the corresponding [`lookup_cloned`] of the `Cache`.
+	///
+	/// [`lookup_cloned`]: struct.Cache.html#method.lookup_cloned
+	///
+	pub fn lookup_cloned<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> Option<B::Artifact>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.shared.read().artifacts.get(&promise.id()).map(|can| {
+			can.downcast_can_ref()
+				.expect("Cached artifact is of invalid type")
+				.clone()
+		})
+	}
+
+	/// Gets the Artifact in its Bin.
+	///
+	/// If the Artifact is not cached, it is built by the calling thread,
+	/// unless another thread is already building it, in which case this
+	/// method waits for its result. The Builder's dependencies are resolved
+	/// the same way.
+	///
+	/// An `Err` will be returned only, if the Artifact was not cached and
+	/// the Builder returned an `Err`. Errors are not cached, thus threads
+	/// waiting for a failed build will try to build it again.
+	///
+	/// Also see the corresponding [`get`] of the `Cache`.
+	///
+	/// # Panics
+	///
+	/// Panics if there is a dependency cycle.
+	///
+	/// [`get`]: struct.Cache.html#method.get
+	///
+	pub fn get<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> Result<ArtCan::Bin, B::Err>
+			where
+				ArtCan: CanSized<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		if let Some(art) = self.lookup(promise) {
+			return Ok(art);
+		}
+
+		self.with_worker(|worker| worker.get(promise))
+	}
+
+	/// Gets a clone of the Artifact.
+	///
+	/// Same as [`get`], but returns a clone of the Artifact itself.
+	///
+	/// [`get`]: struct.SyncCache.html#method.get
+	///
+	pub fn get_cloned<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> Result<B::Artifact, B::Err>
+			where
+				ArtCan: CanRef<B::Artifact>,
+				B: Builder<ArtCan, BCan>,
+				B::Artifact: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		if let Some(art) = self.lookup_cloned(promise) {
+			return Ok(art);
+		}
+
+		self.with_worker(|worker| worker.get_cloned(promise))
+	}
+
+	/// Gets a clone of the dynamic state of the given builder, if it exists.
+	///
+	/// Waits for a running build of the given builder to finish.
+	///
+	pub fn get_dyn_state<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> Option<B::DynState>
+			where
+				B: Builder<ArtCan, BCan>,
+				B::DynState: Clone,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		self.shared.lock_builds_idle(promise.id())
+			.dyn_states.get(&promise.id())
+			.map(|state| {
+				state.downcast_ref::<B::DynState>()
+					.expect("Cached dyn state is of invalid type")
+					.clone()
+			})
+	}
+
+	/// Gets a clone of the dynamic state of the given builder.
+	///
+	/// If the builder has no dynamic state yet, it is initialized and kept
+	/// in this cache.
+	///
+	/// Waits for a running build of the given builder to finish.
+	///
+	pub fn dyn_state<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		) -> B::DynState
+			where
+				B: Builder<ArtCan, BCan>,
+				B::DynState: Clone + Send,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let bid = promise.id();
+		let mut builds = self.shared.lock_builds_idle(bid);
+
+		let dyn_state = builds.dyn_states.entry(bid).or_insert_with(|| {
+			self.shared.write().make_builder_known(bid, &promise.canned().can.downgrade());
+
+			Box::new(promise.builder().builder.init_dyn_state())
+		});
+
+		dyn_state.downcast_ref::<B::DynState>()
+			.expect("Cached dyn state is of invalid type")
+			.clone()
+	}
+
+	/// Sets the dynamic state of the given builder, if it differs from the
+	/// current one.
+	///
+	/// Like [`Cache::set_dyn_state`], the artifact of the given builder,
+	/// including all depending artifacts, are only invalidated, if the
+	/// dynamic state changed. Returns whether it changed. Both compare the
+	/// given dynamic state against the initial one, if the builder has none
+	/// yet.
+	///
+	/// Waits for a running build of the given builder to finish.
+	///
+	/// [`Cache::set_dyn_state`]: struct.Cache.html#method.set_dyn_state
+	///
+	pub fn set_dyn_state<AP, B: ?Sized>(
+			&self,
+			promise: &AP,
+			dyn_state: B::DynState,
+		) -> bool
+			where
+				B: Builder<ArtCan, BCan>,
+				B::DynState: PartialEq + Send,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let bid = promise.id();
+		let mut builds = self.shared.lock_builds_idle(bid);
+
+		let changed = match builds.dyn_states.get(&bid) {
+			Some(current) => {
+				*current.downcast_ref::<B::DynState>()
+					.expect("Cached dyn state is of invalid type") != dyn_state
+			}
+			None => promise.builder().builder.init_dyn_state() != dyn_state,
+		};
+
+		{
+			let mut state = self.shared.write();
+			if changed {
+				state.invalidate(&mut builds, bid);
+			}
+			state.make_builder_known(bid, &promise.canned().can.downgrade());
+		}
+
+		builds.dyn_states.insert(bid, Box::new(dyn_state));
+
+		changed
+	}
+
+	/// Deletes all cached artifacts.
+	///
+	/// Running builds will not cache their artifacts.
+	///
+	pub fn clear_artifacts(&self) {
+		let mut builds = self.shared.lock_builds();
+		let mut state = self.shared.write();
+
+		builds.invalidate_all(false);
+
+		let state = &mut *state;
+		state.artifacts.clear();
+		state.graph.clear_dependencies(state.known_builders.keys().copied());
+	}
+
+	/// Clears the entire cache including all kept builders, artifacts and
+	/// dynamic states.
+	///
+	/// Running builds will not cache their artifacts.
+	///
+	pub fn clear_all(&self) {
+		let mut builds = self.shared.lock_builds();
+		let mut state = self.shared.write();
+
+		builds.invalidate_all(true);
+		builds.dyn_states.clear();
+
+		state.artifacts.clear();
+		state.graph.clear();
+		state.known_builders.clear();
+	}
+
+	/// Deletes the artifact and dynamic state of the given builder, and
+	/// invalidates its dependents.
+	///
+	/// Also see the corresponding [`purge`] of the `Cache`.
+	///
+	/// [`purge`]: struct.Cache.html#method.purge
+	///
+	pub fn purge<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		)
+			where
+				B: Debug + 'static,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let bid = promise.id();
+
+		let mut builds = self.shared.lock_builds();
+		let mut state = self.shared.write();
+
+		state.invalidate(&mut builds, bid);
+		state.graph.remove(bid);
+		state.known_builders.remove(&bid);
+		builds.dyn_states.remove(&bid);
+
+		if let Some(building) = builds.building.get_mut(&bid) {
+			building.purged = true;
+		}
+	}
+
+	/// Invalidates the artifact of the given builder and all its dependents.
+	///
+	/// Running builds of any of them will not cache their artifacts.
+	///
+	/// Also see the corresponding [`invalidate`] of the `Cache`.
+	///
+	/// [`invalidate`]: struct.Cache.html#method.invalidate
+	///
+	pub fn invalidate<AP, B: ?Sized>(
+			&self,
+			promise: &AP
+		)
+			where
+				B: Debug + 'static,
+				BCan: Can<AP::Builder>,
+				AP: Promise<Builder = B, BCan = BCan>  {
+
+		let mut builds = self.shared.lock_builds();
+
+		self.shared.write().invalidate(&mut builds, promise.id());
+	}
+
+	/// Removes all builders, which are no longer referenced outside of this
+	/// cache, together with their artifacts and dynamic states.
+	///
+	/// Also see the corresponding [`garbage_collection`] of the `Cache`.
+	///
+	/// [`garbage_collection`]: struct.Cache.html#method.garbage_collection
+	///
+	pub fn garbage_collection(&self) {
+		let mut builds = self.shared.lock_builds();
+		let mut state = self.shared.write();
+
+		// Only check the leaf builders
+		let unreachable_builder_ids: Vec<_> = state.graph.leafs()
+			// Only retain those which can't be upgraded (i.e. no strong
+			// references exist any more).
+			.filter(|bid| BCan::upgrade_from_weak(&state.known_builders[bid]).is_none())
+			.collect();
+
+		for bid in unreachable_builder_ids {
+			state.invalidate(&mut builds, bid);
+			state.graph.remove(bid);
+			state.known_builders.remove(&bid);
+			builds.dyn_states.remove(&bid);
+		}
+	}
+
+	/// Returns the artifact of the given builder, if any, to be shared with
+	/// a cache layered upon this one.
+	///
+	pub(super) fn shared_artifact(&self, bid: BuilderId) -> Option<ArtCan> {
+		self.shared.read().artifacts.get(&bid).cloned()
+	}
+
+	/// Returns the given builder and its (transitive) dependents by id.
+	///
+	pub(super) fn dependents_closure(&self, bid: BuilderId) -> Vec<BuilderId> {
+		self.shared.read().graph.dependents_closure(bid)
+	}
+
+	/// Returns the number of currently kept builders.
+	///
+	pub fn number_of_known_builders(&self) -> usize {
+		self.shared.read().known_builders.len()
+	}
+
+	/// Returns the maximum number of threads used to build Artifacts in
+	/// parallel.
+	///
+	/// See [`set_max_threads`].
+	///
+	/// [`set_max_threads`]: struct.SyncCache.html#method.set_max_threads
+	///
+	pub fn max_threads(&self) -> usize {
+		self.shared.max_threads.load(Ordering::Relaxed)
+	}
+
+	/// Limits the number of threads used to build Artifacts in parallel.
+	///
+	/// Each call of [`Resolver::resolve_all`] builds its Artifacts by at most
+	/// this many threads. A limit of zero counts as one. The default is the
+	/// number of available CPUs.
+	///
+	/// [`Resolver::resolve_all`]: struct.Resolver.html#method.resolve_all
+	///
+	pub fn set_max_threads(&self, max: usize) {
+		self.shared.max_threads.store(max.max(1), Ordering::Relaxed)
+	}
+}
+
+
+/// Runs `f` with a `RawCache` as given worker of the shared cache.
+///
+fn run_worker<ArtCan, BCan, R>(
+		shared: Arc<SyncShared<ArtCan, BCan>>,
+		worker: usize,
+		f: impl FnOnce(&mut RawCache<ArtCan, BCan>) -> R
+	) -> R
+		where
+			ArtCan: CanBase + Clone + Debug,
+			BCan: CanStrong + Debug,
+			BCan::CanWeak: Clone {
+
+	cfg_if! {
+		if #[cfg(feature = "diagnostics")] {
+			let mut cache = RawCache::new_with_doctor(crate::DefDoctor);
+		} else {
+			let mut cache = RawCache::new();
+		}
+	}
+
+	let cache: &mut RawCache<ArtCan, BCan> = &mut cache;
+
+	cache.set_upstream(Rc::new(SyncWorker {
+		shared,
+		worker,
+	}));
+
+	f(cache)
+}
+
+/// Builds the artifacts of the given promises in parallel, if `cache` is a
+/// worker of a `SyncCache`.
+///
+/// The artifacts not yet cached by the `SyncCache` are built by at most
+/// `max_threads` threads, each of which takes the next pending promise until
+/// none is left. Returns `None`, if `cache` is not
+/// a worker of a `SyncCache`.
+///
+pub(super) fn build_parallel<AP, B: ?Sized, ArtCan, BCan>(
+		cache: &RawCache<ArtCan, BCan>,
+		promises: &[AP],
+	) -> Option<Vec<Result<ArtCan::Bin, B::Err>>>
+		where
+			ArtCan: CanSized<B::Artifact> + Clone + Debug + Send + Sync,
+			ArtCan::Bin: Send + 'static,
+			B: Builder<ArtCan, BCan>,
+			B::Err: Send,
+			BCan: Can<AP::Builder> + CanStrong + Debug + Send + Sync,
+			BCan::CanWeak: Clone + Send + Sync,
+			AP: Promise<Builder = B, BCan = BCan> + Clone + Send + 'static {
+
+	let upstream = cache.upstream()?.as_any()
+		.downcast_ref::<SyncWorker<ArtCan, BCan>>()?;
+	let shared = &upstream.shared;
+
+	let mut results: Vec<_> = {
+		let state = shared.read();
+
+		promises.iter().map(|promise| {
+			state.artifacts.get(&promise.id()).map(|can| {
+				Ok(can.clone().downcast_can()
+					.expect("Cached artifact is of invalid type"))
+			})
+		}).collect()
+	};
+
+	let pending: Vec<_> = promises.iter().zip(&results).enumerate()
+		.filter(|(_, (_, result))| result.is_none())
+		.map(|(index, (promise, _))| (index, promise.clone()))
+		.collect();
+
+	let threads = shared.max_threads.load(Ordering::Relaxed)
+		.min(pending.len());
+
+	let children: Vec<_> = (0..threads)
+		.map(|_| shared.next_worker.fetch_add(1, Ordering::Relaxed))
+		.collect();
+
+	// While joining, our worker waits for its children. This must be known
+	// before they start, otherwise a child waiting for us would not detect
+	// the cycle.
+	shared.lock_builds().children.insert(upstream.worker, children.clone());
+
+	let queue = Mutex::new(pending.into_iter());
+
+	let joined: Vec<_> = std::thread::scope(|scope| {
+		let threads: Vec<_> = children.into_iter().map(|child| {
+			let queue = &queue;
+
+			scope.spawn(move || {
+				// Just take the next promise, without holding the lock while
+				// building it
+				let next = || queue.lock()
+					.unwrap_or_else(PoisonError::into_inner)
+					.next();
+
+				let mut built = Vec::new();
+
+				while let Some((index, promise)) = next() {
+					let res = run_worker(shared.clone(), child, |cache| {
+						cache.try_get(&promise)
+					});
+
+					built.push((index, res));
+				}
+
+				built
+			})
+		}).collect();
+
+		threads.into_iter().map(|thread| thread.join()).collect()
+	});
+
+	shared.lock_builds().children.remove(&upstream.worker);
+
+	for built in joined {
+		match built {
+			Ok(built) => {
+				for (index, res) in built {
+					// Propagate interrupts, such as a detected dependency cycle
+					results[index] = Some(res.map_err(ResolveError::or_raise));
+				}
+			}
+			// Propagate panics of the builders
+			Err(payload) => std::panic::resume_unwind(payload),
+		}
+	}
+
+	Some(results.into_iter().map(|res| res.expect("Missing result")).collect())
+}
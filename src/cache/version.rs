@@ -0,0 +1,26 @@
+//!
+//! Versions of artifacts.
+//!
+
+
+
+/// The version of an Artifact, as returned by [`Cache::get_versioned`].
+///
+/// Each time an Artifact is built or otherwise inserted into the `Cache`, it
+/// gets a new version, which is unique within that `Cache`. Thus, a version
+/// can be used to tell via [`Cache::is_current`] whether an Artifact taken
+/// out of the `Cache` earlier is still the current one.
+///
+/// [`Cache::get_versioned`]: struct.Cache.html#method.get_versioned
+/// [`Cache::is_current`]: struct.Cache.html#method.is_current
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArtifactVersion(u64);
+
+impl ArtifactVersion {
+	/// Creates a version from its number.
+	///
+	pub(crate) fn new(version: u64) -> Self {
+		ArtifactVersion(version)
+	}
+}
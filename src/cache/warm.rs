@@ -0,0 +1,99 @@
+//!
+//! Building heterogeneous promises up front.
+//!
+
+use std::fmt::Debug;
+
+use crate::CanStrong;
+use crate::CanSized;
+use crate::Can;
+
+use crate::Promise;
+
+use crate::Builder;
+
+use super::BuilderInfo;
+use super::Cache;
+
+
+
+/// A promise of any type, which can be built by [`Cache::warm`].
+///
+/// This trait is implemented for all promises, such as `Blueprint`s and
+/// `BlueprintDyn`s, so that promises of different Builder types can be
+/// passed to `Cache::warm` as trait objects.
+///
+/// [`Cache::warm`]: struct.Cache.html#method.warm
+///
+pub trait Warm<ArtCan, BCan: CanStrong> {
+	/// Describes the Builder of this promise.
+	///
+	fn info(&self) -> BuilderInfo;
+
+	/// Builds the Artifact of this promise, if it is not cached yet.
+	///
+	/// Returns the error of the Builder, if the build failed.
+	///
+	fn warm(&self, cache: &mut Cache<ArtCan, BCan>) -> Result<(), Box<dyn Debug>>;
+}
+
+impl<ArtCan, BCan, AP, B: ?Sized> Warm<ArtCan, BCan> for AP
+	where
+		ArtCan: CanSized<B::Artifact> + Clone + Debug,
+		B: Builder<ArtCan, BCan>,
+		BCan: Can<AP::Builder> + CanStrong + Debug,
+		AP: Promise<Builder = B, BCan = BCan> {
+
+	fn info(&self) -> BuilderInfo {
+		BuilderInfo::new(self)
+	}
+
+	fn warm(&self, cache: &mut Cache<ArtCan, BCan>) -> Result<(), Box<dyn Debug>> {
+		cache.get(self)
+			.map(|_| ())
+			.map_err(|err| Box::new(err) as Box<dyn Debug>)
+	}
+}
+
+/// The summary of [`Cache::warm`].
+///
+/// [`Cache::warm`]: struct.Cache.html#method.warm
+///
+#[derive(Debug)]
+pub struct WarmReport {
+	builds: usize,
+	failed: Vec<(BuilderInfo, Box<dyn Debug>)>,
+}
+
+impl WarmReport {
+	/// Creates a new report.
+	///
+	pub(crate) fn new(builds: usize, failed: Vec<(BuilderInfo, Box<dyn Debug>)>) -> Self {
+		WarmReport {
+			builds,
+			failed,
+		}
+	}
+
+	/// Returns the number of builds which actually ran, including those of
+	/// dependencies and failed ones.
+	///
+	/// Artifacts which have already been cached are not built again.
+	///
+	pub fn builds(&self) -> usize {
+		self.builds
+	}
+
+	/// Returns the promises whose Artifacts failed to build, along with the
+	/// errors of their Builders.
+	///
+	pub fn failed(&self) -> &[(BuilderInfo, Box<dyn Debug>)] {
+		&self.failed
+	}
+
+	/// Tests whether all Artifacts have been built successfully.
+	///
+	pub fn is_success(&self) -> bool {
+		self.failed.is_empty()
+	}
+}
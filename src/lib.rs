@@ -19,6 +19,10 @@
 // Prevents public API entries without a doc comment.
 #![warn(missing_docs)]
 
+// Bounds such as `B: ?Sized` are kept in the generics list by convention,
+// while the remaining bounds go into the where clause.
+#![allow(clippy::multiple_bound_locations)]
+
 
 //!
 //! DAG Aware Artifact Builder
@@ -84,6 +88,25 @@
 //! Also see [`Cache`], [`Builder`], [`blueprint`], [`canning`]
 //!
 //!
+//!
+//! ## Dependency Cycles
+//!
+//! A Builder which (directly or indirectly) resolves its own Artifact forms
+//! a dependency cycle, which the `Cache` detects as [`CycleError`]. The
+//! `try_*` accessors of the `Cache` such as [`Cache::try_get`] and the
+//! `try_resolve*` methods of the `Resolver` such as [`Resolver::try_resolve`]
+//! return it as [`ResolveError`], just like a timeout or a cancellation of
+//! the builds. Their plain counterparts can only return the error type of
+//! the respective Builder. Thus, the plain accessors of the `Cache` panic,
+//! while the plain `resolve*` methods abort the builds by unwinding up to
+//! the nearest `try_*` call, which returns the error.
+//!
+//! Therefore, Builders which resolve via the plain methods require the
+//! `unwind` panic strategy to report such errors. With `panic = "abort"`,
+//! only Builders using the `try_resolve*` methods get them reported, the
+//! others abort the process instead.
+//!
+//!
 //![`Builder`]: trait.Builder.html
 //![`Artifact`]: trait.Builder.html#associatedtype.Artifact
 //![`build`]: trait.Builder.html#tymethod.build
@@ -95,6 +118,10 @@
 //![`Resolver`]: cache/struct.Resolver.html
 //![`Cache`]: cache/struct.Cache.html
 //![Advanced Feature section of `Builder`]: trait.Builder.html#advanced-features
+//![`CycleError`]: cache/struct.CycleError.html
+//![`Cache::try_get`]: cache/struct.Cache.html#method.try_get
+//![`Resolver::try_resolve`]: cache/struct.Resolver.html#method.try_resolve
+//![`ResolveError`]: cache/enum.ResolveError.html
 //!
 //!
 //! ## Example
@@ -261,11 +288,18 @@
 //!   `Doctor`s, hence it is only useful in connection with the `diagnostics`
 //!   feature.
 //!
+//! - **`persistence`** enables storing Artifacts on disk, to restore them on
+//!   a later run, see the [`persistence`] module. It adds the optional
+//!   dependencies on [`serde`] and [`serde_json`].
+//!
 //! - **`unsized`** enables better conversion between unsized Builders with
 //!   [`BlueprintUnsized::into_unsized`]. **This feature requires Nightly
 //!   Rust**.
 //!
 //![`tynm`]: https://crates.io/crates/tynm
+//![`persistence`]: persistence/index.html
+//![`serde`]: https://crates.io/crates/serde
+//![`serde_json`]: https://crates.io/crates/serde_json
 //![`BlueprintUnsized::into_unsized`]: blueprint/struct.BlueprintUnsized.html#method.into_unsized
 //!
 
@@ -275,6 +309,9 @@ use std::any::Any;
 use std::hash::Hash;
 use std::fmt;
 use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
 
 use cfg_if::cfg_if;
 
@@ -311,6 +348,10 @@ cfg_if! {
 #[cfg(feature = "diagnostics")]
 pub mod diagnostics;
 
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "persistence")))]
+#[cfg(feature = "persistence")]
+pub mod persistence;
+
 cfg_if! {
 	if #[cfg(feature = "diagnostics")] {
 		use diagnostics::Doctor;
@@ -506,10 +547,274 @@ pub trait Builder<ArtCan, BCan>: Debug + 'static
 	/// to obtain an initial value for the dynamic state of this builder.
 	///
 	fn init_dyn_state(&self) -> Self::DynState;
+
+	/// Returns the policy of this builder, i.e. how the `Cache` treats its
+	/// artifacts, dynamic state and errors, see [`BuildPolicy`].
+	///
+	/// The default implementation returns the [`DefaultPolicy`]. A builder
+	/// may implement `BuildPolicy` itself and return `self` instead.
+	///
+	/// [`BuildPolicy`]: trait.BuildPolicy.html
+	/// [`DefaultPolicy`]: struct.DefaultPolicy.html
+	///
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err> {
+		&DefaultPolicy
+	}
+}
+
+/// An owned and type-erased future, as returned by [`AsyncBuilder::build`].
+///
+/// [`AsyncBuilder::build`]: trait.AsyncBuilder.html#tymethod.build
+///
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Represents a Builder for an Artifact, which is built asynchronously.
+///
+/// This is the async counterpart of the [`Builder`] trait, intended for
+/// Builders which are I/O bound. Instead of producing the Artifact right
+/// away, its [`build`] method returns a future of it. This crate does not
+/// depend on any async runtime, the returned future can be driven by any
+/// executor.
+///
+/// The Artifact of an `AsyncBuilder` is built via the async accessors of
+/// the [`Cache`] such as [`Cache::get_async`], or by other Builders via
+/// [`Resolver::resolve_async`]. Since this requires awaiting, only other
+/// `AsyncBuilder`s can depend on an `AsyncBuilder`, while an `AsyncBuilder`
+/// may depend on both kinds of Builders.
+///
+/// Otherwise, the Artifacts of `AsyncBuilder`s are cached, tracked as
+/// dependencies and invalidated just like those of `Builder`s, including
+/// the cached errors, the early cutoff and the persistence, as its
+/// [`BuildPolicy`] opts in. Also, the dynamic state is
+/// accessible from the `build` method via [`Resolver::my_state`].
+///
+/// Notice, the `Cache` is exclusively borrowed while a future returned by
+/// its async accessors is alive, thus the dependencies of an
+/// `AsyncBuilder` are built one after another.
+///
+/// [`Builder`]: trait.Builder.html
+/// [`build`]: trait.AsyncBuilder.html#tymethod.build
+/// [`Cache`]: cache/struct.Cache.html
+/// [`Cache::get_async`]: cache/struct.Cache.html#method.get_async
+/// [`Resolver::resolve_async`]: cache/struct.Resolver.html#method.resolve_async
+/// [`Resolver::my_state`]: cache/struct.Resolver.html#method.my_state
+/// [`BuildPolicy`]: trait.BuildPolicy.html
+///
+pub trait AsyncBuilder<ArtCan, BCan>: Debug + 'static
+		where
+			BCan: CanStrong, {
+
+	/// The artifact type as produced by this builder.
+	///
+	type Artifact : Debug + 'static;
+
+	/// Type of the dynamic state of this builder.
+	///
+	/// The dynamic state can be used to store mutable data for the builder
+	/// or to modify the builder for outside.
+	///
+	type DynState : Debug + 'static;
+
+	/// Error type returned by this Builder in case of failure to produce an
+	/// Artifact.
+	type Err : Debug + 'static;
+
+	/// Produces a future of an artifact using the given `Resolver` for
+	/// resolving dependencies.
+	///
+	fn build<'a>(&'a self, cache: &'a mut Resolver<'_, ArtCan, BCan, Self::DynState>)
+		-> BoxFuture<'a, Result<ArtCan::Bin, Self::Err>>
+		where
+			ArtCan: Can<Self::Artifact>;
+
+	/// Return an initial dynamic state for this builder.
+	///
+	/// When a builder is first seen by a `Cache` the cache will use this method
+	/// to obtain an initial value for the dynamic state of this builder.
+	///
+	fn init_dyn_state(&self) -> Self::DynState;
+
+	/// Returns the policy of this builder, just like [`Builder::policy`].
+	///
+	/// [`Builder::policy`]: trait.Builder.html#method.policy
+	///
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err> {
+		&DefaultPolicy
+	}
 }
 
 
 
+/// The policy of a Builder, i.e. how a `Cache` treats the artifacts,
+/// dynamic state and errors of the Builder.
+///
+/// Every method of this trait has a default implementation, which applies
+/// to Builders not opting in to the respective feature. A Builder opts in
+/// by implementing this trait, typically for itself, and returning it from
+/// the `policy` method of its Builder trait, e.g. [`Builder::policy`].
+/// Otherwise, that method returns the [`DefaultPolicy`].
+///
+/// The type parameters are the artifact Can of the `Cache` and the
+/// `Artifact`, `DynState` and `Err` types of the Builder.
+///
+/// [`Builder::policy`]: trait.Builder.html#method.policy
+/// [`DefaultPolicy`]: struct.DefaultPolicy.html
+///
+pub trait BuildPolicy<ArtCan, Artifact, DynState = (), Err = Never> {
+	/// Returns the size of the given artifact as produced by this builder.
+	///
+	/// The size is used by the `Cache` to limit the sum of the sizes of its
+	/// artifacts, see [`Cache::set_max_size`]. The unit of the size is up to
+	/// the user, it only has to be consistent across the builders of a
+	/// `Cache`. The default implementation returns the size of the artifact
+	/// type, as given by `size_of`.
+	///
+	/// [`Cache::set_max_size`]: cache/struct.Cache.html#method.set_max_size
+	///
+	fn artifact_size(&self, _artifact: &ArtCan::Bin) -> usize
+			where
+				ArtCan: Can<Artifact> {
+
+		std::mem::size_of::<Artifact>()
+	}
+
+	/// Returns how long the artifacts of this builder stay valid.
+	///
+	/// A `Cache` treats an artifact older than its time to live as invalid,
+	/// i.e. it invalidates the artifact including its dependents, and
+	/// rebuilds it when it is requested again. This is useful for
+	/// artifacts which become stale, even if none of their dependencies
+	/// changed, e.g. snapshots of some external state.
+	///
+	/// The default implementation returns `None`, i.e. the artifacts stay
+	/// valid until invalidated explicitly.
+	///
+	fn time_to_live(&self) -> Option<Duration> {
+		None
+	}
+
+	/// Tests whether the artifact of this builder became stale, because of
+	/// inputs which are not tracked by the `Cache`.
+	///
+	/// Some artifacts depend on inputs outside of the dependency graph, such
+	/// as environment variables or external services. [`Cache::validate`]
+	/// asks the builders of all cached artifacts whether they are stale, and
+	/// invalidates the stale ones including their dependents. The given
+	/// dynamic state is the one of this builder. The default implementation
+	/// returns `false`.
+	///
+	/// [`Cache::validate`]: cache/struct.Cache.html#method.validate
+	///
+	fn is_stale(&self, _dyn_state: &DynState) -> bool {
+		false
+	}
+
+	/// Returns a copy of the given error to be cached, if errors of this
+	/// builder shall be cached.
+	///
+	/// By default, a failed build caches nothing, thus the `Cache` tries to
+	/// build the artifact again on each request. A builder with a cloneable
+	/// error type may opt in to negative caching by returning
+	/// `Some(err.clone())`. Then, the cached error is returned instead of
+	/// building again, until the builder or any of the dependencies it used
+	/// before failing is invalidated. The default implementation returns
+	/// `None`.
+	///
+	fn cached_error(&self, _err: &Err) -> Option<Err> {
+		None
+	}
+
+	/// Returns a copy of the given dynamic state for a forked `Cache`.
+	///
+	/// [`Cache::fork`] shares the artifacts and the dynamic states with the
+	/// forked `Cache`. Once either `Cache` changes a shared dynamic state, it
+	/// gets its own copy from this function. A builder may opt in by
+	/// returning a copy, e.g. `Some(dyn_state.clone())`, which must not share
+	/// mutable data with the given dynamic state (e.g. via an
+	/// `Rc<RefCell<_>>`). Whether a builder opts in must not depend on the
+	/// given dynamic state.
+	///
+	/// The default implementation returns `None`, thus the forked `Cache`
+	/// drops the artifact and initializes the dynamic state anew, unless it
+	/// is the `()` unit type, which is always shared.
+	///
+	/// [`Cache::fork`]: cache/struct.Cache.html#method.fork
+	///
+	fn fork_dyn_state(&self, _dyn_state: &DynState) -> Option<DynState> {
+		None
+	}
+
+	/// Moves the given dynamic state into a `Send` box, if it is `Send`.
+	///
+	/// A [`SyncCache`] builds its artifacts in worker threads, thus it can
+	/// only keep the dynamic state of a builder across builds, if the worker
+	/// can send it back. A builder with a `Send` dynamic state may opt in by
+	/// returning `Ok(Box::new(dyn_state))`. The default implementation returns
+	/// the dynamic state unchanged, thus a `SyncCache` drops it after each
+	/// build, unless it has been set through the `SyncCache`.
+	///
+	/// [`SyncCache`]: cache/struct.SyncCache.html
+	///
+	fn send_dyn_state(&self, dyn_state: DynState)
+			-> Result<Box<dyn Any + Send>, DynState> {
+
+		Err(dyn_state)
+	}
+
+	/// Tests whether two artifacts of this builder are equal.
+	///
+	/// If the early cutoff of the `Cache` is enabled (see
+	/// [`Cache::set_early_cutoff`]), a rebuilt artifact which equals its
+	/// previous one does not require its dependents to be rebuilt. Builders
+	/// may opt in by comparing the artifacts e.g. via `PartialEq` or some
+	/// fingerprint. The default implementation returns `false`, i.e. a rebuilt
+	/// artifact always counts as changed.
+	///
+	/// [`Cache::set_early_cutoff`]: cache/struct.Cache.html#method.set_early_cutoff
+	///
+	fn artifacts_equal(&self, _old: &ArtCan::Bin, _new: &ArtCan::Bin) -> bool
+			where
+				ArtCan: Can<Artifact> {
+
+		false
+	}
+
+	/// Returns how the artifacts of this builder are stored on disk, if at
+	/// all.
+	///
+	/// Builders with serializable artifacts and dynamic states may opt in to
+	/// persistence by returning a [`Persistence`], see the [`persistence`]
+	/// module for details. The default implementation returns `None`.
+	///
+	/// **Notice: This method is only available if the `persistence` feature
+	/// has been activated**.
+	///
+	/// [`Persistence`]: persistence/struct.Persistence.html
+	/// [`persistence`]: persistence/index.html
+	///
+	#[cfg(feature = "persistence")]
+	fn persistence(&self)
+			-> Option<persistence::Persistence<ArtCan, Artifact, DynState>>
+			where
+				ArtCan: Can<Artifact> {
+
+		None
+	}
+}
+
+/// The policy of Builders, which do not opt in to any feature of the
+/// [`BuildPolicy`].
+///
+/// [`BuildPolicy`]: trait.BuildPolicy.html
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultPolicy;
+
+impl<ArtCan, Artifact, DynState, Err> BuildPolicy<ArtCan, Artifact, DynState, Err>
+		for DefaultPolicy {}
+
+
+
 /// Id to differentiate builder instances across types.
 ///
 /// Notice, this type simply wraps `*const` to the builder `Rc`s.
@@ -0,0 +1,508 @@
+//!
+//! Persistent storage of artifacts on disk.
+//!
+//! **Notice: This module is only available if the `persistence` feature has
+//! been activated**.
+//!
+//! A [`Cache`] can be given a local directory via [`Cache::set_store`], where
+//! it writes the Artifacts and dynamic states of those Builders, which opt in
+//! by returning a [`Persistence`] from [`BuildPolicy::persistence`]. On a later
+//! run (i.e. with a new `Cache` using the same directory), these Artifacts are
+//! restored instead of being built again.
+//!
+//! Each Builder is identified by a stable key plus a version, which are given
+//! by its `Persistence`. The key must be unique among the Builders of a
+//! `Cache`, and the version should be incremented whenever the Builder
+//! changes in a way that its stored Artifacts become unusable. While a
+//! Builder has a persistent Artifact in the `Cache`, the Artifacts of any
+//! other Builder with the same key are neither stored nor restored.
+//!
+//! Along with each Artifact, the fingerprints of the Artifacts of its
+//! dependencies are stored. A stored Artifact is only reused, if the
+//! Artifacts of its dependencies are still the same. Thus, an Artifact is
+//! only stored, if all its dependencies are persistent too. If a dependency
+//! has not been built or restored yet, its latest stored Artifact is assumed
+//! for the check. Should it turn out different once it is built, the
+//! restored Artifact expires.
+//!
+//! Stored Artifacts are restored when a Builder is built for the first time
+//! in a `Cache`, or after its Artifact has been evicted. Once an Artifact has
+//! been invalidated, it is built again.
+//!
+//! The dynamic state is stored along with the Artifact, and restored before
+//! it, unless the Builder already has a dynamic state in the `Cache`. A
+//! stored Artifact is only restored together with the very dynamic state it
+//! has been stored with, i.e. if the current dynamic state differs (e.g.
+//! because it has been set before the first build), the Artifact is built
+//! again.
+//!
+//! Storing and restoring is done on a best-effort basis, i.e. any failure
+//! just causes the respective Artifact to be built as usual. Notably, an
+//! Artifact is not stored at all, if it or its dynamic state fails to
+//! serialize.
+//!
+//! [`Cache`]: ../cache/struct.Cache.html
+//! [`Cache::set_store`]: ../cache/struct.Cache.html#method.set_store
+//! [`Persistence`]: struct.Persistence.html
+//! [`BuildPolicy::persistence`]: ../trait.BuildPolicy.html#method.persistence
+//!
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::BuilderId;
+use crate::Can;
+use crate::CanSized;
+
+
+
+/// Describes how the Artifacts and dynamic states of a Builder are stored.
+///
+/// An instance is returned by [`BuildPolicy::persistence`], for Builders which
+/// opt in to persistence. It requires the Artifact and the dynamic state to
+/// be serializable. If either of them fails to serialize, the Artifact is
+/// not stored, i.e. it is just built again on the next run.
+///
+/// ```
+/// use daab::*;
+/// use daab::persistence::Persistence;
+///
+/// #[derive(Debug)]
+/// struct Greeting;
+///
+/// impl rc::Builder for Greeting {
+///     type Artifact = String;
+///     type DynState = ();
+///     type Err = Never;
+///
+///     fn build(&self, _resolver: &mut rc::Resolver) -> Result<rc::BinType<String>, Never> {
+///         Ok(rc::BinType::new("Hello".to_string()))
+///     }
+///     fn init_dyn_state(&self) -> Self::DynState {}
+///
+///     fn policy(&self) -> &dyn BuildPolicy<rc::CanType, String> {
+///         self
+///     }
+/// }
+///
+/// impl BuildPolicy<rc::CanType, String> for Greeting {
+///     fn persistence(&self) -> Option<rc::Persistence<String, ()>> {
+///         Some(Persistence::new("greeting", 1))
+///     }
+/// }
+/// ```
+///
+/// [`BuildPolicy::persistence`]: ../trait.BuildPolicy.html#method.persistence
+///
+pub struct Persistence<ArtCan, T, S>
+		where
+			ArtCan: Can<T>, {
+
+	key: String,
+	version: u32,
+	store_artifact: fn(&ArtCan::Bin) -> serde_json::Result<Value>,
+	restore_artifact: fn(Value) -> serde_json::Result<ArtCan::Bin>,
+	store_dyn_state: fn(&S) -> serde_json::Result<Value>,
+	restore_dyn_state: fn(Value) -> serde_json::Result<S>,
+}
+
+impl<ArtCan, T, S> Persistence<ArtCan, T, S>
+		where
+			ArtCan: CanSized<T>,
+			<ArtCan as Can<T>>::Bin: Deref<Target = T>,
+			T: Serialize + DeserializeOwned,
+			S: Serialize + DeserializeOwned, {
+
+	/// Creates a new `Persistence` with the given stable key and version.
+	///
+	pub fn new(key: impl Into<String>, version: u32) -> Self {
+		Self {
+			key: key.into(),
+			version,
+			store_artifact: |bin| serde_json::to_value(&**bin),
+			restore_artifact: |value| {
+				serde_json::from_value(value).map(ArtCan::into_bin)
+			},
+			store_dyn_state: |state| serde_json::to_value(state),
+			restore_dyn_state: serde_json::from_value,
+		}
+	}
+}
+
+impl<ArtCan, T, S> Persistence<ArtCan, T, S>
+		where
+			ArtCan: Can<T>, {
+
+	/// Returns the stable key.
+	///
+	pub fn key(&self) -> &str {
+		&self.key
+	}
+
+	/// Returns the version.
+	///
+	pub fn version(&self) -> u32 {
+		self.version
+	}
+}
+
+impl<ArtCan, T, S> Debug for Persistence<ArtCan, T, S>
+		where
+			ArtCan: Can<T>, {
+
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Persistence")
+			.field("key", &self.key)
+			.field("version", &self.version)
+			.finish()
+	}
+}
+
+
+/// A stored Artifact along with its dynamic state.
+///
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+	key: String,
+	version: u32,
+	fingerprint: u64,
+	/// The keys and fingerprints of the dependencies.
+	dependencies: Vec<(String, u64)>,
+	artifact: Value,
+	/// The fingerprint of the dyn state.
+	dyn_state_fingerprint: u64,
+	dyn_state: Value,
+}
+
+/// A restored artifact.
+///
+pub(crate) struct Restored<Bin> {
+	/// The restored artifact.
+	pub(crate) artifact: Bin,
+
+	/// The dependencies of the artifact, which are in the cache.
+	pub(crate) dependencies: Vec<BuilderId>,
+
+	/// The dependents, which have been restored before the artifact, and
+	/// whether each of them is outdated.
+	pub(crate) dependents: Vec<(BuilderId, bool)>,
+}
+
+/// Computes a fingerprint of the given value, which is stable across runs.
+///
+/// This is the 64 bit FNV-1a hash of its JSON representation.
+///
+fn fingerprint(value: &Value) -> u64 {
+	value.to_string().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+/// The persistence state of a cache.
+///
+/// This tracks the keys and fingerprints of the persistent Artifacts in the
+/// cache, and accesses the storage directory.
+///
+#[derive(Debug, Default)]
+pub(crate) struct Persisted {
+	/// The storage directory, if any.
+	dir: Option<PathBuf>,
+
+	/// Maps builder id to key and fingerprint of its persistent artifact.
+	artifacts: HashMap<BuilderId, (String, u64)>,
+
+	/// Maps the key of a persistent artifact to its builder id.
+	keys: HashMap<String, BuilderId>,
+
+	/// Maps the key of a dependency, which has not been in the cache when its
+	/// dependents have been restored, to those dependents and the
+	/// fingerprint they recorded for it.
+	pending: HashMap<String, Vec<(BuilderId, u64)>>,
+
+	/// The builders, which have been built or restored before.
+	///
+	/// Once the artifact of such a builder has been invalidated, its stored
+	/// artifact must not be restored again.
+	seen: HashSet<BuilderId>,
+}
+
+impl Persisted {
+	/// Creates a new state without a storage directory.
+	///
+	pub(crate) fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the storage directory, creating it if necessary.
+	///
+	pub(crate) fn set_dir(&mut self, dir: &Path) -> io::Result<()> {
+		fs::create_dir_all(dir)?;
+		self.dir = Some(dir.to_path_buf());
+
+		Ok(())
+	}
+
+	/// Returns the path of the record of the given key.
+	///
+	fn path(dir: &Path, key: &str) -> PathBuf {
+		let name = fingerprint(&Value::String(key.to_string()));
+
+		dir.join(format!("{:016x}.json", name))
+	}
+
+	/// Loads the record of the given key and version, if any.
+	///
+	fn load(&self, key: &str, version: u32) -> Option<Record> {
+		let dir = self.dir.as_ref()?;
+		let data = fs::read(Self::path(dir, key)).ok()?;
+		let record: Record = serde_json::from_slice(&data).ok()?;
+
+		if record.key == key && record.version == version {
+			Some(record)
+		} else {
+			None
+		}
+	}
+
+	/// Writes the given record, replacing any previous one.
+	///
+	fn save(&self, record: &Record) -> io::Result<()> {
+		if let Some(dir) = &self.dir {
+			let path = Self::path(dir, &record.key);
+			let tmp = path.with_extension("tmp");
+
+			fs::write(&tmp, serde_json::to_vec(record)?)?;
+			fs::rename(tmp, path)?;
+		}
+
+		Ok(())
+	}
+
+	/// Restores the stored dynamic state of the given persistence, if any.
+	///
+	pub(crate) fn restore_dyn_state<ArtCan, T, S>(
+			&self,
+			persistence: &Persistence<ArtCan, T, S>,
+		) -> Option<S>
+			where
+				ArtCan: Can<T>, {
+
+		let record = self.load(&persistence.key, persistence.version)?;
+
+		(persistence.restore_dyn_state)(record.dyn_state).ok()
+	}
+
+	/// Restores the stored artifact of the given builder, if it is still
+	/// valid.
+	///
+	/// The artifact is only valid for the dyn state it has been stored with,
+	/// thus the given current dyn state must be the stored one.
+	///
+	/// The dependencies of the artifact, which are not in the cache yet, are
+	/// checked as soon as they enter it, see `register`.
+	///
+	pub(crate) fn restore<ArtCan, T, S>(
+			&mut self,
+			bid: BuilderId,
+			persistence: &Persistence<ArtCan, T, S>,
+			dyn_state: Option<&S>,
+		) -> Option<Restored<ArtCan::Bin>>
+			where
+				ArtCan: Can<T>, {
+
+		if self.is_taken(bid, &persistence.key) {
+			return None;
+		}
+
+		let record = self.load(&persistence.key, persistence.version)?;
+
+		let dyn_state = (persistence.store_dyn_state)(dyn_state?).ok()?;
+		if fingerprint(&dyn_state) != record.dyn_state_fingerprint {
+			return None;
+		}
+
+		// An evicted artifact may be restored as long as it is the same, but an
+		// invalidated one must not be restored.
+		match self.artifacts.get(&bid) {
+			Some((_, print)) => {
+				if record.fingerprint != *print {
+					return None;
+				}
+			}
+			None => {
+				if self.seen.contains(&bid) {
+					return None;
+				}
+			}
+		}
+
+		// Check the dependencies, either with the cache or with the storage
+		let mut present = Vec::new();
+		let mut absent = Vec::new();
+
+		for (key, print) in record.dependencies {
+			match self.keys.get(&key) {
+				Some(dep) => {
+					if self.artifacts[dep].1 != print {
+						return None;
+					}
+
+					present.push(*dep);
+				}
+				None => {
+					let stored = self.dir.as_ref()
+						.and_then(|dir| fs::read(Self::path(dir, &key)).ok())
+						.and_then(|data| serde_json::from_slice::<Record>(&data).ok())
+						// The file name is only a hash of the key
+						.filter(|dep| dep.key == key);
+
+					if stored.map(|dep| dep.fingerprint) != Some(print) {
+						return None;
+					}
+
+					absent.push((key, print));
+				}
+			}
+		}
+
+		let bin = (persistence.restore_artifact)(record.artifact).ok()?;
+
+		for (key, print) in absent {
+			self.pending.entry(key).or_default().push((bid, print));
+		}
+
+		let dependents = self.register(bid, &persistence.key, record.fingerprint);
+
+		Some(Restored {
+			artifact: bin,
+			dependencies: present,
+			dependents,
+		})
+	}
+
+	/// Stores the given artifact and dyn state of the given builder.
+	///
+	/// The artifact is only written, if all given dependencies are persistent,
+	/// and if both the artifact and the dyn state serialize. Otherwise, it is
+	/// silently skipped, as persistence is best-effort.
+	///
+	/// Nothing is stored, if another builder in the cache has a persistent
+	/// artifact with the same key.
+	///
+	/// Returns the dependents, which have been restored before the artifact
+	/// entered the cache, see `register`.
+	///
+	pub(crate) fn store<ArtCan, T, S>(
+			&mut self,
+			bid: BuilderId,
+			persistence: &Persistence<ArtCan, T, S>,
+			artifact: &ArtCan::Bin,
+			dyn_state: Option<&S>,
+			dependencies: impl Iterator<Item = BuilderId>,
+		) -> Vec<(BuilderId, bool)>
+			where
+				ArtCan: Can<T>, {
+
+		if self.is_taken(bid, &persistence.key) {
+			return Vec::new();
+		}
+
+		let artifact = match (persistence.store_artifact)(artifact) {
+			Ok(artifact) => artifact,
+			Err(_) => return Vec::new(),
+		};
+		let print = fingerprint(&artifact);
+
+		let dependencies: Option<Vec<_>> = dependencies
+			.map(|dep| self.artifacts.get(&dep).cloned())
+			.collect();
+
+		let dyn_state = dyn_state.and_then(|s| (persistence.store_dyn_state)(s).ok());
+
+		if let (Some(dependencies), Some(dyn_state)) = (dependencies, dyn_state) {
+			let record = Record {
+				key: persistence.key.clone(),
+				version: persistence.version,
+				fingerprint: print,
+				dependencies,
+				artifact,
+				dyn_state_fingerprint: fingerprint(&dyn_state),
+				dyn_state,
+			};
+
+			// Best-effort, i.e. it will just be rebuilt next time
+			let _ = self.save(&record);
+		}
+
+		self.register(bid, &persistence.key, print)
+	}
+
+	/// Tests whether the given key is used by another builder than the given
+	/// one, which has a persistent artifact in the cache.
+	///
+	fn is_taken(&self, bid: BuilderId, key: &str) -> bool {
+		self.keys.get(key).is_some_and(|other| *other != bid)
+	}
+
+	/// Records the key and fingerprint of a persistent artifact in the cache.
+	///
+	/// Returns the dependents, which have been restored before the artifact
+	/// entered the cache, and whether each of them is outdated, because it
+	/// has been restored with a different artifact.
+	///
+	fn register(&mut self, bid: BuilderId, key: &str, print: u64) -> Vec<(BuilderId, bool)> {
+		self.artifacts.insert(bid, (key.to_string(), print));
+		self.keys.insert(key.to_string(), bid);
+		self.seen.insert(bid);
+
+		self.pending.remove(key).into_iter().flatten()
+			.map(|(dependent, recorded)| (dependent, recorded != print))
+			.collect()
+	}
+
+	/// Forgets the persistent artifact of the given builder, if any.
+	///
+	pub(crate) fn remove(&mut self, bid: BuilderId) {
+		if let Some((key, _)) = self.artifacts.remove(&bid) {
+			self.keys.remove(&key);
+		}
+
+		for dependents in self.pending.values_mut() {
+			dependents.retain(|(dependent, _)| *dependent != bid);
+		}
+	}
+
+	/// Forgets all persistent artifacts.
+	///
+	pub(crate) fn clear(&mut self) {
+		self.artifacts.clear();
+		self.keys.clear();
+		self.pending.clear();
+	}
+
+	/// Forgets that the given builder has been seen, because it has been
+	/// removed from the cache entirely.
+	///
+	pub(crate) fn forget(&mut self, bid: BuilderId) {
+		self.seen.remove(&bid);
+	}
+
+	/// Forgets all persistent artifacts and all seen builders.
+	///
+	pub(crate) fn forget_all(&mut self) {
+		self.clear();
+		self.seen.clear();
+	}
+}
@@ -356,6 +356,7 @@ fn as_ptr<T,E>(res: Result<&T,E>) -> Result<*const T,E> {
 	res.map(|v| v as *const T)
 }
 
+#[cfg_attr(not(feature = "mut_box"), allow(dead_code))]
 fn as_ptr_mut<T,E>(res: Result<&mut T,E>) -> Result<*mut T,E> {
 	res.map(|v| v as *mut T)
 }
@@ -716,3 +717,2846 @@ fn test_complex_invalidate() {
 
 }
 
+
+
+#[derive(Debug)]
+pub(crate) struct BuilderCyclic {
+	// empty
+}
+
+impl BuilderCyclic {
+	pub(crate) fn new() -> Self {
+		Self {
+			// empty
+		}
+	}
+}
+
+impl crate::rc::Builder for BuilderCyclic {
+	type Artifact = Leaf;
+	// The next builder to resolve, and whether to use the panicking resolve
+	type DynState = Option<(rc::Blueprint<BuilderCyclic>, bool)>;
+	type Err = crate::cache::CycleError;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Leaf>, Self::Err> {
+
+		if let Some((next, panicking)) = resolver.my_state().clone() {
+			if panicking {
+				resolver.resolve(&next)?;
+			} else {
+				resolver.try_resolve(&next).map_err(|err| {
+					match err {
+						crate::cache::ResolveError::Cycle(cycle) => cycle,
+						crate::cache::ResolveError::Failed(cycle) => cycle,
+						err => panic!("Unexpected error: {}", err),
+					}
+				})?;
+			}
+		}
+
+		Ok(std::rc::Rc::new(Leaf{
+			id: COUNTER.fetch_add(1, Ordering::SeqCst),
+		}))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		None
+	}
+}
+
+#[test]
+fn test_cycle() {
+	let mut cache = rc::Cache::new();
+
+	let first = Blueprint::new(BuilderCyclic::new());
+	let second = Blueprint::new(BuilderCyclic::new());
+	let third = Blueprint::new(BuilderCyclic::new());
+
+	*cache.dyn_state_mut(&first) = Some((second.clone(), false));
+	*cache.dyn_state_mut(&second) = Some((third.clone(), false));
+
+	// No cycle yet
+	assert!(cache.get(&first).is_ok());
+
+	// Close the cycle
+	*cache.dyn_state_mut(&third) = Some((first.clone(), false));
+
+	let cycle = cache.get(&first).unwrap_err();
+
+	let ids: Vec<_> = cycle.chain().iter().map(|info| info.id()).collect();
+	assert_eq!(ids, vec![first.id(), second.id(), third.id(), first.id()]);
+	assert!(cycle.chain().iter().all(|info| {
+		info.type_name().ends_with("BuilderCyclic")
+	}));
+
+	// The cache is still usable after the cycle is broken
+	*cache.dyn_state_mut(&third) = None;
+	assert!(cache.get(&first).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "Dependency cycle detected")]
+fn test_cycle_panic() {
+	let mut cache = rc::Cache::new();
+
+	let first = Blueprint::new(BuilderCyclic::new());
+	let second = Blueprint::new(BuilderCyclic::new());
+
+	*cache.dyn_state_mut(&first) = Some((second.clone(), true));
+	*cache.dyn_state_mut(&second) = Some((first.clone(), true));
+
+	let _ = cache.get(&first);
+}
+
+#[test]
+fn test_cycle_try_get() {
+	let mut cache = rc::Cache::new();
+
+	let first = Blueprint::new(BuilderCyclic::new());
+	let second = Blueprint::new(BuilderCyclic::new());
+	let third = Blueprint::new(BuilderCyclic::new());
+
+	// Only plain resolves, thus the cycle aborts all builds
+	*cache.dyn_state_mut(&first) = Some((second.clone(), true));
+	*cache.dyn_state_mut(&second) = Some((first.clone(), true));
+
+	let cycle = match cache.try_get(&first) {
+		Err(crate::cache::ResolveError::Cycle(cycle)) => cycle,
+		res => panic!("Unexpected result: {:?}", res),
+	};
+
+	let ids: Vec<_> = cycle.chain().iter().map(|info| info.id()).collect();
+	assert_eq!(ids, vec![first.id(), second.id(), first.id()]);
+
+	// The aborted builds are not mistaken for a cycle
+	*cache.dyn_state_mut(&second) = None;
+	assert!(cache.try_get(&first).is_ok());
+
+	// The cycle is reported by the nearest `try_resolve`
+	*cache.dyn_state_mut(&first) = Some((second.clone(), false));
+	*cache.dyn_state_mut(&second) = Some((third.clone(), true));
+	*cache.dyn_state_mut(&third) = Some((second.clone(), true));
+
+	let cycle = cache.get(&first).unwrap_err();
+
+	let ids: Vec<_> = cycle.chain().iter().map(|info| info.id()).collect();
+	assert_eq!(ids, vec![second.id(), third.id(), second.id()]);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderPanicking {
+	// empty
+}
+
+impl crate::rc::Builder for BuilderPanicking {
+	type Artifact = Leaf;
+	// Whether to panic
+	type DynState = bool;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Leaf>, Self::Err> {
+
+		if *resolver.my_state() {
+			panic!("Builder panicked");
+		}
+
+		Ok(std::rc::Rc::new(Leaf{
+			id: COUNTER.fetch_add(1, Ordering::SeqCst),
+		}))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		true
+	}
+}
+
+#[test]
+fn test_build_panic() {
+	let mut cache = rc::Cache::new();
+
+	let builder = Blueprint::new(BuilderPanicking{});
+
+	let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+		cache.get(&builder)
+	}));
+	assert!(res.is_err());
+	assert!(cache.lookup(&builder).is_none());
+
+	// The panicked build is not mistaken for a cycle
+	*cache.dyn_state_mut(&builder) = false;
+	assert!(cache.get(&builder).is_ok());
+}
+
+
+
+
+#[derive(Debug)]
+pub(crate) struct BuilderSlow {
+	// Optional dependency
+	dep: Option<arc::Blueprint<BuilderSlow>>,
+	// Number of builds done so far
+	builds: AtomicU32,
+}
+
+impl BuilderSlow {
+	pub(crate) fn new(dep: Option<arc::Blueprint<BuilderSlow>>) -> Self {
+		Self {
+			dep,
+			builds: AtomicU32::new(0),
+		}
+	}
+}
+
+impl crate::arc::Builder for BuilderSlow {
+	// The dyn state at build time, and the build number
+	type Artifact = (u32, u32);
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut arc::Resolver<Self::DynState>)
+			-> Result<std::sync::Arc<Self::Artifact>, Self::Err> {
+
+		if let Some(dep) = &self.dep {
+			resolver.resolve(dep)?;
+		}
+
+		// Give other threads the chance to request it too
+		std::thread::sleep(std::time::Duration::from_millis(50));
+
+		let state = *resolver.my_state();
+		let build = self.builds.fetch_add(1, Ordering::SeqCst);
+
+		Ok(std::sync::Arc::new((state, build)))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+}
+
+#[test]
+fn test_sync_concurrent() {
+	let cache = std::sync::Arc::new(arc::SyncCache::new());
+
+	let leaf = Blueprint::new(BuilderSlow::new(None));
+	let node1 = Blueprint::new(BuilderSlow::new(Some(leaf.clone())));
+	let node2 = Blueprint::new(BuilderSlow::new(Some(leaf.clone())));
+
+	let threads: Vec<_> = (0..8).map(|i| {
+		let cache = cache.clone();
+		let leaf = leaf.clone();
+		let node = if i % 2 == 0 { node1.clone() } else { node2.clone() };
+
+		std::thread::spawn(move || {
+			let node = cache.get(&node).unpack();
+			(cache.get(&leaf).unpack(), node)
+		})
+	}).collect();
+
+	let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+
+	// Ensure each builder was built only once
+	assert_eq!(leaf.builder().builder.builds.load(Ordering::SeqCst), 1);
+	assert_eq!(node1.builder().builder.builds.load(Ordering::SeqCst), 1);
+	assert_eq!(node2.builder().builder.builds.load(Ordering::SeqCst), 1);
+
+	// Ensure all threads got the same artifacts
+	for (i, (art, node)) in results.iter().enumerate() {
+		assert!(std::sync::Arc::ptr_eq(art, &results[0].0));
+		assert!(std::sync::Arc::ptr_eq(node, &results[i % 2].1));
+	}
+
+	assert_eq!(cache.number_of_known_builders(), 3);
+}
+
+#[test]
+fn test_sync_invalidate() {
+	let cache = arc::SyncCache::new();
+
+	let leaf = Blueprint::new(BuilderSlow::new(None));
+	let node = Blueprint::new(BuilderSlow::new(Some(leaf.clone())));
+	let root = Blueprint::new(BuilderSlow::new(Some(node.clone())));
+
+	let artifact_leaf = cache.get(&leaf).unpack();
+	let artifact_node = cache.get(&node).unpack();
+	let artifact_root = cache.get(&root).unpack();
+
+	assert_eq!(cache.lookup(&node), Some(artifact_node.clone()));
+
+	// Only invalidate the intermediate node
+	cache.invalidate(&node);
+
+	assert_eq!(cache.lookup(&node), None);
+	assert_eq!(cache.lookup(&root), None);
+	assert_eq!(cache.lookup(&leaf), Some(artifact_leaf.clone()));
+
+	let artifact_node_2 = cache.get(&node).unpack();
+	let artifact_root_2 = cache.get(&root).unpack();
+
+	assert_ne!(artifact_node, artifact_node_2);
+	assert_ne!(artifact_root, artifact_root_2);
+	assert!(std::sync::Arc::ptr_eq(&cache.get(&leaf).unpack(), &artifact_leaf));
+
+	// Purging the leaf removes it and invalidates its dependents
+	cache.purge(&leaf);
+
+	assert!(!cache.is_builder_known(&leaf));
+	assert_eq!(cache.lookup(&root), None);
+
+	// Dropped builders are collected
+	drop(leaf);
+	drop(node);
+	drop(root);
+	cache.garbage_collection();
+	assert_eq!(cache.number_of_known_builders(), 0);
+}
+
+#[test]
+fn test_sync_dyn_state() {
+	let cache = arc::SyncCache::new();
+
+	let leaf = Blueprint::new(BuilderSlow::new(None));
+
+	assert_eq!(cache.get_dyn_state(&leaf), None);
+	assert_eq!(*cache.get(&leaf).unpack(), (0, 0));
+
+	// The dyn state of the build is not sent back, thus not kept
+	assert_eq!(cache.get_dyn_state(&leaf), None);
+
+	// Accessing it through the cache keeps it
+	assert_eq!(cache.dyn_state(&leaf), 0);
+	assert_eq!(cache.get_dyn_state(&leaf), Some(0));
+
+	// Setting an equal dyn state keeps the artifact
+	assert!(!cache.set_dyn_state(&leaf, 0));
+	assert_eq!(cache.lookup_cloned(&leaf), Some((0, 0)));
+
+	assert!(cache.set_dyn_state(&leaf, 42));
+	assert_eq!(cache.get_dyn_state(&leaf), Some(42));
+
+	// Setting the dyn state invalidates the artifact
+	assert_eq!(cache.get_cloned(&leaf), Ok((42, 1)));
+	assert_eq!(cache.lookup_cloned(&leaf), Some((42, 1)));
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderCountingSync {
+	// Optional dependency
+	dep: Option<arc::Blueprint<BuilderCountingSync>>,
+}
+
+impl crate::arc::Builder for BuilderCountingSync {
+	// The number of builds so far, as counted by the dyn state
+	type Artifact = u32;
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut arc::Resolver<Self::DynState>)
+			-> Result<std::sync::Arc<Self::Artifact>, Self::Err> {
+
+		if let Some(dep) = &self.dep {
+			resolver.resolve(dep)?;
+		}
+
+		*resolver.my_state() += 1;
+
+		Ok(std::sync::Arc::new(*resolver.my_state()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<arc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<arc::CanType, u32, u32, Never> for BuilderCountingSync {
+	fn send_dyn_state(&self, dyn_state: u32)
+			-> Result<Box<dyn std::any::Any + Send>, u32> {
+
+		Ok(Box::new(dyn_state))
+	}
+}
+
+#[test]
+fn test_sync_dyn_state_kept() {
+	let cache = arc::SyncCache::new();
+
+	let leaf = Blueprint::new(BuilderCountingSync { dep: None });
+	let root = Blueprint::new(BuilderCountingSync { dep: Some(leaf.clone()) });
+
+	assert_eq!(cache.get_cloned(&root), Ok(1));
+
+	// The dyn states modified by the builds are kept, including the one of
+	// the dependency
+	assert_eq!(cache.get_dyn_state(&root), Some(1));
+	assert_eq!(cache.get_dyn_state(&leaf), Some(1));
+
+	cache.invalidate(&leaf);
+
+	assert_eq!(cache.get_cloned(&root), Ok(2));
+	assert_eq!(cache.get_cloned(&leaf), Ok(2));
+}
+
+
+
+#[derive(Debug, Default)]
+pub(crate) struct BuilderFanOut {
+	// Number of builds currently running, and the maximum thereof
+	running: std::sync::Arc<std::sync::Mutex<(u32, u32)>>,
+}
+
+impl BuilderFanOut {
+	pub(crate) fn new(running: &std::sync::Arc<std::sync::Mutex<(u32, u32)>>) -> Self {
+		Self {
+			running: running.clone(),
+		}
+	}
+}
+
+impl crate::arc::Builder for BuilderFanOut {
+	// The number of dependencies
+	type Artifact = usize;
+	// The dependencies to resolve
+	type DynState = Vec<arc::Blueprint<BuilderFanOut>>;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut arc::Resolver<Self::DynState>)
+			-> Result<std::sync::Arc<Self::Artifact>, Self::Err> {
+
+		let deps = resolver.my_state().clone();
+		let arts = resolver.resolve_all(&deps)?;
+
+		{
+			let mut running = self.running.lock().unwrap();
+			running.0 += 1;
+			running.1 = running.1.max(running.0);
+		}
+
+		std::thread::sleep(std::time::Duration::from_millis(50));
+
+		self.running.lock().unwrap().0 -= 1;
+
+		Ok(std::sync::Arc::new(arts.len()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		Vec::new()
+	}
+}
+
+#[test]
+fn test_resolve_all_parallel() {
+	let cache = arc::SyncCache::new();
+	let running = Default::default();
+
+	let leafs: Vec<_> = (0..4).map(|_| Blueprint::new(BuilderFanOut::new(&running))).collect();
+	let root = Blueprint::new(BuilderFanOut::new(&running));
+
+	cache.set_max_threads(2);
+	assert_eq!(cache.max_threads(), 2);
+
+	cache.set_dyn_state(&root, leafs.clone());
+
+	assert_eq!(cache.get_cloned(&root), Ok(4));
+
+	// Ensure the leafs have been built concurrently, but by two threads only
+	assert_eq!(running.lock().unwrap().1, 2);
+
+	// Ensure the dependencies have been recorded
+	assert_eq!(cache.number_of_known_builders(), 5);
+	for leaf in &leafs {
+		assert_eq!(cache.lookup_cloned(leaf), Some(0));
+	}
+
+	cache.invalidate(&leafs[2]);
+	assert_eq!(cache.lookup_cloned(&root), None);
+	assert_eq!(cache.lookup_cloned(&leafs[1]), Some(0));
+}
+
+#[test]
+fn test_resolve_all_sequential() {
+	let mut cache = arc::Cache::new();
+	let running = Default::default();
+
+	let leafs: Vec<_> = (0..3).map(|_| Blueprint::new(BuilderFanOut::new(&running))).collect();
+	let root = Blueprint::new(BuilderFanOut::new(&running));
+
+	*cache.dyn_state_mut(&root) = leafs.clone();
+
+	assert_eq!(cache.get_cloned(&root), Ok(3));
+
+	// Without a `SyncCache`, one is built after another
+	assert_eq!(running.lock().unwrap().1, 1);
+
+	cache.invalidate(&leafs[0]);
+	assert_eq!(cache.lookup_cloned(&root), None);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderSumEach {
+	deps: Vec<rc::Blueprint<BuilderSized>>,
+}
+
+impl crate::rc::Builder for BuilderSumEach {
+	// The total size of the dependencies
+	type Artifact = usize;
+	type DynState = ();
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Self::Err> {
+
+		let arts = resolver.resolve_each(&self.deps)?;
+
+		Ok(std::rc::Rc::new(arts.iter().map(|art| art.0.len()).sum()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_resolve_each() {
+	let mut cache = rc::Cache::new();
+
+	let deps: Vec<_> = (1..=3).map(|n| Blueprint::new(BuilderSized::new(n))).collect();
+	let sum = Blueprint::new(BuilderSumEach {
+		deps: deps.clone(),
+	});
+
+	assert_eq!(*cache.get(&sum).unpack(), 6);
+
+	// Ensure the dependencies have been recorded
+	cache.invalidate(&deps[1]);
+	assert!(cache.lookup(&sum).is_none());
+	assert_eq!(*cache.get(&sum).unpack(), 6);
+}
+
+#[test]
+#[should_panic(expected = "Dependency cycle detected")]
+fn test_resolve_all_cycle() {
+	let cache = arc::SyncCache::new();
+	let running = Default::default();
+
+	let root = Blueprint::new(BuilderFanOut::new(&running));
+	let left = Blueprint::new(BuilderFanOut::new(&running));
+	let right = Blueprint::new(BuilderFanOut::new(&running));
+
+	cache.set_dyn_state(&root, vec![left.clone(), right.clone()]);
+	cache.set_dyn_state(&left, vec![root.clone()]);
+
+	let _ = cache.get(&root);
+}
+
+
+
+// Minimal executor, polling the given future until it is ready
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+	use std::task::{Context, Poll, Wake, Waker};
+
+	struct ThreadWaker(std::thread::Thread);
+
+	impl Wake for ThreadWaker {
+		fn wake(self: std::sync::Arc<Self>) {
+			self.0.unpark();
+		}
+	}
+
+	let waker = Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())));
+	let mut cx = Context::from_waker(&waker);
+	let mut future = Box::pin(future);
+
+	loop {
+		match future.as_mut().poll(&mut cx) {
+			Poll::Ready(output) => return output,
+			Poll::Pending => std::thread::park(),
+		}
+	}
+}
+
+// Future which is pending once, yielding to the executor
+struct YieldNow(bool);
+
+impl std::future::Future for YieldNow {
+	type Output = ();
+
+	fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>)
+			-> std::task::Poll<()> {
+
+		if self.0 {
+			std::task::Poll::Ready(())
+		} else {
+			self.0 = true;
+			cx.waker().wake_by_ref();
+			std::task::Poll::Pending
+		}
+	}
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderAsyncNode {
+	// Async dependency, and sync dependency
+	dep: Option<rc::Blueprint<BuilderAsyncNode>>,
+	leaf: rc::Blueprint<BuilderLeaf>,
+	fail: bool,
+}
+
+impl BuilderAsyncNode {
+	pub(crate) fn new(
+			dep: Option<rc::Blueprint<BuilderAsyncNode>>,
+			leaf: rc::Blueprint<BuilderLeaf>,
+			fail: bool,
+		) -> Self {
+
+		Self {
+			dep,
+			leaf,
+			fail,
+		}
+	}
+}
+
+impl crate::rc::AsyncBuilder for BuilderAsyncNode {
+	// The leaf id, the build number, and the leaf id of the dependency
+	type Artifact = (u32, u32, Option<u32>);
+	// The number of builds
+	type DynState = u32;
+	type Err = ();
+
+	fn build<'a>(&'a self, resolver: &'a mut rc::Resolver<'_, Self::DynState>)
+			-> BoxFuture<'a, Result<std::rc::Rc<Self::Artifact>, Self::Err>> {
+
+		Box::pin(async move {
+			YieldNow(false).await;
+
+			let leaf = resolver.resolve(&self.leaf).unpack();
+
+			let dep = match &self.dep {
+				Some(dep) => Some(resolver.resolve_async(dep).await?.0),
+				None => None,
+			};
+
+			YieldNow(false).await;
+
+			if self.fail {
+				return Err(());
+			}
+
+			*resolver.my_state() += 1;
+
+			Ok(std::rc::Rc::new((leaf.id, *resolver.my_state(), dep)))
+		})
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+}
+
+#[test]
+fn test_async() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let inner = Blueprint::new(BuilderAsyncNode::new(None, leaf.clone(), false));
+	let outer = Blueprint::new(BuilderAsyncNode::new(Some(inner.clone()), leaf.clone(), false));
+
+	let artifact_outer = block_on(cache.get_async(&outer)).unwrap();
+	let artifact_inner = block_on(cache.get_async(&inner)).unwrap();
+
+	// Ensure same builder results in same artifact
+	assert!(std::rc::Rc::ptr_eq(&artifact_outer, &block_on(cache.get_async(&outer)).unwrap()));
+	assert_eq!(*artifact_outer, (artifact_inner.0, 1, Some(artifact_inner.0)));
+	assert_eq!(artifact_outer.0, cache.get(&leaf).unpack().id);
+
+	// Invalidation cascades through the async builders
+	cache.invalidate(&leaf);
+	assert!(cache.lookup(&leaf).is_none());
+
+	let artifact_outer_2 = block_on(cache.get_async_cloned(&outer)).unwrap();
+	let leaf_id = cache.get(&leaf).unpack().id;
+
+	// The dyn state survived the invalidation
+	assert_eq!(artifact_outer_2, (leaf_id, 2, Some(leaf_id)));
+}
+
+#[test]
+fn test_async_err() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let inner = Blueprint::new(BuilderAsyncNode::new(None, leaf.clone(), true));
+	let outer = Blueprint::new(BuilderAsyncNode::new(Some(inner.clone()), leaf.clone(), false));
+
+	assert_eq!(block_on(cache.get_async(&outer)), Err(()));
+
+	// The sync dependency was built anyway
+	assert!(cache.lookup(&leaf).is_some());
+}
+
+struct NoopWake;
+
+impl std::task::Wake for NoopWake {
+	fn wake(self: std::sync::Arc<Self>) {}
+}
+
+#[test]
+fn test_async_abort() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let node = Blueprint::new(BuilderAsyncNode::new(None, leaf.clone(), false));
+
+	{
+		// Poll the build only once, then abort it
+		let waker = std::task::Waker::from(std::sync::Arc::new(NoopWake));
+		let mut cx = std::task::Context::from_waker(&waker);
+		let mut future = Box::pin(cache.get_async(&node));
+
+		assert!(std::future::Future::poll(future.as_mut(), &mut cx).is_pending());
+	}
+
+	// The aborted build is not mistaken for a cycle
+	assert_eq!(block_on(cache.get_async_cloned(&node)).map(|art| art.1), Ok(1));
+
+	// Neither by sync builds
+	cache.invalidate(&leaf);
+	assert!(cache.get(&leaf).is_ok());
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BuilderAsyncCyclic {
+	// The next builder to resolve, and whether to use the panicking resolve
+	next: std::cell::RefCell<Option<(rc::Blueprint<BuilderAsyncCyclic>, bool)>>,
+}
+
+impl crate::rc::AsyncBuilder for BuilderAsyncCyclic {
+	type Artifact = Leaf;
+	type DynState = ();
+	type Err = crate::cache::CycleError;
+
+	fn build<'a>(&'a self, resolver: &'a mut rc::Resolver<'_, Self::DynState>)
+			-> BoxFuture<'a, Result<std::rc::Rc<Self::Artifact>, Self::Err>> {
+
+		Box::pin(async move {
+			YieldNow(false).await;
+
+			let next = self.next.borrow().clone();
+
+			if let Some((next, panicking)) = next {
+				if panicking {
+					resolver.resolve_async(&next).await?;
+				} else {
+					resolver.try_resolve_async(&next).await.map_err(|err| {
+						match err {
+							crate::cache::ResolveError::Cycle(cycle) => cycle,
+							crate::cache::ResolveError::Failed(cycle) => cycle,
+							err => panic!("Unexpected error: {}", err),
+						}
+					})?;
+				}
+			}
+
+			Ok(std::rc::Rc::new(Leaf{
+				id: COUNTER.fetch_add(1, Ordering::SeqCst),
+			}))
+		})
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_async_cycle() {
+	let mut cache = rc::Cache::new();
+
+	let first = Blueprint::new(BuilderAsyncCyclic::default());
+	let second = Blueprint::new(BuilderAsyncCyclic::default());
+
+	// Only plain resolves, thus the cycle aborts all builds
+	*first.builder().builder.next.borrow_mut() = Some((second.clone(), true));
+	*second.builder().builder.next.borrow_mut() = Some((first.clone(), true));
+
+	let cycle = match block_on(cache.try_get_async(&first)) {
+		Err(crate::cache::ResolveError::Cycle(cycle)) => cycle,
+		res => panic!("Unexpected result: {:?}", res),
+	};
+
+	let ids: Vec<_> = cycle.chain().iter().map(|info| info.id()).collect();
+	assert_eq!(ids, vec![first.id(), second.id(), first.id()]);
+
+	// The cycle is returned to the `try_resolve_async` of the builder
+	*second.builder().builder.next.borrow_mut() = Some((first.clone(), false));
+
+	let cycle = block_on(cache.get_async(&first)).unwrap_err();
+
+	let ids: Vec<_> = cycle.chain().iter().map(|info| info.id()).collect();
+	assert_eq!(ids, vec![first.id(), second.id(), first.id()]);
+
+	// The aborted builds are not mistaken for a cycle
+	*second.builder().builder.next.borrow_mut() = None;
+	assert!(block_on(cache.try_get_async(&first)).is_ok());
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BuilderAsyncValue {
+	// The value to build, failing if negative
+	value: std::cell::Cell<i32>,
+	// Number of builds done so far
+	builds: std::cell::Cell<u32>,
+}
+
+impl crate::rc::AsyncBuilder for BuilderAsyncValue {
+	type Artifact = i32;
+	type DynState = ();
+	type Err = i32;
+
+	fn build<'a>(&'a self, _resolver: &'a mut rc::Resolver<'_, Self::DynState>)
+			-> BoxFuture<'a, Result<std::rc::Rc<Self::Artifact>, Self::Err>> {
+
+		Box::pin(async move {
+			self.builds.set(self.builds.get() + 1);
+
+			YieldNow(false).await;
+
+			match self.value.get() {
+				value if value < 0 => Err(value),
+				value => Ok(std::rc::Rc::new(value)),
+			}
+		})
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, i32, (), i32> for BuilderAsyncValue {
+	fn cached_error(&self, err: &i32) -> Option<i32> {
+		Some(*err)
+	}
+	fn artifacts_equal(&self, old: &std::rc::Rc<i32>, new: &std::rc::Rc<i32>) -> bool {
+		old == new
+	}
+}
+
+#[test]
+fn test_async_hooks() {
+	let mut cache = rc::Cache::new();
+	cache.set_early_cutoff(true);
+
+	let value = Blueprint::new(BuilderAsyncValue::default());
+
+	// The error is cached, like the one of a sync builder
+	value.builder().builder.value.set(-1);
+	assert_eq!(block_on(cache.get_async(&value)), Err(-1));
+	assert_eq!(block_on(cache.get_async_cloned(&value)), Err(-1));
+	assert_eq!(value.builder().builder.builds.get(), 1);
+
+	value.builder().builder.value.set(2);
+	cache.invalidate(&value);
+	let old = block_on(cache.get_async(&value)).unwrap();
+	assert_eq!(value.builder().builder.builds.get(), 2);
+
+	// An equal rebuilt artifact is kept
+	cache.invalidate(&value);
+	let new = block_on(cache.get_async(&value)).unwrap();
+	assert_eq!(value.builder().builder.builds.get(), 3);
+	assert!(std::rc::Rc::ptr_eq(&old, &new));
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderSized {
+	size: usize,
+}
+
+impl BuilderSized {
+	pub(crate) fn new(size: usize) -> Self {
+		Self {
+			size,
+		}
+	}
+}
+
+impl crate::rc::Builder for BuilderSized {
+	// The payload and the build number
+	type Artifact = (Vec<u8>, u32);
+	// The number of builds
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		*resolver.my_state() += 1;
+
+		Ok(std::rc::Rc::new((vec![0; self.size], *resolver.my_state())))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, (Vec<u8>, u32), u32, Never> for BuilderSized {
+	fn artifact_size(&self, artifact: &std::rc::Rc<(Vec<u8>, u32)>) -> usize {
+		artifact.0.len()
+	}
+}
+
+#[test]
+fn test_evict_lru() {
+	let mut cache = rc::Cache::new();
+	cache.set_max_artifacts(Some(2));
+
+	let leaf1 = Blueprint::new(BuilderLeaf::new());
+	let leaf2 = Blueprint::new(BuilderLeaf::new());
+	let leaf3 = Blueprint::new(BuilderLeaf::new());
+
+	let artifact1 = cache.get(&leaf1).unpack();
+	let artifact2 = cache.get(&leaf2).unpack();
+
+	// Use the first, thus the second is the least recently used one
+	assert!(cache.lookup(&leaf1).is_some());
+
+	cache.get(&leaf3).unpack();
+
+	assert_eq!(cache.number_of_artifacts(), 2);
+	assert!(cache.lookup(&leaf2).is_none());
+	assert_eq!(cache.lookup(&leaf1), Some(artifact1));
+
+	// The evicted artifact is transparently rebuilt
+	assert_ne!(cache.get(&leaf2).unpack(), artifact2);
+	assert_eq!(cache.number_of_artifacts(), 2);
+
+	// Tightening the limit evicts immediately
+	cache.set_max_artifacts(Some(1));
+	assert_eq!(cache.number_of_artifacts(), 1);
+	assert!(cache.lookup(&leaf2).is_some());
+}
+
+#[test]
+fn test_evict_dependents() {
+	let mut cache = rc::Cache::new();
+	cache.set_max_artifacts(Some(1));
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let node = Blueprint::new(BuilderSimpleNode::new(leaf.clone()));
+
+	let artifact_node = cache.get(&node).unpack();
+
+	// The leaf got evicted, but the node keeps its artifact
+	assert!(cache.lookup(&leaf).is_none());
+	assert_eq!(cache.lookup(&node), Some(artifact_node));
+
+	// Invalidating the evicted artifact still cascades
+	cache.invalidate(&leaf);
+	assert!(cache.lookup(&node).is_none());
+}
+
+#[test]
+fn test_evict_size() {
+	let mut cache = rc::Cache::new();
+	cache.set_max_size(Some(10));
+
+	let small = Blueprint::new(BuilderSized::new(4));
+	let big1 = Blueprint::new(BuilderSized::new(6));
+	let big2 = Blueprint::new(BuilderSized::new(6));
+
+	cache.get(&small).unpack();
+	cache.get(&big1).unpack();
+	assert_eq!(cache.artifacts_size(), 10);
+
+	cache.get(&big2).unpack();
+	assert_eq!(cache.artifacts_size(), 6);
+	assert!(cache.lookup(&small).is_none());
+	assert!(cache.lookup(&big1).is_none());
+
+	// The dyn state survived the eviction
+	assert_eq!(cache.get(&big1).unpack().1, 2);
+	assert_eq!(*cache.dyn_state(&big1), 2);
+
+	// A single artifact may exceed the limit on its own
+	let huge = Blueprint::new(BuilderSized::new(20));
+	cache.get(&huge).unpack();
+	assert_eq!(cache.artifacts_size(), 20);
+
+	cache.clear_artifacts();
+	assert_eq!(cache.artifacts_size(), 0);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderExpiring {
+	dep: Option<rc::Blueprint<BuilderExpiring>>,
+	ttl: Option<std::time::Duration>,
+}
+
+impl BuilderExpiring {
+	pub(crate) fn new(
+			dep: Option<rc::Blueprint<BuilderExpiring>>,
+			ttl: Option<std::time::Duration>,
+		) -> Self {
+
+		Self {
+			dep,
+			ttl,
+		}
+	}
+}
+
+impl crate::rc::Builder for BuilderExpiring {
+	// The build number, and the one of the dependency
+	type Artifact = (u32, Option<u32>);
+	// The number of builds
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let dep = self.dep.as_ref().map(|dep| resolver.resolve(dep).unpack().0);
+
+		*resolver.my_state() += 1;
+
+		Ok(std::rc::Rc::new((*resolver.my_state(), dep)))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, (u32, Option<u32>), u32, Never> for BuilderExpiring {
+	fn time_to_live(&self) -> Option<std::time::Duration> {
+		self.ttl
+	}
+}
+
+#[test]
+fn test_expiry() {
+	use std::time::Duration;
+
+	let clock = crate::cache::ManualClock::new();
+	let mut cache = rc::Cache::new();
+	cache.set_clock(clock.clone());
+
+	let snapshot = Blueprint::new(BuilderExpiring::new(None, Some(Duration::from_secs(10))));
+
+	assert_eq!(cache.get_cloned(&snapshot).unpack(), (1, None));
+
+	clock.advance(Duration::from_secs(5));
+	assert_eq!(cache.get_cloned(&snapshot).unpack(), (1, None));
+
+	// The artifact expired, but the dyn state survived
+	clock.advance(Duration::from_secs(5));
+	assert!(cache.lookup(&snapshot).is_none());
+	assert_eq!(cache.get_cloned(&snapshot).unpack(), (2, None));
+
+	// The time to live starts over with the rebuild
+	clock.advance(Duration::from_secs(5));
+	assert_eq!(cache.lookup_cloned(&snapshot), Some((2, None)));
+}
+
+#[test]
+fn test_expiry_dependents() {
+	use std::time::Duration;
+
+	let clock = crate::cache::ManualClock::new();
+	let mut cache = rc::Cache::new();
+	cache.set_clock(clock.clone());
+
+	let snapshot = Blueprint::new(BuilderExpiring::new(None, Some(Duration::from_secs(10))));
+	let node = Blueprint::new(BuilderExpiring::new(Some(snapshot.clone()), None));
+	let lasting = Blueprint::new(BuilderExpiring::new(None, None));
+
+	assert_eq!(cache.get_cloned(&node).unpack(), (1, Some(1)));
+	assert_eq!(cache.get_cloned(&lasting).unpack(), (1, None));
+
+	// The node expires along with its dependency
+	clock.advance(Duration::from_secs(10));
+	assert!(cache.lookup(&node).is_none());
+	assert_eq!(cache.get_cloned(&node).unpack(), (2, Some(2)));
+
+	// Artifacts without time to live never expire
+	clock.advance(Duration::from_secs(1000));
+	assert_eq!(cache.lookup_cloned(&lasting), Some((1, None)));
+	assert_eq!(cache.get_cloned(&lasting).unpack(), (1, None));
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderCutoff {
+	dep: Option<rc::Blueprint<BuilderCutoff>>,
+	compare: bool,
+	builds: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl BuilderCutoff {
+	pub(crate) fn new(dep: Option<rc::Blueprint<BuilderCutoff>>, compare: bool) -> Self {
+		Self {
+			dep,
+			compare,
+			builds: Default::default(),
+		}
+	}
+}
+
+impl crate::rc::Builder for BuilderCutoff {
+	// Half of the input, or the artifact of the dependency plus one
+	type Artifact = u32;
+	// The input
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let value = match &self.dep {
+			Some(dep) => *resolver.resolve(dep).unpack() + 1,
+			None => *resolver.my_state() / 2,
+		};
+
+		self.builds.set(self.builds.get() + 1);
+
+		Ok(std::rc::Rc::new(value))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		4
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, u32, u32, Never> for BuilderCutoff {
+	fn artifacts_equal(&self, old: &std::rc::Rc<u32>, new: &std::rc::Rc<u32>) -> bool {
+		self.compare && old == new
+	}
+}
+
+fn cutoff_chain(compare: bool) -> [rc::Blueprint<BuilderCutoff>; 3] {
+	let leaf = Blueprint::new(BuilderCutoff::new(None, compare));
+	let node = Blueprint::new(BuilderCutoff::new(Some(leaf.clone()), compare));
+	let top = Blueprint::new(BuilderCutoff::new(Some(node.clone()), compare));
+
+	[leaf, node, top]
+}
+
+fn cutoff_builds(chain: &[rc::Blueprint<BuilderCutoff>; 3]) -> [u32; 3] {
+	[
+		chain[0].builder().builder.builds.get(),
+		chain[1].builder().builder.builds.get(),
+		chain[2].builder().builder.builds.get(),
+	]
+}
+
+#[test]
+fn test_early_cutoff() {
+	let mut cache = rc::Cache::new();
+	cache.set_early_cutoff(true);
+	assert!(cache.early_cutoff());
+
+	let chain = cutoff_chain(true);
+	let [leaf, node, top] = &chain;
+
+	assert_eq!(*cache.get(top).unpack(), 4);
+	assert_eq!(cutoff_builds(&chain), [1, 1, 1]);
+
+	// The leaf rebuilds to an equal artifact, thus the others remain
+	*cache.dyn_state_mut(leaf) = 5;
+	assert!(cache.lookup(top).is_none());
+	let old = cache.get(node).unpack();
+	assert_eq!(*cache.get(top).unpack(), 4);
+	assert_eq!(cutoff_builds(&chain), [2, 1, 1]);
+	assert!(std::rc::Rc::ptr_eq(&old, &cache.get(node).unpack()));
+
+	// The leaf changes, thus the others are rebuilt
+	*cache.dyn_state_mut(leaf) = 6;
+	assert_eq!(*cache.get(top).unpack(), 5);
+	assert_eq!(cutoff_builds(&chain), [3, 2, 2]);
+}
+
+#[test]
+fn test_early_cutoff_changed() {
+	let mut cache = rc::Cache::new();
+	cache.set_early_cutoff(true);
+
+	// Without comparing, any rebuilt artifact counts as changed
+	let chain = cutoff_chain(false);
+	let [leaf, _, top] = &chain;
+
+	assert_eq!(*cache.get(top).unpack(), 4);
+
+	*cache.dyn_state_mut(leaf) = 5;
+	assert_eq!(*cache.get(top).unpack(), 4);
+	assert_eq!(cutoff_builds(&chain), [2, 2, 2]);
+}
+
+#[test]
+fn test_early_cutoff_disabled() {
+	let mut cache = rc::Cache::new();
+	assert!(!cache.early_cutoff());
+
+	let chain = cutoff_chain(true);
+	let [leaf, node, top] = &chain;
+
+	assert_eq!(*cache.get(top).unpack(), 4);
+
+	// Invalidation drops the dependents
+	*cache.dyn_state_mut(leaf) = 5;
+	assert!(cache.lookup(node).is_none());
+	assert_eq!(*cache.get(top).unpack(), 4);
+	assert_eq!(cutoff_builds(&chain), [2, 2, 2]);
+
+	// Disabling drops the dirty artifacts
+	cache.set_early_cutoff(true);
+	cache.invalidate(leaf);
+	cache.set_early_cutoff(false);
+	assert_eq!(cache.number_of_artifacts(), 0);
+	assert_eq!(*cache.get(top).unpack(), 4);
+	assert_eq!(cutoff_builds(&chain), [3, 3, 3]);
+}
+
+#[test]
+fn test_graph_introspection() {
+	let mut cache = rc::Cache::new();
+
+	let chain = cutoff_chain(false);
+	let [leaf, node, top] = &chain;
+	let other = Blueprint::new(BuilderCutoff::new(Some(leaf.clone()), false));
+
+	cache.get(top).unpack();
+	cache.get(&other).unpack();
+
+	let ids = |infos: Vec<crate::cache::BuilderInfo>| -> Vec<BuilderId> {
+		infos.iter().map(|info| info.id()).collect()
+	};
+	let sorted = |mut ids: Vec<BuilderId>| {
+		ids.sort();
+		ids
+	};
+
+	assert_eq!(ids(cache.dependencies_of(top)), vec![node.id()]);
+	assert_eq!(ids(cache.dependencies_of(leaf)), vec![]);
+	assert_eq!(ids(cache.dependents_of(leaf)), sorted(vec![node.id(), other.id()]));
+	assert_eq!(ids(cache.transitive_dependencies_of(top)), sorted(vec![leaf.id(), node.id()]));
+	assert_eq!(ids(cache.transitive_dependents_of(leaf)),
+		sorted(vec![node.id(), top.id(), other.id()]));
+
+	let known = cache.known_builders();
+	assert_eq!(known.len(), 4);
+	assert!(known.iter().all(|info| info.type_name().ends_with("BuilderCutoff")));
+
+	// Each builder comes after its dependencies
+	let order = ids(cache.topological_order());
+	let pos = |bid: BuilderId| order.iter().position(|id| *id == bid).unwrap();
+	assert_eq!(order.len(), 4);
+	assert!(pos(leaf.id()) < pos(node.id()));
+	assert!(pos(node.id()) < pos(top.id()));
+	assert!(pos(leaf.id()) < pos(other.id()));
+
+	// Invalidation drops the dependencies
+	cache.invalidate(node);
+	assert_eq!(ids(cache.dependents_of(leaf)), vec![other.id()]);
+}
+
+#[test]
+fn test_snapshot() {
+	let mut cache = rc::Cache::new();
+
+	let chain = cutoff_chain(false);
+	let [leaf, node, _] = &chain;
+
+	cache.get(node).unpack();
+	cache.invalidate(node);
+	cache.get(leaf).unpack();
+	cache.get(node).unpack();
+	cache.dyn_state(leaf);
+
+	// The top has never been used, thus it is unknown
+	let snapshot = cache.snapshot();
+	assert_eq!(snapshot.builders().len(), 2);
+	assert!(snapshot.builders().iter().all(|entry| entry.has_artifact()));
+	assert_eq!(snapshot.dependencies(), &[(node.id(), leaf.id())]);
+
+	let dot = snapshot.to_dot();
+	assert!(dot.starts_with("strict digraph {"));
+	assert!(dot.contains(&format!(r#""{:p}" -> "{:p}""#, node.id(), leaf.id())));
+	assert!(dot.contains("peripheries = 2"));
+
+	let json = snapshot.to_json();
+	assert!(json.contains(&format!(r#"{{"user":"{:p}","dependency":"{:p}"}}"#, node.id(), leaf.id())));
+	assert!(json.contains(r#""artifact":true,"dyn_state":true"#));
+
+	// Invalidated artifacts are gone
+	cache.invalidate(leaf);
+	let snapshot = cache.snapshot();
+	assert!(snapshot.builders().iter().all(|entry| !entry.has_artifact()));
+	assert!(snapshot.dependencies().is_empty());
+}
+
+#[test]
+fn test_warm() {
+	let mut cache = rc::Cache::new();
+
+	let chain = cutoff_chain(false);
+	let [leaf, _, top] = &chain;
+	let missing = Blueprint::new(rc::FileInputBuilder::new("/nonexistent/daab/input"));
+
+	let report = cache.warm(&[top, &missing, leaf]);
+	assert_eq!(report.builds(), 4);
+	assert_eq!(cutoff_builds(&chain), [1, 1, 1]);
+	assert!(!report.is_success());
+	assert_eq!(report.failed().len(), 1);
+	assert_eq!(report.failed()[0].0.id(), missing.id());
+
+	// Only the failed one is built again
+	let report = cache.warm(&[top, &missing, leaf]);
+	assert_eq!(report.builds(), 1);
+	assert_eq!(report.failed().len(), 1);
+
+	let report = cache.warm(&[top]);
+	assert_eq!(report.builds(), 0);
+	assert!(report.is_success());
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderBroken {
+	dep: Option<rc::Blueprint<BuilderCutoff>>,
+	cache_errors: bool,
+	attempts: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl crate::rc::Builder for BuilderBroken {
+	type Artifact = ();
+	type DynState = ();
+	type Err = String;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, String> {
+
+		if let Some(dep) = &self.dep {
+			resolver.resolve(dep).unpack();
+		}
+
+		self.attempts.set(self.attempts.get() + 1);
+
+		Err(format!("broken #{}", self.attempts.get()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, (), (), String> for BuilderBroken {
+	fn cached_error(&self, err: &String) -> Option<String> {
+		if self.cache_errors {
+			Some(err.clone())
+		} else {
+			None
+		}
+	}
+}
+
+#[test]
+fn test_cached_error() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderCutoff::new(None, false));
+	let attempts = std::rc::Rc::new(std::cell::Cell::new(0));
+	let broken = Blueprint::new(BuilderBroken {
+		dep: Some(leaf.clone()),
+		cache_errors: true,
+		attempts: attempts.clone(),
+	});
+
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #1");
+	assert_eq!(cache.get_ref(&broken).unwrap_err(), "broken #1");
+	assert_eq!(attempts.get(), 1);
+
+	// Invalidating a dependency retries the build
+	cache.invalidate(&leaf);
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #2");
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #2");
+
+	// Just like invalidating the builder itself
+	cache.invalidate(&broken);
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #3");
+	assert_eq!(attempts.get(), 3);
+}
+
+#[test]
+fn test_uncached_error() {
+	let mut cache = rc::Cache::new();
+
+	let attempts = std::rc::Rc::new(std::cell::Cell::new(0));
+	let broken = Blueprint::new(BuilderBroken {
+		dep: None,
+		cache_errors: false,
+		attempts: attempts.clone(),
+	});
+
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #1");
+	assert_eq!(cache.get(&broken).unwrap_err(), "broken #2");
+	assert_eq!(attempts.get(), 2);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderContext {
+	broken: Option<rc::Blueprint<BuilderBroken>>,
+	forward: Option<rc::Blueprint<BuilderForward>>,
+	dep: Option<rc::Blueprint<BuilderContext>>,
+}
+
+impl crate::rc::Builder for BuilderContext {
+	type Artifact = ();
+	type DynState = ();
+	type Err = crate::cache::ContextError<String>;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Self::Err> {
+
+		if let Some(broken) = &self.broken {
+			resolver.resolve_with_context(broken)?;
+		}
+		if let Some(forward) = &self.forward {
+			resolver.resolve_with_context(forward)?;
+		}
+		if let Some(dep) = &self.dep {
+			resolver.resolve_with_context(dep)?;
+		}
+
+		Ok(std::rc::Rc::new(()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_error_context() {
+	let mut cache = rc::Cache::new();
+
+	let broken = Blueprint::new(BuilderBroken {
+		dep: None,
+		cache_errors: false,
+		attempts: std::rc::Rc::new(std::cell::Cell::new(0)),
+	});
+	let inner = Blueprint::new(BuilderContext {
+		broken: Some(broken.clone()),
+		forward: None,
+		dep: None,
+	});
+	let outer = Blueprint::new(BuilderContext {
+		broken: None,
+		forward: None,
+		dep: Some(inner.clone()),
+	});
+
+	let err = cache.get(&outer).unwrap_err();
+
+	assert_eq!(err.original(), "broken #1");
+	assert_eq!(
+		err.chain().iter().map(|info| info.id()).collect::<Vec<_>>(),
+		vec![broken.id(), inner.id(), outer.id()]
+	);
+	assert!(err.chain()[0].type_name().ends_with("BuilderBroken"));
+	assert!(err.chain()[2].type_name().ends_with("BuilderContext"));
+
+	let text = err.to_string();
+	assert!(text.starts_with("broken #1\n  in "));
+	assert_eq!(text.lines().count(), 4);
+
+	// The inner Builder reports the part of the chain below itself
+	let err = cache.get(&inner).unwrap_err();
+	assert_eq!(err.chain().len(), 2);
+	assert_eq!(err.into_original(), "broken #2");
+}
+
+/// Passes the error of its dependency on, without any context.
+#[derive(Debug)]
+pub(crate) struct BuilderForward {
+	dep: rc::Blueprint<BuilderBroken>,
+}
+
+impl crate::rc::Builder for BuilderForward {
+	type Artifact = ();
+	type DynState = ();
+	type Err = String;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, String> {
+
+		resolver.resolve(&self.dep)
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_error_context_forwarded() {
+	let mut cache = rc::Cache::new();
+
+	let broken = Blueprint::new(BuilderBroken {
+		dep: None,
+		cache_errors: true,
+		attempts: std::rc::Rc::new(std::cell::Cell::new(0)),
+	});
+	let forward = Blueprint::new(BuilderForward {
+		dep: broken.clone(),
+	});
+	let outer = Blueprint::new(BuilderContext {
+		broken: None,
+		forward: Some(forward.clone()),
+		dep: None,
+	});
+
+	// The build of the failing Builder is recorded, even though its error
+	// passed through a Builder without context
+	let err = cache.get(&outer).unwrap_err();
+	assert_eq!(
+		err.chain().iter().map(|info| info.id()).collect::<Vec<_>>(),
+		vec![broken.id(), forward.id(), outer.id()]
+	);
+
+	// Also for the cached error
+	let err = cache.get(&outer).unwrap_err();
+	assert_eq!(err.chain().len(), 3);
+	assert_eq!(err.into_original(), "broken #1");
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderCancel {
+	deps: Vec<rc::Blueprint<BuilderCancel>>,
+	cancels: Option<crate::cache::CancellationToken>,
+	// Whether to resolve the dependencies via `try_resolve`
+	tries: bool,
+}
+
+impl crate::rc::Builder for BuilderCancel {
+	type Artifact = ();
+	type DynState = ();
+	type Err = String;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, String> {
+
+		for dep in &self.deps {
+			if self.tries {
+				resolver.try_resolve(dep).map_err(|err| {
+					match err {
+						crate::cache::ResolveError::Failed(err) => err,
+						err => format!("dependency: {}", err),
+					}
+				})?;
+			} else {
+				resolver.resolve(dep)?;
+			}
+		}
+
+		if resolver.is_cancelled() {
+			return Err("cancelled".into());
+		}
+
+		// Stands for a cancellation from the outside after this build
+		if let Some(token) = &self.cancels {
+			token.cancel();
+		}
+
+		Ok(std::rc::Rc::new(()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, (), (), String> for BuilderCancel {
+	fn cached_error(&self, err: &String) -> Option<String> {
+		Some(err.clone())
+	}
+}
+
+#[test]
+fn test_cancellation() {
+	let mut cache = rc::Cache::new();
+	let token = crate::cache::CancellationToken::new();
+
+	let leaf = Blueprint::new(BuilderCancel {
+		deps: Vec::new(),
+		cancels: Some(token.clone()),
+		tries: false,
+	});
+	let top = Blueprint::new(BuilderCancel {
+		deps: vec![leaf.clone()],
+		cancels: None,
+		tries: false,
+	});
+
+	assert_eq!(
+		cache.get_cancellable(&top, &token).unwrap_err(),
+		crate::cache::ResolveError::Failed("cancelled".to_string())
+	);
+
+	// The completed dependency is kept, but not the aborted Artifact
+	assert!(cache.lookup(&leaf).is_some());
+	assert!(cache.lookup(&top).is_none());
+	assert!(token.is_cancelled());
+
+	// Cancelled builds are neither cached nor affect other builds
+	assert!(cache.get(&top).is_ok());
+
+	// A cancelled token refuses to build right away
+	cache.invalidate(&top);
+	match cache.get_cancellable(&top, &token) {
+		Err(crate::cache::ResolveError::Cancelled(cancelled)) => {
+			assert_eq!(cancelled.builder().id(), top.id());
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+	assert!(cache.get_cancellable(&top, &crate::cache::CancellationToken::new()).is_ok());
+}
+
+#[test]
+fn test_cancellation_nested() {
+	let mut cache = rc::Cache::new();
+	let token = crate::cache::CancellationToken::new();
+
+	let first = Blueprint::new(BuilderCancel {
+		deps: Vec::new(),
+		cancels: Some(token.clone()),
+		tries: false,
+	});
+	let second = Blueprint::new(BuilderCancel {
+		deps: Vec::new(),
+		cancels: None,
+		tries: false,
+	});
+	let top = Blueprint::new(BuilderCancel {
+		deps: vec![first.clone(), second.clone()],
+		cancels: None,
+		tries: false,
+	});
+
+	// After the first dependency cancelled, the second one is not built
+	match cache.get_cancellable(&top, &token) {
+		Err(crate::cache::ResolveError::Cancelled(cancelled)) => {
+			assert_eq!(cancelled.builder().id(), second.id());
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+
+	assert!(cache.lookup(&first).is_some());
+	assert!(cache.lookup(&second).is_none());
+	assert!(cache.lookup(&top).is_none());
+
+	// Cached artifacts are still available
+	assert!(cache.get_cancellable(&first, &token).is_ok());
+}
+
+#[test]
+fn test_cancellation_try_resolve() {
+	let mut cache = rc::Cache::new();
+	let token = crate::cache::CancellationToken::new();
+
+	let first = Blueprint::new(BuilderCancel {
+		deps: Vec::new(),
+		cancels: Some(token.clone()),
+		tries: false,
+	});
+	let second = Blueprint::new(BuilderCancel {
+		deps: Vec::new(),
+		cancels: None,
+		tries: false,
+	});
+	let top = Blueprint::new(BuilderCancel {
+		deps: vec![first.clone(), second.clone()],
+		cancels: None,
+		tries: true,
+	});
+
+	// The cancellation is returned to the top build, which passes it on
+	assert_eq!(
+		cache.get_cancellable(&top, &token).unwrap_err(),
+		crate::cache::ResolveError::Failed(format!(
+			"dependency: Cancelled before building {}",
+			crate::cache::BuilderInfo::new(&second),
+		))
+	);
+
+	assert!(cache.lookup(&second).is_none());
+	assert!(cache.lookup(&top).is_none());
+
+	// The error of the cancelled build is not cached
+	assert!(cache.get(&top).is_ok());
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderOverdue {
+	deps: Vec<rc::Blueprint<BuilderOverdue>>,
+	clock: crate::cache::ManualClock,
+	cost: std::time::Duration,
+}
+
+impl crate::rc::Builder for BuilderOverdue {
+	// The remaining budget at the start of the build
+	type Artifact = Option<std::time::Duration>;
+	type DynState = ();
+	type Err = crate::cache::TimeoutError;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Self::Err> {
+
+		let budget = resolver.remaining_budget();
+
+		for dep in &self.deps {
+			resolver.try_resolve(dep).map_err(|err| {
+				match err {
+					crate::cache::ResolveError::Failed(timeout) => timeout,
+					crate::cache::ResolveError::Timeout(timeout) => timeout,
+					err => panic!("Unexpected error: {}", err),
+				}
+			})?;
+		}
+
+		self.clock.advance(self.cost);
+
+		Ok(std::rc::Rc::new(budget))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_deadline() {
+	use std::time::Duration;
+
+	let clock = crate::cache::ManualClock::new();
+	let mut cache = rc::Cache::new();
+	cache.set_clock(clock.clone());
+
+	let slow = || Blueprint::new(BuilderOverdue {
+		deps: Vec::new(),
+		clock: clock.clone(),
+		cost: Duration::from_secs(6),
+	});
+	let (first, second, third) = (slow(), slow(), slow());
+	let top = Blueprint::new(BuilderOverdue {
+		deps: vec![first.clone(), second.clone(), third.clone()],
+		clock: clock.clone(),
+		cost: Duration::from_secs(0),
+	});
+
+	// The third build would start after the deadline
+	match cache.get_with_budget(&top, Duration::from_secs(10)) {
+		Err(crate::cache::ResolveError::Failed(timeout)) => {
+			assert_eq!(timeout.builder().id(), third.id());
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+
+	assert_eq!(cache.lookup_cloned(&first), Some(Some(Duration::from_secs(10))));
+	assert_eq!(cache.lookup_cloned(&second), Some(Some(Duration::from_secs(4))));
+	assert!(cache.lookup(&third).is_none());
+	assert!(cache.lookup(&top).is_none());
+
+	// The top-level build is skipped too, if it would start too late
+	match cache.get_with_budget(&top, Duration::from_secs(0)) {
+		Err(crate::cache::ResolveError::Timeout(timeout)) => {
+			assert_eq!(timeout.builder().id(), top.id());
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+
+	// A larger budget completes the remaining builds
+	assert!(cache.get_with_budget(&top, Duration::from_secs(10)).is_ok());
+	assert_eq!(cache.lookup_cloned(&third), Some(Some(Duration::from_secs(10))));
+
+	// Cached artifacts are available regardless of the deadline
+	assert!(cache.get_with_budget(&top, Duration::from_secs(0)).is_ok());
+
+	// Without deadline there is no budget
+	cache.invalidate(&first);
+	assert_eq!(cache.get_cloned(&first).unwrap(), None);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderLate {
+	dep: rc::Blueprint<BuilderLeaf>,
+	clock: crate::cache::ManualClock,
+}
+
+impl crate::rc::Builder for BuilderLate {
+	type Artifact = ();
+	type DynState = ();
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		self.clock.advance(std::time::Duration::from_secs(10));
+		resolver.resolve(&self.dep).unpack();
+
+		Ok(std::rc::Rc::new(()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_deadline_nested() {
+	use std::time::Duration;
+
+	let clock = crate::cache::ManualClock::new();
+	let mut cache = rc::Cache::new();
+	cache.set_clock(clock.clone());
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let late = Blueprint::new(BuilderLate {
+		dep: leaf.clone(),
+		clock: clock.clone(),
+	});
+
+	// Even a plain resolve does not build after the deadline
+	match cache.get_with_budget(&late, Duration::from_secs(5)) {
+		Err(crate::cache::ResolveError::Timeout(timeout)) => {
+			assert_eq!(timeout.builder().id(), leaf.id());
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+
+	assert!(cache.lookup(&leaf).is_none());
+	assert!(cache.lookup(&late).is_none());
+
+	// Cached dependencies are resolved regardless of the deadline
+	cache.get(&leaf).unpack();
+	assert!(cache.get_with_budget(&late, Duration::from_secs(5)).is_ok());
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderFlaky {
+	// Number of attempts which fail before the first success
+	failures: u32,
+	attempts: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl crate::rc::Builder for BuilderFlaky {
+	type Artifact = u32;
+	type DynState = ();
+	type Err = String;
+
+	fn build(&self, _resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, String> {
+
+		self.attempts.set(self.attempts.get() + 1);
+
+		if self.attempts.get() <= self.failures {
+			Err(format!("attempt #{} failed", self.attempts.get()))
+		} else {
+			Ok(std::rc::Rc::new(self.attempts.get()))
+		}
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RecordingBackoff {
+	retries: std::rc::Rc<std::cell::RefCell<Vec<u32>>>,
+}
+
+impl crate::utils::Backoff for RecordingBackoff {
+	fn wait(&self, retry: u32, _budget: Option<std::time::Duration>) -> bool {
+		self.retries.borrow_mut().push(retry);
+
+		true
+	}
+}
+
+#[test]
+fn test_versioned() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let node = Blueprint::new(BuilderSimpleNode::new(leaf.clone()));
+
+	let (bin, version) = cache.get_versioned(&node).unpack();
+	assert!(cache.is_current(&node, version));
+
+	// Getting it again yields the same version
+	let (same_bin, same_version) = cache.get_versioned(&node).unpack();
+	assert!(std::rc::Rc::ptr_eq(&bin, &same_bin));
+	assert_eq!(version, same_version);
+
+	// Invalidating a dependency outdates the version
+	cache.invalidate(&leaf);
+	assert!(!cache.is_current(&node, version));
+
+	let (_, new_version) = cache.get_versioned(&node).unpack();
+	assert_ne!(version, new_version);
+	assert!(!cache.is_current(&node, version));
+	assert!(cache.is_current(&node, new_version));
+
+	// Versions are unique among all Builders
+	let (_, leaf_version) = cache.get_versioned(&leaf).unpack();
+	assert!(!cache.is_current(&node, leaf_version));
+
+	cache.clear_artifacts();
+	assert!(!cache.is_current(&node, new_version));
+}
+
+#[test]
+fn test_listeners() {
+	use crate::cache::DropReason;
+	use std::cell::RefCell;
+
+	let mut cache = rc::Cache::new();
+	let dropped = std::rc::Rc::new(RefCell::new(Vec::new()));
+
+	let leaf = Blueprint::new(BuilderLeaf::new());
+	let node = Blueprint::new(BuilderSimpleNode::new(leaf.clone()));
+
+	let listen = |name: &'static str| {
+		let dropped = dropped.clone();
+		move |reason| dropped.borrow_mut().push((name, reason))
+	};
+	let leaf_listener = cache.subscribe(&leaf, listen("leaf"));
+	cache.subscribe(&node, listen("node"));
+
+	// Nothing to drop yet
+	cache.invalidate(&leaf);
+	assert!(dropped.borrow().is_empty());
+
+	cache.get(&node).unpack();
+	cache.invalidate(&leaf);
+	assert_eq!(*dropped.borrow(), vec![
+		("leaf", DropReason::Invalidated),
+		("node", DropReason::Dependency),
+	]);
+	dropped.borrow_mut().clear();
+
+	// The same with the early cutoff
+	cache.set_early_cutoff(true);
+	cache.get(&node).unpack();
+	cache.invalidate(&leaf);
+	dropped.borrow_mut().sort_by_key(|(name, _)| *name);
+	assert_eq!(*dropped.borrow(), vec![
+		("leaf", DropReason::Invalidated),
+		("node", DropReason::Dependency),
+	]);
+	dropped.borrow_mut().clear();
+	cache.set_early_cutoff(false);
+
+	// Evicting the least recently used
+	cache.get(&node).unpack();
+	cache.set_max_artifacts(Some(1));
+	assert_eq!(*dropped.borrow(), vec![("leaf", DropReason::Evicted)]);
+	dropped.borrow_mut().clear();
+	cache.set_max_artifacts(None);
+
+	cache.clear_artifacts();
+	assert_eq!(*dropped.borrow(), vec![("node", DropReason::Cleared)]);
+	dropped.borrow_mut().clear();
+
+	// An unsubscribed listener is not called anymore
+	assert!(cache.unsubscribe(leaf_listener));
+	assert!(!cache.unsubscribe(leaf_listener));
+
+	cache.get(&node).unpack();
+	cache.purge(&leaf);
+	assert_eq!(*dropped.borrow(), vec![("node", DropReason::Dependency)]);
+	dropped.borrow_mut().clear();
+
+	// Collecting forgets the listener along with the Builder
+	cache.get(&node).unpack();
+	drop(node);
+	cache.garbage_collection();
+	assert_eq!(*dropped.borrow(), vec![("node", DropReason::Collected)]);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderForkable {
+	dep: Option<rc::Blueprint<BuilderForkable>>,
+	forkable: bool,
+}
+
+impl crate::rc::Builder for BuilderForkable {
+	// The sum of the dyn states of this builder and its dependencies
+	type Artifact = u32;
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let dep = match &self.dep {
+			Some(dep) => *resolver.resolve(dep)?,
+			None => 0,
+		};
+
+		Ok(std::rc::Rc::new(*resolver.my_state() + dep))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		1
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, u32, u32, Never> for BuilderForkable {
+	fn fork_dyn_state(&self, dyn_state: &u32) -> Option<u32> {
+		if self.forkable {
+			Some(*dyn_state)
+		} else {
+			None
+		}
+	}
+}
+
+#[test]
+fn test_fork() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderForkable {
+		dep: None,
+		forkable: true,
+	});
+	let node = Blueprint::new(BuilderForkable {
+		dep: Some(leaf.clone()),
+		forkable: true,
+	});
+	let unit = Blueprint::new(BuilderSimpleNode::new(Blueprint::new(BuilderLeaf::new())));
+
+	*cache.dyn_state_mut(&leaf) = 10;
+	let artifact = cache.get(&node).unpack();
+	let unit_artifact = cache.get(&unit).unpack();
+	assert_eq!(*artifact, 11);
+
+	let mut fork = cache.fork();
+
+	// The artifacts are shared, as are the dyn states until they change
+	assert!(std::rc::Rc::ptr_eq(&artifact, &fork.get(&node).unpack()));
+	assert!(std::rc::Rc::ptr_eq(&unit_artifact, &fork.get(&unit).unpack()));
+	assert!(std::ptr::eq(fork.get_dyn_state(&leaf).unwrap(), cache.get_dyn_state(&leaf).unwrap()));
+	assert_eq!(*fork.dyn_state(&leaf), 10);
+
+	// Changes in the fork do not disturb the original
+	*fork.dyn_state_mut(&leaf) = 20;
+	assert_eq!(*fork.get(&node).unpack(), 21);
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(*cache.dyn_state(&leaf), 10);
+
+	// And vice versa
+	*cache.dyn_state_mut(&node) = 5;
+	assert_eq!(*cache.get(&node).unpack(), 15);
+	assert_eq!(*fork.get(&node).unpack(), 21);
+
+	// A dyn state which can not be copied starts over in the fork
+	let unforkable = Blueprint::new(BuilderForkable {
+		dep: Some(leaf.clone()),
+		forkable: false,
+	});
+	*cache.dyn_state_mut(&unforkable) = 100;
+	assert_eq!(*cache.get(&unforkable).unpack(), 110);
+
+	let mut fork = cache.fork();
+	assert!(fork.lookup(&unforkable).is_none());
+	assert_eq!(*fork.get(&unforkable).unpack(), 11);
+	assert_eq!(*cache.get(&unforkable).unpack(), 110);
+
+	// Also if it has never been built
+	let stale = Blueprint::new(BuilderStale {
+		dep: None,
+		source: std::rc::Rc::new(std::cell::Cell::new(0)),
+	});
+	*cache.dyn_state_mut(&stale) = 7;
+
+	let mut fork = cache.fork();
+	assert_eq!(*fork.dyn_state(&stale), 0);
+	assert_eq!(*cache.dyn_state(&stale), 7);
+}
+
+#[test]
+fn test_parent_cache() {
+	let mut parent = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderForkable {
+		dep: None,
+		forkable: true,
+	});
+	let node = Blueprint::new(BuilderForkable {
+		dep: Some(leaf.clone()),
+		forkable: true,
+	});
+
+	*parent.dyn_state_mut(&leaf) = 10;
+	let artifact = parent.get(&node).unpack();
+	assert_eq!(*artifact, 11);
+
+	let parent = std::rc::Rc::new(parent);
+	let mut child = rc::Cache::with_parent(parent.clone());
+	let mut sibling = rc::Cache::with_parent(parent.clone());
+
+	// The artifacts of the parent are used, without building anything
+	assert!(std::rc::Rc::ptr_eq(&artifact, &child.get(&node).unpack()));
+	assert!(!child.is_builder_known(&leaf));
+
+	// Invalidation in the child also drops the artifacts of the dependents
+	// taken from the parent
+	*child.dyn_state_mut(&leaf) = 20;
+	assert_eq!(*child.get(&node).unpack(), 21);
+	assert!(child.is_builder_known(&leaf));
+
+	// The parent and other children are not touched
+	assert!(std::rc::Rc::ptr_eq(&artifact, &parent.lookup(&node).unwrap()));
+	assert_eq!(parent.get_dyn_state(&leaf), Some(&10));
+	assert!(std::rc::Rc::ptr_eq(&artifact, &sibling.get(&node).unpack()));
+
+	// New artifacts are only kept in the child
+	let other = Blueprint::new(BuilderForkable {
+		dep: Some(node.clone()),
+		forkable: true,
+	});
+	assert_eq!(*child.get(&other).unpack(), 22);
+	assert!(parent.lookup(&other).is_none());
+	assert_eq!(*sibling.get(&other).unpack(), 12);
+}
+
+#[test]
+fn test_parent_cache_expiry() {
+	use crate::cache::DropReason;
+	use std::cell::RefCell;
+	use std::time::Duration;
+
+	let clock = crate::cache::ManualClock::new();
+	let mut parent = rc::Cache::new();
+	parent.set_clock(clock.clone());
+
+	let snapshot = Blueprint::new(BuilderExpiring::new(None, Some(Duration::from_secs(10))));
+	let node = Blueprint::new(BuilderExpiring::new(Some(snapshot.clone()), None));
+
+	let artifact = parent.get(&node).unpack();
+
+	let parent = std::rc::Rc::new(parent);
+	let mut child = rc::Cache::with_parent(parent.clone());
+	let dropped = std::rc::Rc::new(RefCell::new(Vec::new()));
+	{
+		let dropped = dropped.clone();
+		child.subscribe(&node, move |reason| dropped.borrow_mut().push(reason));
+	}
+
+	assert!(std::rc::Rc::ptr_eq(&artifact, &child.get(&node).unpack()));
+	assert!(!child.is_builder_known(&snapshot));
+
+	// The artifacts taken from the parent expire along with the parent's
+	clock.advance(Duration::from_secs(10));
+	assert!(child.lookup(&node).is_none());
+
+	// Then, the child builds them itself
+	assert_eq!(*child.get(&node).unpack(), (1, Some(1)));
+	assert!(child.is_builder_known(&snapshot));
+	assert_eq!(*dropped.borrow(), vec![DropReason::Outdated]);
+}
+
+#[test]
+fn test_parent_sync_cache() {
+	let parent = std::sync::Arc::new(arc::SyncCache::new());
+
+	let leaf = Blueprint::new(BuilderSlow::new(None));
+	let node = Blueprint::new(BuilderSlow::new(Some(leaf.clone())));
+
+	let artifact = parent.get(&node).unpack();
+
+	// Caches of other threads can be layered upon the same parent
+	let thread = {
+		let parent = parent.clone();
+		let node = node.clone();
+		let artifact = artifact.clone();
+
+		std::thread::spawn(move || {
+			let mut child = arc::Cache::with_parent(parent);
+			assert!(std::sync::Arc::ptr_eq(&artifact, &child.get(&node).unpack()));
+		})
+	};
+	thread.join().unwrap();
+
+	let mut child = arc::Cache::with_parent(parent.clone());
+	assert!(std::sync::Arc::ptr_eq(&artifact, &child.get(&node).unpack()));
+	assert!(!child.is_builder_known(&leaf));
+
+	// Invalidation in the parent drops the artifacts taken from it
+	parent.invalidate(&leaf);
+	assert!(child.lookup(&node).is_none());
+
+	// Artifacts rebuilt by the parent are taken anew
+	let rebuilt = parent.get(&node).unpack();
+	assert_eq!(*rebuilt, (0, 1));
+	assert!(std::sync::Arc::ptr_eq(&rebuilt, &child.get(&node).unpack()));
+	assert!(!child.is_builder_known(&leaf));
+}
+
+#[test]
+fn test_update_dyn_state() {
+	let mut cache = rc::Cache::new();
+
+	let leaf = Blueprint::new(BuilderForkable {
+		dep: None,
+		forkable: true,
+	});
+	let node = Blueprint::new(BuilderForkable {
+		dep: Some(leaf.clone()),
+		forkable: true,
+	});
+
+	let (artifact, version) = cache.get_versioned(&node).unpack();
+	assert_eq!(*artifact, 2);
+
+	// Unchanged dyn states keep the artifacts
+	assert!(!cache.update_dyn_state(&leaf, |_| false));
+	assert!(!cache.set_dyn_state(&leaf, 1));
+	assert!(cache.is_current(&node, version));
+	assert!(std::rc::Rc::ptr_eq(&artifact, &cache.get(&node).unpack()));
+
+	// Changed ones invalidate them
+	assert!(cache.set_dyn_state(&leaf, 5));
+	assert!(!cache.is_current(&node, version));
+	assert_eq!(*cache.get(&node).unpack(), 6);
+
+	assert!(cache.update_dyn_state(&node, |state| {
+		*state += 1;
+		true
+	}));
+	assert_eq!(*cache.get(&node).unpack(), 7);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderStateReader {
+	config: rc::Blueprint<BuilderCutoff>,
+	resolve: bool,
+}
+
+impl crate::rc::Builder for BuilderStateReader {
+	// The artifact of the config times hundred, if resolved, plus its input
+	type Artifact = u32;
+	type DynState = ();
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let artifact = if self.resolve {
+			*resolver.resolve(&self.config).unpack()
+		} else {
+			0
+		};
+
+		Ok(std::rc::Rc::new(artifact * 100 + *resolver.dyn_state_of(&self.config)))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_dyn_state_of() {
+	let mut cache = rc::Cache::new();
+
+	let config = Blueprint::new(BuilderCutoff::new(None, true));
+	let reader = Blueprint::new(BuilderStateReader {
+		config: config.clone(),
+		resolve: false,
+	});
+
+	// Reading the dyn state does not build the config
+	let artifact = cache.get(&reader).unpack();
+	assert_eq!(*artifact, 4);
+	assert!(cache.lookup(&config).is_none());
+	assert_eq!(cache.dependencies_of(&reader), vec![crate::cache::BuilderInfo::new(&config)]);
+
+	// Changing the dyn state invalidates the reader
+	assert!(!cache.set_dyn_state(&config, 4));
+	assert!(std::rc::Rc::ptr_eq(&artifact, &cache.get(&reader).unpack()));
+	*cache.dyn_state_mut(&config) = 6;
+	assert_eq!(*cache.get(&reader).unpack(), 6);
+
+	// Also with the early cutoff, if the artifact of the config is unchanged
+	let mut cache = rc::Cache::new();
+	cache.set_early_cutoff(true);
+
+	let reader = Blueprint::new(BuilderStateReader {
+		config: config.clone(),
+		resolve: true,
+	});
+
+	assert_eq!(*cache.get(&reader).unpack(), 204);
+	*cache.dyn_state_mut(&config) = 5;
+	assert_eq!(*cache.get(&reader).unpack(), 205);
+	assert_eq!(*cache.get(&config).unpack(), 2);
+}
+
+#[derive(Debug, Hash, PartialEq, Eq)]
+pub(crate) struct BuilderPath {
+	path: &'static str,
+}
+
+impl crate::rc::Builder for BuilderPath {
+	type Artifact = String;
+	type DynState = ();
+	type Err = Never;
+
+	fn build(&self, _resolver: &mut rc::Resolver) -> Result<std::rc::Rc<String>, Never> {
+		Ok(std::rc::Rc::new(self.path.to_uppercase()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_interned() {
+	let mut cache = rc::Cache::new();
+
+	let a = rc::Blueprint::interned(BuilderPath { path: "a" });
+	let b = rc::Blueprint::interned(BuilderPath { path: "b" });
+	let a2 = rc::Blueprint::interned(BuilderPath { path: "a" });
+
+	// Equal builders share one identity and artifact
+	assert_eq!(a.id(), a2.id());
+	assert_ne!(a.id(), b.id());
+	assert!(std::rc::Rc::ptr_eq(&cache.get(&a).unpack(), &cache.get(&a2).unpack()));
+	assert_eq!(*cache.get(&b).unpack(), "B");
+	assert_eq!(cache.number_of_known_builders(), 2);
+
+	// Other than new ones
+	let a3 = rc::Blueprint::new(BuilderPath { path: "a" });
+	assert_ne!(a.id(), a3.id());
+
+	// Dropped builders are released
+	let weak = std::rc::Rc::downgrade(&crate::Promise::canned(&a).can);
+	drop(a);
+	drop(a2);
+	cache.garbage_collection();
+	assert_eq!(weak.strong_count(), 0);
+	assert_eq!(cache.number_of_known_builders(), 1);
+}
+
+#[derive(Debug, Hash, PartialEq, Eq)]
+pub(crate) struct BuilderSyncPath {
+	path: &'static str,
+}
+
+impl crate::arc::Builder for BuilderSyncPath {
+	type Artifact = String;
+	type DynState = ();
+	type Err = Never;
+
+	fn build(&self, _resolver: &mut arc::Resolver) -> Result<std::sync::Arc<String>, Never> {
+		Ok(std::sync::Arc::new(self.path.to_uppercase()))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_interned_sync() {
+	let cache = std::sync::Arc::new(arc::SyncCache::new());
+
+	let a = arc::Blueprint::interned_sync(BuilderSyncPath { path: "a" });
+
+	// Equal builders of other threads share one identity and artifact
+	let thread = {
+		let cache = cache.clone();
+
+		std::thread::spawn(move || {
+			let a = arc::Blueprint::interned_sync(BuilderSyncPath { path: "a" });
+			(a.id(), cache.get(&a).unpack())
+		})
+	};
+	let (id, artifact) = thread.join().unwrap();
+
+	assert_eq!(a.id(), id);
+	assert!(std::sync::Arc::ptr_eq(&cache.get(&a).unpack(), &artifact));
+	assert_eq!(cache.number_of_known_builders(), 1);
+
+	// Unlike those interned per thread
+	let thread = std::thread::spawn(|| {
+		arc::Blueprint::interned(BuilderSyncPath { path: "a" }).id()
+	});
+	assert_ne!(a.id(), thread.join().unwrap());
+}
+
+#[test]
+fn test_retrying_builder() {
+	use crate::utils::RetryingBuilder;
+
+	let mut cache = rc::Cache::new();
+
+	let flaky = Blueprint::new(BuilderFlaky {
+		failures: 2,
+		attempts: Default::default(),
+	});
+	let backoff = RecordingBackoff::default();
+	let retrying = Blueprint::new(
+		RetryingBuilder::with_backoff(flaky.clone(), 3, backoff.clone())
+	);
+
+	assert_eq!(*cache.get(&retrying).unwrap(), 3);
+	assert_eq!(cache.dyn_state(&retrying).last(), 3);
+	assert_eq!(cache.dyn_state(&retrying).failures(), 2);
+	assert_eq!(*backoff.retries.borrow(), vec![1, 2]);
+
+	// Gives up after the last retry
+	let broken = Blueprint::new(BuilderFlaky {
+		failures: 3,
+		attempts: Default::default(),
+	});
+	let retrying = Blueprint::new(RetryingBuilder::new(broken.clone(), 1));
+
+	assert_eq!(cache.get(&retrying).unwrap_err(), "attempt #2 failed");
+	assert_eq!(cache.dyn_state(&retrying).last(), 2);
+
+	// The attempts accumulate over builds
+	assert_eq!(*cache.get(&retrying).unwrap(), 4);
+	assert_eq!(cache.dyn_state(&retrying).last(), 2);
+	assert_eq!(cache.dyn_state(&retrying).failures(), 3);
+}
+
+#[test]
+fn test_exponential_backoff() {
+	use crate::utils::ExponentialBackoff;
+	use std::time::Duration;
+
+	let backoff = ExponentialBackoff::new(
+		Duration::from_millis(100),
+		Duration::from_secs(1),
+	);
+
+	assert_eq!(backoff.delay(1), Duration::from_millis(100));
+	assert_eq!(backoff.delay(2), Duration::from_millis(200));
+	assert_eq!(backoff.delay(4), Duration::from_millis(800));
+	assert_eq!(backoff.delay(5), Duration::from_secs(1));
+	assert_eq!(backoff.delay(100), Duration::from_secs(1));
+}
+
+#[test]
+fn test_retrying_builder_budget() {
+	use crate::utils::ExponentialBackoff;
+	use crate::utils::RetryingBuilder;
+	use std::time::Duration;
+
+	let mut cache = rc::Cache::new();
+
+	let flaky = Blueprint::new(BuilderFlaky {
+		failures: 1,
+		attempts: Default::default(),
+	});
+	let retrying = Blueprint::new(RetryingBuilder::with_backoff(
+		flaky.clone(),
+		3,
+		ExponentialBackoff::new(Duration::from_secs(60), Duration::from_secs(60)),
+	));
+
+	// Waiting for the retry would exceed the budget, thus it gives up
+	match cache.get_with_budget(&retrying, Duration::from_secs(30)) {
+		Err(crate::cache::ResolveError::Failed(err)) => {
+			assert_eq!(err, "attempt #1 failed");
+		}
+		res => panic!("Unexpected result: {:?}", res),
+	}
+	assert_eq!(cache.dyn_state(&retrying).last(), 1);
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderStale {
+	dep: Option<rc::Blueprint<BuilderStale>>,
+	source: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl crate::rc::Builder for BuilderStale {
+	// The source plus the artifact of the dependency
+	type Artifact = u32;
+	// The source as used by the last build
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let dep = self.dep.as_ref().map(|dep| *resolver.resolve(dep).unpack()).unwrap_or(0);
+
+		*resolver.my_state() = self.source.get();
+
+		Ok(std::rc::Rc::new(self.source.get() + dep))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl crate::BuildPolicy<rc::CanType, u32, u32, Never> for BuilderStale {
+	fn is_stale(&self, dyn_state: &u32) -> bool {
+		*dyn_state != self.source.get()
+	}
+}
+
+#[test]
+fn test_validate() {
+	let mut cache = rc::Cache::new();
+
+	let env = std::rc::Rc::new(std::cell::Cell::new(1));
+	let leaf = Blueprint::new(BuilderStale {
+		dep: None,
+		source: env.clone(),
+	});
+	let node = Blueprint::new(BuilderStale {
+		dep: Some(leaf.clone()),
+		source: std::rc::Rc::new(std::cell::Cell::new(10)),
+	});
+
+	assert_eq!(*cache.get(&node).unpack(), 11);
+
+	// Nothing is stale
+	cache.validate();
+	assert_eq!(cache.number_of_artifacts(), 2);
+
+	// The stale leaf is invalidated with its dependents
+	env.set(2);
+	assert_eq!(cache.lookup(&node).map(|art| *art), Some(11));
+	cache.validate();
+	assert_eq!(cache.number_of_artifacts(), 0);
+	assert_eq!(*cache.get(&node).unpack(), 12);
+}
+
+#[cfg(feature = "persistence")]
+#[derive(Debug)]
+pub(crate) struct BuilderStored {
+	key: &'static str,
+	version: u32,
+	input: u32,
+	dep: Option<rc::Blueprint<BuilderStored>>,
+	builds: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+#[cfg(feature = "persistence")]
+impl crate::rc::Builder for BuilderStored {
+	// The input plus the artifact of the dependency
+	type Artifact = u32;
+	// The number of builds
+	type DynState = u32;
+	type Err = Never;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Never> {
+
+		let dep = self.dep.as_ref().map(|dep| *resolver.resolve(dep).unpack()).unwrap_or(0);
+
+		*resolver.my_state() += 1;
+		self.builds.set(self.builds.get() + 1);
+
+		Ok(std::rc::Rc::new(self.input + dep))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		0
+	}
+	fn policy(&self) -> &dyn crate::BuildPolicy<rc::CanType, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+#[cfg(feature = "persistence")]
+impl crate::BuildPolicy<rc::CanType, u32, u32, Never> for BuilderStored {
+	fn persistence(&self) -> Option<rc::Persistence<u32, u32>> {
+		Some(crate::persistence::Persistence::new(self.key, self.version))
+	}
+}
+
+/// Creates a leaf and a node `BuilderStored`, sharing one build counter.
+#[cfg(feature = "persistence")]
+fn stored_pair(leaf_version: u32, leaf_input: u32)
+		-> (rc::Blueprint<BuilderStored>, rc::Blueprint<BuilderStored>, std::rc::Rc<std::cell::Cell<u32>>) {
+
+	let builds = std::rc::Rc::new(std::cell::Cell::new(0));
+
+	let leaf = Blueprint::new(BuilderStored {
+		key: "leaf",
+		version: leaf_version,
+		input: leaf_input,
+		dep: None,
+		builds: builds.clone(),
+	});
+	let node = Blueprint::new(BuilderStored {
+		key: "node",
+		version: 1,
+		input: 10,
+		dep: Some(leaf.clone()),
+		builds: builds.clone(),
+	});
+
+	(leaf, node, builds)
+}
+
+/// Returns a fresh directory for storing artifacts.
+#[cfg(feature = "persistence")]
+fn store_dir(name: &str) -> std::path::PathBuf {
+	let dir = std::env::temp_dir()
+		.join(format!("daab-test-{}-{}", std::process::id(), name));
+	let _ = std::fs::remove_dir_all(&dir);
+
+	dir
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn test_persistence() {
+	let dir = store_dir("persistence");
+
+	{
+		let (_leaf, node, builds) = stored_pair(1, 1);
+		let mut cache = rc::Cache::new();
+		cache.set_store(&dir).unwrap();
+
+		assert_eq!(*cache.get(&node).unpack(), 11);
+		assert_eq!(builds.get(), 2);
+	}
+
+	// A later run restores the artifacts and dyn states
+	let (leaf, node, builds) = stored_pair(1, 1);
+	let mut cache = rc::Cache::new();
+	cache.set_store(&dir).unwrap();
+
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(*cache.get(&leaf).unpack(), 1);
+	assert_eq!(builds.get(), 0);
+	assert_eq!(*cache.dyn_state(&node), 1);
+
+	// Evicted artifacts are restored again
+	cache.set_max_artifacts(Some(1));
+	assert!(cache.lookup(&node).is_none());
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(builds.get(), 0);
+	cache.set_max_artifacts(None);
+
+	// Invalidated artifacts are built again
+	cache.invalidate(&leaf);
+	assert!(cache.lookup(&node).is_none());
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(builds.get(), 2);
+	assert_eq!(*cache.dyn_state(&node), 2);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn test_persistence_changed_dependency() {
+	let dir = store_dir("changed");
+
+	{
+		let (_leaf, node, _builds) = stored_pair(1, 1);
+		let mut cache = rc::Cache::new();
+		cache.set_store(&dir).unwrap();
+
+		assert_eq!(*cache.get(&node).unpack(), 11);
+	}
+
+	// The new version of the leaf produces a different artifact
+	let (leaf, node, builds) = stored_pair(2, 2);
+	let mut cache = rc::Cache::new();
+	cache.set_store(&dir).unwrap();
+
+	assert_eq!(*cache.get(&leaf).unpack(), 2);
+	assert_eq!(*cache.get(&node).unpack(), 12);
+	assert_eq!(builds.get(), 2);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn test_persistence_changed_dyn_state() {
+	let dir = store_dir("changed-dyn-state");
+
+	{
+		let (_leaf, node, _builds) = stored_pair(1, 1);
+		let mut cache = rc::Cache::new();
+		cache.set_store(&dir).unwrap();
+
+		assert_eq!(*cache.get(&node).unpack(), 11);
+	}
+
+	let (leaf, node, builds) = stored_pair(1, 1);
+	let mut cache = rc::Cache::new();
+	cache.set_store(&dir).unwrap();
+
+	// The leaf was stored with another dyn state, thus it is built again
+	cache.set_dyn_state(&leaf, 5);
+	assert_eq!(*cache.get(&leaf).unpack(), 1);
+	assert_eq!(builds.get(), 1);
+	assert_eq!(*cache.dyn_state(&leaf), 6);
+
+	// The node still got the same leaf artifact
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(builds.get(), 1);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn test_persistence_outdated() {
+	let dir = store_dir("outdated");
+
+	{
+		let (_leaf, node, _builds) = stored_pair(1, 1);
+		let mut cache = rc::Cache::new();
+		cache.set_store(&dir).unwrap();
+
+		assert_eq!(*cache.get(&node).unpack(), 11);
+	}
+
+	let (leaf, node, builds) = stored_pair(2, 2);
+	let mut cache = rc::Cache::new();
+	cache.set_store(&dir).unwrap();
+
+	// The node is restored, because the leaf is unknown yet
+	assert_eq!(*cache.get(&node).unpack(), 11);
+	assert_eq!(builds.get(), 0);
+
+	// Once the leaf turns out different, the node expires
+	assert_eq!(*cache.get(&leaf).unpack(), 2);
+	assert!(cache.lookup(&node).is_none());
+	assert_eq!(*cache.get(&node).unpack(), 12);
+	assert_eq!(builds.get(), 2);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn test_persistence_duplicate_key() {
+	let dir = store_dir("duplicate");
+
+	let builds = std::rc::Rc::new(std::cell::Cell::new(0));
+	let stored = |input| Blueprint::new(BuilderStored {
+		key: "duplicate",
+		version: 1,
+		input,
+		dep: None,
+		builds: builds.clone(),
+	});
+
+	{
+		let (first, second) = (stored(1), stored(2));
+		let mut cache = rc::Cache::new();
+		cache.set_store(&dir).unwrap();
+
+		assert_eq!(*cache.get(&first).unpack(), 1);
+
+		// The key is taken, thus the artifact of the first one is not restored
+		assert_eq!(*cache.get(&second).unpack(), 2);
+		assert_eq!(builds.get(), 2);
+	}
+
+	// Neither has the second one replaced the stored artifact
+	let first = stored(1);
+	let mut cache = rc::Cache::new();
+	cache.set_store(&dir).unwrap();
+
+	assert_eq!(*cache.get(&first).unpack(), 1);
+	assert_eq!(builds.get(), 2);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[derive(Debug)]
+pub(crate) struct BuilderFileLength {
+	file: rc::Blueprint<rc::FileInputBuilder>,
+	builds: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl crate::rc::Builder for BuilderFileLength {
+	type Artifact = usize;
+	type DynState = ();
+	type Err = std::io::Error;
+
+	fn build(&self, resolver: &mut rc::Resolver<Self::DynState>)
+			-> Result<std::rc::Rc<Self::Artifact>, Self::Err> {
+
+		let len = resolver.resolve(&self.file)?.contents().len();
+
+		self.builds.set(self.builds.get() + 1);
+
+		Ok(std::rc::Rc::new(len))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}
+
+#[test]
+fn test_refresh_inputs() {
+	let path = std::env::temp_dir()
+		.join(format!("daab-test-{}-input.txt", std::process::id()));
+	std::fs::write(&path, "foo").unwrap();
+
+	let mut cache = rc::Cache::new();
+
+	let file = Blueprint::new(rc::FileInputBuilder::new(&path));
+	let other = Blueprint::new(rc::FileInputBuilder::new(&path));
+	let builds = std::rc::Rc::new(std::cell::Cell::new(0));
+	let length = Blueprint::new(BuilderFileLength {
+		file: file.clone(),
+		builds: builds.clone(),
+	});
+
+	assert_eq!(*cache.get(&length).unwrap(), 3);
+	assert_eq!(cache.get(&file).unwrap().path(), path.as_path());
+
+	// Unchanged files keep their artifacts
+	cache.refresh_inputs();
+	assert!(cache.lookup(&length).is_some());
+
+	// Sets a distinct modification time, regardless of the file system
+	let touch = |secs| {
+		std::fs::File::options().write(true).open(&path).unwrap()
+			.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+			.unwrap();
+	};
+
+	// Changed files invalidate their dependents
+	std::fs::write(&path, "fooo").unwrap();
+	touch(1);
+	cache.refresh_inputs();
+	assert!(cache.lookup(&length).is_none());
+	assert_eq!(*cache.get(&length).unwrap(), 4);
+	assert_eq!(builds.get(), 2);
+
+	// Touched files with unchanged contents keep their artifacts
+	touch(2);
+	cache.refresh_inputs();
+	assert!(cache.lookup(&length).is_some());
+
+	// Deleted files fail to build
+	assert_eq!(cache.get(&other).unwrap().contents(), b"fooo");
+	std::fs::remove_file(&path).unwrap();
+	cache.refresh_inputs();
+	assert!(cache.get(&other).is_err());
+}
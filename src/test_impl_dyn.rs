@@ -45,17 +45,20 @@ impl SimpleBuilder for BuilderLeaf {
 }
 
 
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq)]
 struct SimpleNode {
 	id: u32,
 	leaf: BinType<Leaf>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct BuilderSimpleNode {
 	leaf: Blueprint<BuilderLeaf>,
 }
 
+#[allow(dead_code)]
 impl BuilderSimpleNode {
 	pub(crate) fn new(leaf: Blueprint<BuilderLeaf>) -> Self {
 		Self {
@@ -125,6 +128,7 @@ struct ComplexNode {
 	inner: LeafOrNodes,
 }
 
+#[allow(dead_code)]
 impl ComplexNode {
 	pub(crate) fn leaf(&self) -> Option<&BinType<Leaf>> {
 		if let LeafOrNodes::Leaf(ref l) = self.inner {
@@ -237,8 +241,8 @@ fn test_dyn_builder_complex() {
 	let noden2 = Blueprint::new(BuilderComplexNode::new_nodes(nodef3.clone(), noden1.clone()));
 	let noden3 = Blueprint::new(BuilderComplexNode::new_nodes(noden2.clone(), noden2.clone()));
 
-	let artifact_node = cache.get(&noden1);
-	let artifact_root = cache.get(&noden3);
+	let _artifact_node = cache.get(&noden1);
+	let _artifact_root = cache.get(&noden3);
 
 	let mut unsized_vec = Vec::new();
 
@@ -259,7 +263,7 @@ fn test_dyn_builder_complex() {
 		cache.get(ap)
 	).collect();
 
-	unsized_vec.iter().zip(artifact_vec.into_iter()).for_each( |(ap,art)| {
+	unsized_vec.iter().zip(artifact_vec).for_each( |(ap,art)| {
 		assert_eq!(
 			cache.get(ap),
 			art
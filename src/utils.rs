@@ -8,15 +8,22 @@ use crate::Resolver;
 use crate::Promise;
 use crate::Blueprint;
 use crate::Builder;
+use crate::BuildPolicy;
 use crate::CanRef;
 use crate::CanStrong;
 use crate::CanSized;
 use crate::Can;
 use crate::Never;
+use crate::cache::FileStamp;
 
+use std::any::Any;
 use std::fmt;
 use std::fmt::Debug;
+use std::io;
 use std::marker::PhantomData;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
 
 
 
@@ -41,11 +48,19 @@ use std::marker::PhantomData;
 /// itself needs to be invalidated. This should otherwise not be an issue, because if the inner
 /// builder still fails, the `RedeemingBuilder` will again retun the _old artifact_.
 ///
+/// Notice, that a [`SyncCache`] only keeps the _old artifact_ across builds,
+/// if the dynamic state of the `RedeemingBuilder` has been set through the
+/// `SyncCache`, because the generic Bin of the artifact might not be `Send`
+/// (see [`BuildPolicy::send_dyn_state`]).
+///
 /// # Panics
 ///
 /// This builder panics in its `build` method if the first build of its inner
 /// builder failed and the `default_value` has been set to `None`.
 ///
+/// [`SyncCache`]: ../cache/struct.SyncCache.html
+/// [`BuildPolicy::send_dyn_state`]: ../trait.BuildPolicy.html#method.send_dyn_state
+///
 #[derive(Debug, Clone)]
 pub struct RedeemingBuilder<AP, ArtBin> {
 	inner: AP,
@@ -122,6 +137,250 @@ impl<ArtCan, AP, B: ?Sized, BCan, ArtBin, T> Builder<ArtCan, BCan> for Redeeming
 
 
 
+/// A strategy for waiting between the attempts of a [`RetryingBuilder`].
+///
+/// [`RetryingBuilder`]: struct.RetryingBuilder.html
+///
+pub trait Backoff: Debug {
+	/// Waits before the given retry, counting from `1` for the first retry.
+	///
+	/// The `budget` is the time left until the deadline of the build, if
+	/// any, see [`Resolver::remaining_budget`]. Returns whether to retry,
+	/// i.e. `false` without waiting, if the retry would start after the
+	/// deadline anyway.
+	///
+	/// [`Resolver::remaining_budget`]: ../cache/struct.Resolver.html#method.remaining_budget
+	///
+	fn wait(&self, retry: u32, budget: Option<Duration>) -> bool;
+}
+
+/// Retries right away, without waiting.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoBackoff;
+
+impl Backoff for NoBackoff {
+	fn wait(&self, _retry: u32, _budget: Option<Duration>) -> bool {
+		true
+	}
+}
+
+/// Waits exponentially longer before each retry, up to a maximum.
+///
+/// The first retry waits the `initial` duration, and each further retry
+/// waits twice as long as the previous one, but never longer than `max`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExponentialBackoff {
+	initial: Duration,
+	max: Duration,
+}
+
+impl ExponentialBackoff {
+	/// Creates a new exponential backoff.
+	///
+	pub fn new(initial: Duration, max: Duration) -> Self {
+		ExponentialBackoff {
+			initial,
+			max,
+		}
+	}
+
+	/// Returns the duration to wait before the given retry, counting from
+	/// `1` for the first retry.
+	///
+	pub fn delay(&self, retry: u32) -> Duration {
+		let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+
+		self.initial.checked_mul(factor)
+			.map(|delay| delay.min(self.max))
+			.unwrap_or(self.max)
+	}
+}
+
+impl Backoff for ExponentialBackoff {
+	fn wait(&self, retry: u32, budget: Option<Duration>) -> bool {
+		let delay = self.delay(retry);
+
+		if budget.is_some_and(|budget| delay >= budget) {
+			return false;
+		}
+
+		std::thread::sleep(delay);
+
+		true
+	}
+}
+
+/// The attempts of a [`RetryingBuilder`], kept as its dynamic state.
+///
+/// [`RetryingBuilder`]: struct.RetryingBuilder.html
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryAttempts {
+	last: u32,
+	failures: u64,
+}
+
+impl RetryAttempts {
+	/// Returns the number of attempts of the latest build, including the
+	/// first one.
+	///
+	pub fn last(&self) -> u32 {
+		self.last
+	}
+
+	/// Returns the number of failed attempts of all builds so far.
+	///
+	pub fn failures(&self) -> u64 {
+		self.failures
+	}
+}
+
+/// A intermediate Builder which retries its failing inner builder.
+///
+/// If the inner builder fails, it is built again up to `retries` more times,
+/// waiting according to the given [`Backoff`] before each retry. The
+/// Artifact of the first successful attempt is forwarded, or the error of
+/// the last attempt if all of them failed. The attempts are recorded as
+/// [`RetryAttempts`] in the dynamic state of this builder.
+///
+/// Retrying stops early, if the build has been cancelled or its deadline
+/// would pass while waiting for the next attempt. Notice, that an inner builder which caches its errors (see
+/// [`BuildPolicy::cached_error`]) will not be built again, thus retrying it is
+/// useless.
+///
+/// [`Backoff`]: trait.Backoff.html
+/// [`RetryAttempts`]: struct.RetryAttempts.html
+/// [`BuildPolicy::cached_error`]: ../trait.BuildPolicy.html#method.cached_error
+///
+#[derive(Debug, Clone)]
+pub struct RetryingBuilder<AP, Bo = NoBackoff> {
+	inner: AP,
+	retries: u32,
+	backoff: Bo,
+}
+
+impl<AP> RetryingBuilder<AP, NoBackoff> {
+
+	/// Wrap given Builder retrying it up to `retries` times right away.
+	///
+	pub fn new<ArtCan, BCan, B: ?Sized>(
+		inner: AP,
+		retries: u32,
+	) -> Self
+		where
+			B: Builder<ArtCan, BCan>,
+			BCan: Can<AP::Builder>,
+			AP: Promise<Builder = B, BCan = BCan>,
+			ArtCan: CanSized<B::Artifact>,
+			ArtCan: Clone,
+			BCan: CanStrong,
+			BCan: CanSized<Self>,
+	{
+
+		Self::with_backoff(inner, retries, NoBackoff)
+	}
+}
+
+impl<AP, Bo: Backoff + 'static> RetryingBuilder<AP, Bo> {
+
+	/// Wrap given Builder retrying it up to `retries` times with the given
+	/// backoff.
+	///
+	pub fn with_backoff<ArtCan, BCan, B: ?Sized>(
+		inner: AP,
+		retries: u32,
+		backoff: Bo,
+	) -> Self
+		where
+			B: Builder<ArtCan, BCan>,
+			BCan: Can<AP::Builder>,
+			AP: Promise<Builder = B, BCan = BCan>,
+			ArtCan: CanSized<B::Artifact>,
+			ArtCan: Clone,
+			BCan: CanStrong,
+			BCan: CanSized<Self>,
+	{
+
+		RetryingBuilder {
+			inner,
+			retries,
+			backoff,
+		}
+	}
+}
+
+impl<ArtCan, AP, B: ?Sized, BCan, Bo> Builder<ArtCan, BCan> for RetryingBuilder<AP, Bo>
+	where
+		B: Builder<ArtCan, BCan>,
+		BCan: Can<B>,
+		AP: Promise<Builder = B, BCan = BCan>,
+		ArtCan: CanSized<B::Artifact>,
+		ArtCan: Clone,
+		BCan: CanStrong,
+		Bo: Backoff + 'static,
+	{
+
+	type Artifact = B::Artifact;
+	type DynState = RetryAttempts;
+	type Err = B::Err;
+
+	fn build(&self, resolver: &mut Resolver<ArtCan, BCan, Self::DynState>)
+			-> Result<ArtCan::Bin, Self::Err> {
+
+		let mut attempt = 1;
+
+		loop {
+			let res = resolver.resolve(&self.inner);
+
+			let attempts = resolver.my_state();
+			attempts.last = attempt;
+
+			match res {
+				Ok(bin) => return Ok(bin),
+				Err(err) => {
+					attempts.failures += 1;
+
+					let interrupted = resolver.is_cancelled()
+						|| resolver.remaining_budget() == Some(Duration::from_secs(0));
+
+					if attempt > self.retries || interrupted {
+						return Err(err);
+					}
+
+					if !self.backoff.wait(attempt, resolver.remaining_budget()) {
+						return Err(err);
+					}
+				}
+			}
+
+			attempt += 1;
+		}
+	}
+
+	fn init_dyn_state(&self) -> Self::DynState {
+		RetryAttempts::default()
+	}
+
+	fn policy(&self) -> &dyn BuildPolicy<ArtCan, Self::Artifact, Self::DynState, Self::Err> {
+		self
+	}
+}
+
+impl<ArtCan, Artifact, Err, AP, Bo> BuildPolicy<ArtCan, Artifact, RetryAttempts, Err>
+		for RetryingBuilder<AP, Bo> {
+
+	fn send_dyn_state(&self, dyn_state: RetryAttempts)
+			-> Result<Box<dyn Any + Send>, RetryAttempts> {
+
+		Ok(Box::new(dyn_state))
+	}
+}
+
+
+
+
 /// Functional leaf builder wrapper.
 ///
 /// A functional builder is a builder consisting of a single function
@@ -708,4 +967,139 @@ impl<ArtCan, AP, B: ?Sized, BCan, Err> Builder<ArtCan, BCan> for FeigningBuilder
 
 
 
+/// The contents of a file, as built by the `FileInputBuilder`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInput {
+	stamp: FileStamp,
+	contents: Vec<u8>,
+}
+
+impl FileInput {
+	/// Reads the given file.
+	///
+	/// The modification time is taken before reading, thus a concurrent
+	/// modification is detected by the stamp later on.
+	///
+	pub fn read(path: impl Into<PathBuf>) -> io::Result<Self> {
+		let (stamp, contents) = FileStamp::read(path.into())?;
+
+		Ok(FileInput {
+			stamp,
+			contents,
+		})
+	}
+
+	/// Returns the [`FileStamp`] of the file, as it was read.
+	///
+	/// [`FileStamp`]: ../cache/struct.FileStamp.html
+	///
+	pub fn stamp(&self) -> &FileStamp {
+		&self.stamp
+	}
+
+	/// Returns the path of the file.
+	///
+	pub fn path(&self) -> &Path {
+		self.stamp.path()
+	}
+
+	/// Returns the contents of the file.
+	///
+	pub fn contents(&self) -> &[u8] {
+		&self.contents
+	}
+}
+
+/// A leaf Builder loading a file.
+///
+/// The Artifact of a `FileInputBuilder` is the [`FileInput`] with the
+/// contents of the file. The file is watched by the `Cache`, thus calling
+/// [`Cache::refresh_inputs`] invalidates the Artifact if the file changed
+/// since, so that it and its dependents are rebuilt on the next `get`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use daab::rc::Cache;
+/// use daab::rc::Blueprint;
+/// use daab::rc::FileInputBuilder;
+/// use daab::prelude::*;
+///
+/// let path = std::env::temp_dir()
+///     .join(format!("daab-doc-{}-file-input.txt", std::process::id()));
+/// std::fs::write(&path, "foo").unwrap();
+///
+/// let blueprint = Blueprint::new(FileInputBuilder::new(&path));
+///
+/// let mut cache = Cache::new();
+///
+/// assert_eq!(cache.get(&blueprint).unwrap().contents(), b"foo");
+///
+/// std::fs::write(&path, "bar").unwrap();
+/// cache.refresh_inputs();
+/// assert_eq!(cache.get(&blueprint).unwrap().contents(), b"bar");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// [`FileInput`]: struct.FileInput.html
+/// [`Cache::refresh_inputs`]: ../cache/struct.Cache.html#method.refresh_inputs
+///
+pub struct FileInputBuilder<ArtCan, BCan> {
+	path: PathBuf,
+	_art_can: PhantomData<ArtCan>,
+	_b_can: PhantomData<BCan>,
+}
+
+impl<ArtCan, BCan> Debug for FileInputBuilder<ArtCan, BCan> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		write!(fmt, "FileInputBuilder{{path: {:?}}}", self.path)
+	}
+}
+
+impl<ArtCan, BCan> FileInputBuilder<ArtCan, BCan>
+	where
+		BCan: CanStrong,
+		ArtCan: Debug + 'static {
+
+	/// Creates a Builder loading the given file.
+	///
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		FileInputBuilder {
+			path: path.into(),
+			_art_can: PhantomData,
+			_b_can: PhantomData,
+		}
+	}
+
+	/// Returns the path of the loaded file.
+	///
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+}
 
+impl<ArtCan, BCan> Builder<ArtCan, BCan> for FileInputBuilder<ArtCan, BCan>
+	where
+		BCan: CanStrong,
+		ArtCan: CanSized<FileInput>,
+		ArtCan: Debug + 'static {
+
+	type Artifact = FileInput;
+	type DynState = ();
+	type Err = io::Error;
+
+	fn build(&self, resolver: &mut Resolver<ArtCan, BCan>)
+			-> Result<ArtCan::Bin, io::Error> {
+
+		let input = FileInput::read(&self.path)?;
+		resolver.watch_file(input.stamp().clone());
+
+		Ok(ArtCan::into_bin(input))
+	}
+	fn init_dyn_state(&self) -> Self::DynState {
+		// empty
+	}
+}